@@ -0,0 +1,192 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Fields};
+
+/// Implements `Schema` and `TryFrom<u32>` for an enum of schema versions.
+/// Each unit variant is one version, numbered from 1 in declaration order,
+/// and names the function which applies it with `#[schema(apply = path)]`.
+/// `#[schema(layout = path)]` optionally names a function returning the `SchemaLayout` of the database
+/// after the version, which `Schema::plan` compares to describe the version's operations.
+/// ```ignore
+/// #[derive(database::Schema)]
+/// enum Version {
+///   #[schema(apply = create_entries, layout = entries_layout)]
+///   V1,
+///   #[schema(apply = index_entries_by_name)]
+///   V2,
+/// }
+///
+/// fn create_entries(database: &Client) -> Result<(), Error> { /* ... */ }
+/// fn entries_layout() -> SchemaLayout { /* ... */ }
+/// ```
+#[proc_macro_derive(Schema, attributes(schema))]
+pub fn derive_schema(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	match schema(input) {
+		Ok(tokens) => tokens.into(),
+		Err(err) => err.to_compile_error().into(),
+	}
+}
+
+fn schema(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+	let Data::Enum(data) = &input.data else {
+		return Err(syn::Error::new(
+			input.span(),
+			"Schema can only be derived for enums of versions",
+		));
+	};
+	if data.variants.is_empty() {
+		return Err(syn::Error::new(input.span(), "Schema requires at least one version"));
+	}
+
+	let mut versions = Vec::with_capacity(data.variants.len());
+	let mut applies = Vec::with_capacity(data.variants.len());
+	let mut layouts = Vec::new();
+	for (idx, variant) in data.variants.iter().enumerate() {
+		if !matches!(variant.fields, Fields::Unit) {
+			return Err(syn::Error::new(variant.span(), "schema versions cannot have fields"));
+		}
+		let mut apply = None;
+		let mut layout = None;
+		for attr in variant.attrs.iter().filter(|attr| attr.path().is_ident("schema")) {
+			attr.parse_nested_meta(|meta| {
+				if meta.path.is_ident("apply") {
+					apply = Some(meta.value()?.parse::<syn::Path>()?);
+					Ok(())
+				} else if meta.path.is_ident("layout") {
+					layout = Some(meta.value()?.parse::<syn::Path>()?);
+					Ok(())
+				} else {
+					Err(meta.error("unsupported schema attribute"))
+				}
+			})?;
+		}
+		let Some(apply) = apply else {
+			return Err(syn::Error::new(variant.span(), "missing `#[schema(apply = ...)]`"));
+		};
+		let version = idx as u32 + 1;
+		let ident = &variant.ident;
+		versions.push(quote! { #version => Ok(Self::#ident) });
+		applies.push(quote! { Self::#ident => #apply(database) });
+		if let Some(layout) = layout {
+			layouts.push(quote! { Self::#ident => Some(#layout()) });
+		}
+	}
+	let latest = data.variants.len() as u32;
+	let layout = match layouts.is_empty() {
+		true => quote! {},
+		false => quote! {
+			fn layout(&self) -> Option<::database::SchemaLayout> {
+				#[allow(unreachable_patterns)]
+				match self {
+					#(#layouts,)*
+					_ => None,
+				}
+			}
+		},
+	};
+
+	let name = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	Ok(quote! {
+		impl #impl_generics ::core::convert::TryFrom<u32> for #name #ty_generics #where_clause {
+			type Error = ::database::MissingVersion;
+
+			fn try_from(version: u32) -> Result<Self, Self::Error> {
+				match version {
+					#(#versions,)*
+					_ => Err(::database::MissingVersion(version)),
+				}
+			}
+		}
+
+		impl #impl_generics ::database::Schema for #name #ty_generics #where_clause {
+			fn latest() -> u32 {
+				#latest
+			}
+
+			fn apply(&self, database: &::database::Client) -> Result<(), ::database::Error> {
+				match self {
+					#(#applies,)*
+				}
+			}
+
+			#layout
+		}
+	})
+}
+
+/// Implements `Record` for a struct stored in the object store named by `#[record(store = "...")]`.
+/// `#[record(key = field)]` names the field which holds the key, for stores without a key path.
+/// ```ignore
+/// #[derive(Serialize, Deserialize, database::Record)]
+/// #[record(store = "users", key = "id")]
+/// struct User {
+///   id: u32,
+///   name: String,
+/// }
+/// ```
+#[proc_macro_derive(Record, attributes(record))]
+pub fn derive_record(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	match record(input) {
+		Ok(tokens) => tokens.into(),
+		Err(err) => err.to_compile_error().into(),
+	}
+}
+
+fn record(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+	let Data::Struct(data) = &input.data else {
+		return Err(syn::Error::new(input.span(), "Record can only be derived for structs"));
+	};
+
+	let mut store = None;
+	let mut key = None;
+	for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("record")) {
+		attr.parse_nested_meta(|meta| {
+			if meta.path.is_ident("store") {
+				store = Some(meta.value()?.parse::<syn::LitStr>()?);
+				Ok(())
+			} else if meta.path.is_ident("key") {
+				key = Some(meta.value()?.parse::<syn::LitStr>()?);
+				Ok(())
+			} else {
+				Err(meta.error("unsupported record attribute"))
+			}
+		})?;
+	}
+	let Some(store) = store else {
+		return Err(syn::Error::new(input.span(), "missing `#[record(store = \"...\")]`"));
+	};
+
+	let key_value = match key {
+		None => quote! {},
+		Some(key) => {
+			let exists = data
+				.fields
+				.iter()
+				.any(|field| field.ident.as_ref().is_some_and(|ident| *ident == key.value()));
+			if !exists {
+				return Err(syn::Error::new(key.span(), "no field with this name"));
+			}
+			let field = syn::Ident::new(&key.value(), key.span());
+			quote! {
+				fn key_value(&self) -> Result<Option<::database::__private::JsValue>, ::database::Error> {
+					Ok(Some(::database::to_key(&self.#field)?))
+				}
+			}
+		}
+	};
+
+	let name = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	Ok(quote! {
+		impl #impl_generics ::database::Record for #name #ty_generics #where_clause {
+			fn store_id() -> &'static str {
+				#store
+			}
+
+			#key_value
+		}
+	})
+}
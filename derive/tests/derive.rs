@@ -0,0 +1,6 @@
+#[test]
+fn derive() {
+	let cases = trybuild::TestCases::new();
+	cases.pass("tests/pass/*.rs");
+	cases.compile_fail("tests/fail/*.rs");
+}
@@ -0,0 +1,7 @@
+#[derive(serde::Serialize, database::Record)]
+#[record(store = "users")]
+enum User {
+	Alice,
+}
+
+fn main() {}
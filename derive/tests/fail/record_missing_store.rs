@@ -0,0 +1,6 @@
+#[derive(serde::Serialize, database::Record)]
+struct User {
+	name: String,
+}
+
+fn main() {}
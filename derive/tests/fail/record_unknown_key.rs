@@ -0,0 +1,7 @@
+#[derive(serde::Serialize, database::Record)]
+#[record(store = "users", key = "id")]
+struct User {
+	name: String,
+}
+
+fn main() {}
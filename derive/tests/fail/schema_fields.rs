@@ -0,0 +1,13 @@
+use database::{Client, Error};
+
+#[derive(database::Schema)]
+enum Version {
+	#[schema(apply = create_entries)]
+	V1(u32),
+}
+
+fn create_entries(_database: &Client) -> Result<(), Error> {
+	Ok(())
+}
+
+fn main() {}
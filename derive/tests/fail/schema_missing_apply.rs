@@ -0,0 +1,6 @@
+#[derive(database::Schema)]
+enum Version {
+	V1,
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+use database::Record;
+
+#[derive(serde::Serialize, database::Record)]
+#[record(store = "users")]
+struct User {
+	name: String,
+}
+
+#[derive(serde::Serialize, database::Record)]
+#[record(store = "posts", key = "id")]
+struct Post {
+	id: u32,
+	title: String,
+}
+
+fn main() {
+	assert_eq!(User::store_id(), "users");
+	assert_eq!(Post::store_id(), "posts");
+	// converting a key needs JS, so only check that the keys are wired up
+	let _ = Post::key_value;
+	let _ = User { name: "alice".into() };
+	let _ = Post { id: 1, title: "hello".into() };
+}
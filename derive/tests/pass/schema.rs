@@ -0,0 +1,24 @@
+use database::{Client, Error, Schema};
+
+#[derive(database::Schema)]
+enum Version {
+	#[schema(apply = create_entries)]
+	V1,
+	#[schema(apply = index_entries)]
+	V2,
+}
+
+fn create_entries(_database: &Client) -> Result<(), Error> {
+	Ok(())
+}
+
+fn index_entries(_database: &Client) -> Result<(), Error> {
+	Ok(())
+}
+
+fn main() {
+	assert_eq!(Version::latest(), 2);
+	assert!(matches!(Version::try_from(1), Ok(Version::V1)));
+	assert!(matches!(Version::try_from(2), Ok(Version::V2)));
+	assert!(Version::try_from(3).is_err());
+}
@@ -0,0 +1,33 @@
+use database::{Client, Error, MigrationOp, Schema, SchemaBuilder, SchemaLayout};
+
+#[derive(database::Schema)]
+enum Version {
+	#[schema(apply = create_users, layout = users)]
+	V1,
+	#[schema(layout = users_by_email, apply = index_users)]
+	V2,
+}
+
+fn create_users(_database: &Client) -> Result<(), Error> {
+	Ok(())
+}
+
+fn index_users(_database: &Client) -> Result<(), Error> {
+	Ok(())
+}
+
+fn users() -> SchemaLayout {
+	SchemaBuilder::new().store("users", |store| store.key_path("id")).build()
+}
+
+fn users_by_email() -> SchemaLayout {
+	SchemaBuilder::new()
+		.store("users", |store| store.key_path("id").unique_index("email", "email"))
+		.build()
+}
+
+fn main() {
+	let plan = Version::plan(1).unwrap();
+	assert_eq!(plan.len(), 1);
+	assert!(matches!(&plan[0].operations[..], [MigrationOp::CreateIndex { .. }]));
+}
@@ -0,0 +1,87 @@
+use super::{Error, IndexedKey};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use wasm_bindgen::{JsCast, JsValue};
+
+/// Bytes which are stored as a binary key, such as a content hash, rather than as an array of numbers.
+/// IndexedDB sorts binary keys byte by byte, after every other kind of key.
+/// Binary keys are read back as an `ArrayBuffer`, which this converts from when deserialized,
+/// so it can be used as the key of a record, of a key range, and of a cursor.
+/// ```ignore
+/// #[derive(Serialize, Deserialize)]
+/// struct Chunk {
+///   hash: BinaryKey,
+///   data: Vec<u8>,
+/// }
+/// static CHUNKS: StoreDescriptor<Chunk> = StoreDescriptor::new("chunks").key_path(&["hash"]);
+/// let chunk = client.store(&CHUNKS).get(&BinaryKey::from(hash)).await?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct BinaryKey(pub Vec<u8>);
+
+impl BinaryKey {
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.0
+	}
+
+	pub fn into_bytes(self) -> Vec<u8> {
+		self.0
+	}
+
+	fn to_array(&self) -> js_sys::Uint8Array {
+		js_sys::Uint8Array::from(self.0.as_slice())
+	}
+
+	fn from_js(value: &JsValue) -> Option<Self> {
+		if let Some(buffer) = value.dyn_ref::<js_sys::ArrayBuffer>() {
+			return Some(Self(js_sys::Uint8Array::new(buffer).to_vec()));
+		}
+		let array = value.dyn_ref::<js_sys::Uint8Array>()?;
+		Some(Self(array.to_vec()))
+	}
+}
+
+impl From<Vec<u8>> for BinaryKey {
+	fn from(bytes: Vec<u8>) -> Self {
+		Self(bytes)
+	}
+}
+
+impl From<&[u8]> for BinaryKey {
+	fn from(bytes: &[u8]) -> Self {
+		Self(bytes.to_vec())
+	}
+}
+
+impl<const N: usize> From<[u8; N]> for BinaryKey {
+	fn from(bytes: [u8; N]) -> Self {
+		Self(bytes.to_vec())
+	}
+}
+
+impl std::ops::Deref for BinaryKey {
+	type Target = [u8];
+
+	fn deref(&self) -> &[u8] {
+		&self.0
+	}
+}
+
+impl Serialize for BinaryKey {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serde_wasm_bindgen::preserve::serialize(&self.to_array(), serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for BinaryKey {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let value = serde_wasm_bindgen::preserve::deserialize::<D, JsValue>(deserializer)?;
+		Self::from_js(&value).ok_or_else(|| serde::de::Error::custom("expected an ArrayBuffer or Uint8Array"))
+	}
+}
+
+impl crate::indexed_key::sealed::Sealed for BinaryKey {}
+impl IndexedKey for BinaryKey {
+	fn to_js_key(&self) -> Result<JsValue, Error> {
+		Ok(self.to_array().into())
+	}
+}
@@ -0,0 +1,172 @@
+use super::{js::call, Error};
+use futures::channel::mpsc;
+use futures_util::Stream;
+use serde::de::DeserializeOwned;
+use std::{
+	pin::Pin,
+	task::{Context, Poll},
+};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+/// How a record was changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+	Add,
+	Put,
+	Delete,
+	/// Every record in the store was deleted. The key of the event is `undefined`.
+	Clear,
+}
+
+impl ChangeKind {
+	fn as_str(&self) -> &'static str {
+		match self {
+			Self::Add => "add",
+			Self::Put => "put",
+			Self::Delete => "delete",
+			Self::Clear => "clear",
+		}
+	}
+
+	fn parse(kind: &str) -> Option<Self> {
+		match kind {
+			"add" => Some(Self::Add),
+			"put" => Some(Self::Put),
+			"delete" => Some(Self::Delete),
+			"clear" => Some(Self::Clear),
+			_ => None,
+		}
+	}
+}
+
+/// A record which was written by a [`Store`](crate::Store) of the database, possibly in another tab.
+#[derive(Debug, Clone)]
+pub struct ChangeEvent {
+	pub store: String,
+	pub key: JsValue,
+	pub kind: ChangeKind,
+}
+
+impl ChangeEvent {
+	/// Parses the key of the changed record.
+	pub fn parse_key<K: DeserializeOwned>(&self) -> Result<K, Error> {
+		Ok(serde_wasm_bindgen::from_value(self.key.clone())?)
+	}
+
+	fn to_message(&self, origin: &str) -> Result<JsValue, Error> {
+		let message = js_sys::Object::new();
+		let set = |field: &str, value: &JsValue| js_sys::Reflect::set(&message, &field.into(), value);
+		set("origin", &origin.into())
+			.and_then(|_| set("store", &self.store.as_str().into()))
+			.and_then(|_| set("key", &self.key))
+			.and_then(|_| set("kind", &self.kind.as_str().into()))
+			.map_err(|err| Error::Internal(format!("{err:?}")))?;
+		Ok(message.into())
+	}
+
+	fn from_message(message: &JsValue) -> Option<Self> {
+		let get = |field: &str| js_sys::Reflect::get(message, &field.into()).ok();
+		Some(Self {
+			store: get("store")?.as_string()?,
+			key: get("key")?,
+			kind: ChangeKind::parse(&get("kind")?.as_string()?)?,
+		})
+	}
+}
+
+/// Posts [`ChangeEvent`]s to the `BroadcastChannel` of a database.
+/// Each message is tagged with the `origin` of the broadcaster, so its own [`ChangeStream`]s can skip them.
+pub(crate) struct Broadcaster {
+	name: String,
+	origin: String,
+	channel: JsValue,
+}
+
+impl Broadcaster {
+	pub(crate) fn new(database: &str) -> Result<Self, Error> {
+		let name = format!("database:{database}");
+		let channel = open_channel(&name)?;
+		let origin = format!("{:x}", (js_sys::Math::random() * 2f64.powi(53)) as u64);
+		Ok(Self { name, origin, channel })
+	}
+
+	pub(crate) fn post(&self, change: &ChangeEvent) {
+		let posted = change.to_message(&self.origin).and_then(|message| {
+			call(&self.channel, "postMessage", &[message])?;
+			Ok(())
+		});
+		if let Err(err) = posted {
+			log::error!(target: "database::changes", "Failed to broadcast change: {err}");
+		}
+	}
+
+	pub(crate) fn subscribe(&self) -> Result<ChangeStream, Error> {
+		ChangeStream::new(&self.name, self.origin.clone())
+	}
+}
+
+impl Drop for Broadcaster {
+	fn drop(&mut self) {
+		let _ = call(&self.channel, "close", &[]);
+	}
+}
+
+/// The changes made to a database by other clients, such as in other tabs.
+/// Created by [`Client::changes`](crate::Client::changes).
+pub struct ChangeStream {
+	channel: JsValue,
+	receiver: mpsc::UnboundedReceiver<ChangeEvent>,
+	_on_message: Closure<dyn FnMut(JsValue)>,
+}
+
+impl ChangeStream {
+	/// Streams the changes posted to the channel `name`, except those posted by the broadcaster with `origin`.
+	fn new(name: &str, origin: String) -> Result<Self, Error> {
+		let channel = open_channel(name)?;
+		let (sender, receiver) = mpsc::unbounded();
+		let on_message = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+			let data = js_sys::Reflect::get(&event, &"data".into()).unwrap_or_default();
+			let sent_by = js_sys::Reflect::get(&data, &"origin".into()).unwrap_or_default();
+			if sent_by.as_string().as_deref() == Some(origin.as_str()) {
+				return;
+			}
+			match ChangeEvent::from_message(&data) {
+				Some(change) => {
+					let _ = sender.unbounded_send(change);
+				}
+				None => log::warn!(target: "database::changes", "Ignoring unknown change message {data:?}"),
+			}
+		});
+		js_sys::Reflect::set(&channel, &"onmessage".into(), on_message.as_ref())
+			.map_err(|err| Error::Internal(format!("{err:?}")))?;
+		Ok(Self {
+			channel,
+			receiver,
+			_on_message: on_message,
+		})
+	}
+}
+
+impl Stream for ChangeStream {
+	type Item = ChangeEvent;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		Pin::new(&mut self.receiver).poll_next(cx)
+	}
+}
+
+impl Drop for ChangeStream {
+	fn drop(&mut self) {
+		let _ = call(&self.channel, "close", &[]);
+	}
+}
+
+fn open_channel(name: &str) -> Result<JsValue, Error> {
+	let unsupported = || Error::Unsupported("BroadcastChannel".into());
+	let constructor = js_sys::Reflect::get(&js_sys::global(), &"BroadcastChannel".into()).map_err(|_| unsupported())?;
+	let constructor = constructor.dyn_into::<js_sys::Function>().map_err(|_| unsupported())?;
+	let arguments = js_sys::Array::of1(&name.into());
+	let channel =
+		js_sys::Reflect::construct(&constructor, &arguments).map_err(|err| Error::Internal(format!("{err:?}")))?;
+	Ok(channel)
+}
@@ -1,85 +1,342 @@
-use crate::Record;
+use crate::{Mode, ReadOnly, ReadWrite, Record, ScopedTransaction};
 
-use super::{Error, MissingVersion, Schema, UpgradeError};
+use super::{Error, MissingVersion, Schema};
+use futures_util::future::LocalBoxFuture;
 use idb::event::VersionChangeEvent;
-use std::sync::Arc;
+use std::{cell::Cell, future::IntoFuture, rc::Rc};
 use wasm_bindgen::JsValue;
 
 pub use idb::TransactionMode;
 
+/// How durably a transaction's changes are written before it completes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Durability {
+	/// The browser's default.
+	#[default]
+	Default,
+	/// Changes are flushed to disk before the transaction completes.
+	Strict,
+	/// Changes are written to the operating system, but may not be flushed to disk before the transaction completes.
+	/// Much faster for frequent small writes, at the risk of losing the latest writes if the device loses power.
+	Relaxed,
+}
+
+impl Durability {
+	fn as_str(&self) -> &'static str {
+		match self {
+			Self::Default => "default",
+			Self::Strict => "strict",
+			Self::Relaxed => "relaxed",
+		}
+	}
+}
+
+type Reopen = Rc<dyn Fn() -> LocalBoxFuture<'static, Result<Client, Error>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConnectionState {
+	Open,
+	/// Closed by the app, such as by [`Client::close`].
+	Closed,
+	/// Closed by the browser, such as because the database was deleted in the devtools.
+	Gone,
+}
+
 /// A connection to a local IndexedDB database.
+/// Cloning a client is cheap, and every clone shares the same connection, which closes once all of them are dropped.
 #[derive(Clone)]
-pub struct Client(Arc<idb::Database>);
+pub struct Client(Rc<Connection>);
+
+struct Connection {
+	database: idb::Database,
+	/// The `IDBDatabase` of the connection, for calls which idb doesn't expose.
+	handle: JsValue,
+	/// True if the database was opened at a newer version than supported, so may only be read from.
+	read_only: bool,
+	/// Shared by every clone of the client, and cleared when the connection is closed.
+	state: Rc<Cell<ConnectionState>>,
+	/// Opens the database again the same way it was first opened, for [`Client::reopen`].
+	reopen: Option<Reopen>,
+	/// Posts the changes made by [`Store`](crate::Store)s, if enabled by [`ClientBuilder::broadcast_changes`].
+	broadcaster: Option<crate::changes::Broadcaster>,
+}
+
+impl Connection {
+	fn new(database: idb::Database, read_only: bool) -> Self {
+		let handle = JsValue::from(database);
+		let database = idb::Database::try_from(handle.clone()).expect("the value was an IDBDatabase");
+		Self {
+			database,
+			handle,
+			read_only,
+			state: Rc::new(Cell::new(ConnectionState::Open)),
+			reopen: None,
+			broadcaster: None,
+		}
+	}
+}
+
+impl Drop for Connection {
+	fn drop(&mut self) {
+		self.database.close();
+	}
+}
+
+mod builder;
+pub use builder::*;
+
+/// A database which exists in the browser, as listed by [`Client::list_databases`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct DatabaseInfo {
+	pub name: String,
+	pub version: u32,
+}
 
 impl PartialEq for Client {
 	fn eq(&self, other: &Self) -> bool {
-		Arc::ptr_eq(&self.0, &other.0)
+		Rc::ptr_eq(&self.0, &other.0)
 	}
 }
 
 impl Client {
-	pub async fn open<V>(name: &str) -> Result<Self, idb::Error>
+	pub(crate) fn new(database: idb::Database, read_only: bool) -> Self {
+		Self(Rc::new(Connection::new(database, read_only)))
+	}
+
+	/// Configures how the database is opened, such as what to do if it is newer than the schema.
+	pub fn builder(name: impl Into<String>) -> ClientBuilder {
+		ClientBuilder::new(name.into())
+	}
+
+	pub async fn open<V>(name: &str) -> Result<Self, Error>
 	where
 		V: 'static + Schema + TryFrom<u32, Error = MissingVersion>,
 	{
-		let factory = idb::Factory::new()?;
-		let mut request = factory.open(name, Some(V::latest()))?;
-		// called when the database is being created for the first time, or when the version is old
-		request.on_upgrade_needed(|event| {
-			if let Err(err) = Self::upgrade_database::<V>(&event) {
-				let old = event.old_version().unwrap();
-				let new = event
-					.new_version()
-					.unwrap()
-					.map(|v| format!(" to v{v}"))
-					.unwrap_or_default();
-				log::error!(target: "database::client", "Failed to upgrade database from v{old}{new}: {err:?}");
-			}
-		});
-		let database = request.await?;
-		Ok(Self(Arc::new(database)))
+		Self::builder(name).open::<V>().await
+	}
+
+	/// Opens the database at the latest version of `migrations`, running any steps the stored database is missing.
+	pub async fn open_with(name: &str, migrations: crate::Migrations) -> Result<Self, Error> {
+		Self::builder(name).open_with(migrations).await
+	}
+
+	/// Opens the database at `version`, and if it is being created or upgraded,
+	/// creates and removes stores and indexes so the database matches `layout`.
+	pub async fn open_layout(name: &str, version: u32, layout: crate::SchemaLayout) -> Result<Self, Error> {
+		Self::builder(name).open_layout(version, layout).await
+	}
+
+	/// Deletes the database and all of its data. Use [`ClientBuilder::on_blocked`] with [`ClientBuilder::delete`]
+	/// to be notified when other connections are delaying the deletion.
+	pub async fn delete_database(name: &str) -> Result<(), Error> {
+		Self::builder(name).delete().await
+	}
+
+	/// The name and version of every database of this origin. Fails with [`Error::Unsupported`]
+	/// in browsers which don't implement `indexedDB.databases()`.
+	pub async fn list_databases() -> Result<Vec<DatabaseInfo>, Error> {
+		use wasm_bindgen::JsCast;
+		let unsupported = || Error::Unsupported("indexedDB.databases()".into());
+		let factory =
+			js_sys::Reflect::get(&js_sys::global(), &JsValue::from("indexedDB")).map_err(|_| unsupported())?;
+		let databases = js_sys::Reflect::get(&factory, &JsValue::from("databases")).map_err(|_| unsupported())?;
+		let databases = databases.dyn_into::<js_sys::Function>().map_err(|_| unsupported())?;
+		let promise = databases
+			.call0(&factory)
+			.map_err(|err| Error::Internal(format!("{err:?}")))?;
+		let promise = promise.dyn_into::<js_sys::Promise>().map_err(|_| unsupported())?;
+		let infos = wasm_bindgen_futures::JsFuture::from(promise)
+			.await
+			.map_err(|err| Error::Internal(format!("{err:?}")))?;
+		Ok(serde_wasm_bindgen::from_value(infos)?)
+	}
+
+	/// The databases whose names start with `prefix`, such as those opened with [`ClientBuilder::prefix`].
+	pub async fn list_databases_with_prefix(prefix: &str) -> Result<Vec<DatabaseInfo>, Error> {
+		let mut databases = Self::list_databases().await?;
+		databases.retain(|database| database.name.starts_with(prefix));
+		Ok(databases)
 	}
 
-	fn upgrade_database<V>(event: &VersionChangeEvent) -> Result<(), UpgradeError>
+	/// Deletes every database whose name starts with `prefix`, such as to remove the data of a signed-out account.
+	/// Returns the names of the deleted databases.
+	pub async fn delete_databases_with_prefix(prefix: &str) -> Result<Vec<String>, Error> {
+		let mut deleted = Vec::new();
+		for database in Self::list_databases_with_prefix(prefix).await? {
+			Self::delete_database(&database.name).await?;
+			deleted.push(database.name);
+		}
+		Ok(deleted)
+	}
+
+	/// Checks whether IndexedDB can actually be used, by creating, writing to, and deleting a small database,
+	/// so that the app can fall back to in-memory storage in environments like some private browsing modes.
+	pub async fn probe() -> crate::ProbeReport {
+		crate::storage::probe().await
+	}
+
+	/// How much storage this origin is using and may use, so that the app can warn before writes start failing.
+	/// Fails with [`Error::Unsupported`] in browsers which don't implement `navigator.storage.estimate()`.
+	pub async fn storage_estimate() -> Result<crate::StorageEstimate, Error> {
+		crate::storage::estimate().await
+	}
+
+	/// Asks the browser to keep the origin's data (including every database) rather than evicting it
+	/// when storage runs low, returning true if the storage is persistent. Browsers may prompt the user,
+	/// or decide based on how the site is used. Does nothing if the storage is already persistent.
+	pub async fn request_persistent_storage() -> Result<bool, Error> {
+		if crate::storage::persisted().await? {
+			return Ok(true);
+		}
+		crate::storage::persist().await
+	}
+
+	/// True if the origin's storage is persistent, see [`Client::request_persistent_storage`].
+	pub async fn is_storage_persistent() -> Result<bool, Error> {
+		crate::storage::persisted().await
+	}
+
+	pub(crate) fn upgrade_database<V>(event: &VersionChangeEvent) -> Result<(), Error>
 	where
 		V: 'static + Schema + TryFrom<u32, Error = MissingVersion>,
 	{
 		use idb::DatabaseEvent;
-		let database = Self(Arc::new(event.database()?));
+		let database = Self::new(event.database()?, false);
 		// This is always 0 for database initialization, and is otherwise the previous version.
 		let old_version = event.old_version()?;
 		// I've never seen this be None in practice.
 		let Some(new_version) = event.new_version()? else {
 			return Ok(());
 		};
+		let transaction = Self::upgrade_transaction(event)?;
+		let old = Some(old_version).filter(|version| *version > 0);
 		// Even if we are initializing fresh, we need to step through all of the versions
 		for version in (old_version + 1)..=new_version {
-			let schema = V::try_from(version)?;
-			schema.apply(&database)?;
+			let applied = V::try_from(version)
+				.map_err(Error::from)
+				.and_then(|schema| schema.apply_versioned(&database, &transaction, old, new_version));
+			applied.map_err(|err| Error::migration_failed(version, err))?;
 		}
 		Ok(())
 	}
-}
 
-impl Drop for Client {
-	fn drop(&mut self) {
-		if Arc::strong_count(&self.0) <= 1 {
-			self.0.close();
+	/// The `versionchange` transaction which an upgrade runs in.
+	pub(crate) fn upgrade_transaction(event: &VersionChangeEvent) -> Result<Transaction, Error> {
+		use idb::{Event, Request};
+		match event.target()?.transaction() {
+			Some(transaction) => Ok(Transaction(transaction)),
+			None => Err(Error::Internal("missing upgrade transaction".into())),
 		}
 	}
+
+	/// True if the database is newer than the schema and was opened with [`VersionPolicy::ReadOnly`],
+	/// in which case read-write transactions cannot be opened.
+	pub fn is_read_only(&self) -> bool {
+		self.0.read_only
+	}
+
+	/// Closes the connection for every clone of the client, such as to release the database while the app is idle.
+	pub fn close(&self) {
+		self.0.database.close();
+		self.0.state.set(ConnectionState::Closed);
+	}
+
+	/// False once the connection has been closed, whether by [`Client::close`],
+	/// by [`ClientBuilder::close_on_version_change`], or by the browser.
+	pub fn is_open(&self) -> bool {
+		self.0.state.get() == ConnectionState::Open
+	}
+
+	/// If the connection is closed, opens the database again the same way it was first opened,
+	/// so the schema is checked (and the database upgraded, or created again if it was deleted) again.
+	/// Other clones keep the closed connection. [`LazyClient`](crate::LazyClient) and
+	/// [`DatabaseRegistry`](crate::DatabaseRegistry) reopen closed clients automatically.
+	pub async fn reopen(&mut self) -> Result<(), Error> {
+		if self.is_open() {
+			return Ok(());
+		}
+		let Some(reopen) = self.0.reopen.clone() else {
+			return Err(Error::Closed);
+		};
+		*self = reopen().await?;
+		Ok(())
+	}
 }
 
 impl std::ops::Deref for Client {
 	type Target = idb::Database;
 
 	fn deref(&self) -> &Self::Target {
-		&self.0
+		&self.0.database
 	}
 }
 
 impl Client {
 	pub fn transaction<T: AsRef<str>>(&self, store_ids: &[T], mode: TransactionMode) -> Result<Transaction, Error> {
-		Ok(Transaction(self.0.transaction(store_ids, mode)?))
+		self.check_mode(mode)?;
+		match self.0.database.transaction(store_ids, mode) {
+			Ok(transaction) => Ok(Transaction(transaction)),
+			Err(err) => Err(self.open_failed(crate::error::dom_exception_name(&err), err.into())),
+		}
+	}
+
+	/// Opens a transaction with the given durability. See [`Durability`].
+	pub fn transaction_with<T: AsRef<str>>(
+		&self,
+		store_ids: &[T],
+		mode: TransactionMode,
+		durability: Durability,
+	) -> Result<Transaction, Error> {
+		use wasm_bindgen::JsCast;
+		self.check_mode(mode)?;
+		let js_error = |err: JsValue| Error::Internal(format!("{err:?}"));
+		let store_ids = store_ids
+			.iter()
+			.map(|id| JsValue::from(id.as_ref()))
+			.collect::<js_sys::Array>();
+		let mode = JsValue::from(match mode {
+			TransactionMode::ReadOnly => "readonly",
+			TransactionMode::ReadWrite => "readwrite",
+			TransactionMode::VersionChange => "versionchange",
+		});
+		let options = js_sys::Object::new();
+		js_sys::Reflect::set(&options, &"durability".into(), &durability.as_str().into()).map_err(js_error)?;
+		let open = js_sys::Reflect::get(&self.0.handle, &"transaction".into()).map_err(js_error)?;
+		let open = open.unchecked_into::<js_sys::Function>();
+		let transaction = open.call3(&self.0.handle, &store_ids, &mode, &options).map_err(|err| {
+			let name = js_sys::Reflect::get(&err, &"name".into())
+				.ok()
+				.and_then(|name| name.as_string());
+			self.open_failed(name, js_error(err))
+		})?;
+		Ok(Transaction(idb::Transaction::try_from(transaction)?))
+	}
+
+	fn check_mode(&self, mode: TransactionMode) -> Result<(), Error> {
+		match self.0.state.get() {
+			ConnectionState::Open => {}
+			ConnectionState::Closed => return Err(Error::Closed),
+			ConnectionState::Gone => return Err(Error::DatabaseGone),
+		}
+		if self.0.read_only && mode != TransactionMode::ReadOnly {
+			return Err(Error::ReadOnly);
+		}
+		Ok(())
+	}
+
+	/// `InvalidStateError` when opening a transaction means the connection was closed, even if the `close`
+	/// event hasn't been handled yet.
+	fn open_failed(&self, exception: Option<String>, err: Error) -> Error {
+		if exception.as_deref() != Some("InvalidStateError") {
+			return err;
+		}
+		if self.0.state.get() == ConnectionState::Open {
+			self.0.state.set(ConnectionState::Gone);
+		}
+		match self.0.state.get() {
+			ConnectionState::Closed => Error::Closed,
+			_ => Error::DatabaseGone,
+		}
 	}
 
 	pub fn read_only<T: super::Record>(&self) -> Result<Transaction, Error> {
@@ -90,14 +347,201 @@ impl Client {
 		self.transaction(&[T::store_id()], TransactionMode::ReadWrite)
 	}
 
-	pub async fn get<T>(&self, key: impl Into<wasm_bindgen::JsValue>) -> Result<Option<T>, Error>
+	/// Opens a read-only transaction over `stores`, whose typed store handles only allow access to those stores.
+	pub fn read_transaction(&self, stores: &[&dyn crate::StoreRef]) -> Result<ScopedTransaction<ReadOnly>, Error> {
+		self.scoped_transaction(stores)
+	}
+
+	/// Opens a read-write transaction over `stores`, whose typed store handles only allow access to those stores.
+	pub fn write_transaction(&self, stores: &[&dyn crate::StoreRef]) -> Result<ScopedTransaction<ReadWrite>, Error> {
+		self.scoped_transaction(stores)
+	}
+
+	/// Opens a transaction over `stores` in the mode `M`, either [`ReadOnly`] or [`ReadWrite`].
+	pub fn scoped_transaction<M: Mode>(&self, stores: &[&dyn crate::StoreRef]) -> Result<ScopedTransaction<M>, Error> {
+		self.scoped_transaction_with(stores, Durability::Default)
+	}
+
+	/// Opens a transaction over `stores` in the mode `M`, with the given durability.
+	/// ```ignore
+	/// let transaction = client.scoped_transaction_with::<ReadWrite>(&[&TELEMETRY], Durability::Relaxed)?;
+	/// ```
+	pub fn scoped_transaction_with<M: Mode>(
+		&self,
+		stores: &[&dyn crate::StoreRef],
+		durability: Durability,
+	) -> Result<ScopedTransaction<M>, Error> {
+		let names = stores.iter().map(|store| store.store_name()).collect::<Vec<_>>();
+		let transaction = match durability {
+			Durability::Default => self.transaction(&names, M::MODE)?,
+			durability => self.transaction_with(&names, M::MODE, durability)?,
+		};
+		Ok(ScopedTransaction::new(transaction, names))
+	}
+
+	/// Runs `scope` in a read-write transaction over `stores`, which is committed if `scope` succeeds,
+	/// and aborted if it fails (or is dropped before it finishes), so none of its changes are kept.
+	/// ```ignore
+	/// client.with_write_transaction(&[&USERS, &POSTS], |transaction| async move {
+	///   transaction.store(&USERS)?.put(&user).await?;
+	///   transaction.store(&POSTS)?.put(&post).await?;
+	///   Ok(())
+	/// }).await?;
+	/// ```
+	pub async fn with_write_transaction<F, Fut, R>(&self, stores: &[&dyn crate::StoreRef], scope: F) -> Result<R, Error>
+	where
+		F: FnOnce(ScopedTransaction<ReadWrite>) -> Fut,
+		Fut: std::future::Future<Output = Result<R, Error>>,
+	{
+		self.with_transaction(stores, scope).await
+	}
+
+	/// Runs `scope` in a read-only transaction over `stores`.
+	pub async fn with_read_transaction<F, Fut, R>(&self, stores: &[&dyn crate::StoreRef], scope: F) -> Result<R, Error>
+	where
+		F: FnOnce(ScopedTransaction<ReadOnly>) -> Fut,
+		Fut: std::future::Future<Output = Result<R, Error>>,
+	{
+		self.with_transaction(stores, scope).await
+	}
+
+	/// Runs `scope` in a transaction over `stores` in the mode `M`. See [`Client::with_write_transaction`].
+	pub async fn with_transaction<M, F, Fut, R>(&self, stores: &[&dyn crate::StoreRef], scope: F) -> Result<R, Error>
+	where
+		M: Mode,
+		F: FnOnce(ScopedTransaction<M>) -> Fut,
+		Fut: std::future::Future<Output = Result<R, Error>>,
+	{
+		let transaction = self.scoped_transaction(stores)?;
+		let mut guard = crate::scoped_transaction::AbortGuard::new(&transaction)?;
+		let output = scope(transaction).await?;
+		guard.commit().await?;
+		Ok(output)
+	}
+
+	/// Runs `scope` in a read-write transaction like [`Client::with_write_transaction`], running it again
+	/// in a new transaction whenever it fails with a [transient](Error::is_transient) error, as allowed by `policy`.
+	/// ```ignore
+	/// client.retrying(&[&EVENTS], RetryPolicy::default(), |transaction| async move {
+	///   transaction.store(&EVENTS)?.add(&event).await
+	/// }).await?;
+	/// ```
+	pub async fn retrying<F, Fut, R>(
+		&self,
+		stores: &[&dyn crate::StoreRef],
+		policy: crate::RetryPolicy,
+		scope: F,
+	) -> Result<R, Error>
+	where
+		F: Fn(ScopedTransaction<ReadWrite>) -> Fut,
+		Fut: std::future::Future<Output = Result<R, Error>>,
+	{
+		let mut retry = 0;
+		loop {
+			match self.with_write_transaction(stores, &scope).await {
+				Err(err) if err.is_transient() && retry + 1 < policy.attempts => {
+					log::warn!(target: "database::client", "Retrying transaction after it failed: {err}");
+					crate::retry::sleep(policy.delay(retry)).await?;
+					retry += 1;
+				}
+				result => return result,
+			}
+		}
+	}
+
+	/// Streams the changes which other clients of the database (such as in other tabs) make through
+	/// [`Store`](crate::Store)s, if they [broadcast changes](ClientBuilder::broadcast_changes).
+	/// Changes made through this client (or its clones) are not included.
+	/// ```ignore
+	/// let mut changes = client.changes()?;
+	/// while let Some(change) = changes.next().await {
+	///   cache.invalidate(&change.store, change.parse_key::<String>()?);
+	/// }
+	/// ```
+	pub fn changes(&self) -> Result<crate::ChangeStream, Error> {
+		match &self.0.broadcaster {
+			Some(broadcaster) => broadcaster.subscribe(),
+			None => crate::changes::Broadcaster::new(&self.0.database.name())?.subscribe(),
+		}
+	}
+
+	/// Deletes every record in each of the `stores` in a single transaction, such as to wipe cached data on sign out.
+	pub async fn clear_stores(&self, stores: &[&dyn crate::StoreRef]) -> Result<(), Error> {
+		let names = stores.iter().map(|store| store.store_name()).collect::<Vec<_>>();
+		let transaction = self.transaction(&names, TransactionMode::ReadWrite)?;
+		let mut requests = Vec::with_capacity(names.len());
+		for name in &names {
+			requests.push(transaction.object_store(name)?.clear()?.into_future());
+		}
+		futures_util::future::try_join_all(requests).await?;
+		crate::scoped_transaction::commit(transaction.0).await?;
+		for name in names {
+			self.broadcast(crate::ChangeEvent {
+				store: name.to_owned(),
+				key: JsValue::UNDEFINED,
+				kind: crate::ChangeKind::Clear,
+			});
+		}
+		Ok(())
+	}
+
+	pub(crate) fn broadcast(&self, change: crate::ChangeEvent) {
+		if let Some(broadcaster) = &self.0.broadcaster {
+			broadcaster.post(&change);
+		}
+	}
+
+	/// A typed handle to the store described by `descriptor`.
+	pub fn store<T>(&self, descriptor: &crate::StoreDescriptor<T>) -> crate::Store<T> {
+		crate::Store::new(self.clone(), *descriptor)
+	}
+
+	/// Reads the object stores and indexes which currently exist in the database.
+	pub fn schema_snapshot(&self) -> Result<crate::SchemaLayout, Error> {
+		let store_names = self.0.database.store_names();
+		// a transaction cannot be opened over no stores
+		if store_names.is_empty() {
+			return Ok(crate::SchemaLayout::default());
+		}
+		let transaction = self.transaction(&store_names, TransactionMode::ReadOnly)?;
+		crate::SchemaLayout::read(&transaction)
+	}
+
+	/// Compares the declared layout of the database to the stores and indexes which actually exist,
+	/// returning every mismatch (or nothing if the database matches).
+	pub fn validate(&self, declared: &crate::SchemaLayout) -> Result<Vec<crate::SchemaMismatch>, Error> {
+		Ok(declared.mismatches(&self.schema_snapshot()?))
+	}
+
+	/// Streams every record in the store of `T`, reopening the cursor in a new transaction
+	/// whenever the previous one finishes. See [`ResumableCursor`](crate::ResumableCursor).
+	pub fn resumable_cursor<T: Record + 'static>(
+		&self,
+		direction: Option<crate::CursorDirection>,
+	) -> crate::ResumableCursor<T> {
+		crate::ResumableCursor::new(self.clone(), direction)
+	}
+
+	pub async fn get<T>(&self, key: &(impl crate::IndexedKey + ?Sized)) -> Result<Option<T>, Error>
 	where
 		T: crate::Record + serde::de::DeserializeOwned,
 	{
 		use crate::{ObjectStoreExt, TransactionExt};
 		let transaction = self.read_only::<T>()?;
 		let store = transaction.object_store_of::<T>()?;
-		Ok(store.get_record(key).await?)
+		store.get_record(key).await
+	}
+
+	/// Writes `record` to the store of `T` in its own transaction.
+	pub async fn put<T: Record>(&self, record: &T) -> Result<(), Error> {
+		let transaction = self.read_write::<T>()?.put(record).await?;
+		crate::scoped_transaction::commit(transaction.0).await
+	}
+
+	/// Deletes the record with `key` from the store of `T` in its own transaction.
+	pub async fn delete<T: Record>(&self, key: &(impl crate::IndexedKey + ?Sized)) -> Result<(), Error> {
+		let transaction = self.read_write::<T>()?.delete::<T>(key).await?;
+		crate::scoped_transaction::commit(transaction.0).await
 	}
 }
 
@@ -116,12 +560,12 @@ impl Transaction {
 		Ok(())
 	}
 
-	pub async fn delete<T: Record>(self, key: impl Into<JsValue>) -> Result<Self, Error> {
+	pub async fn delete<T: Record>(self, key: &(impl crate::IndexedKey + ?Sized)) -> Result<Self, Error> {
 		self.delete_ref::<T>(key).await?;
 		Ok(self)
 	}
 
-	pub async fn delete_ref<T: Record>(&self, key: impl Into<JsValue>) -> Result<(), Error> {
+	pub async fn delete_ref<T: Record>(&self, key: &(impl crate::IndexedKey + ?Sized)) -> Result<(), Error> {
 		use crate::{ObjectStoreExt, TransactionExt};
 		let store = self.object_store_of::<T>()?;
 		store.delete_record(key).await?;
@@ -131,6 +575,98 @@ impl Transaction {
 	pub async fn commit(self) -> Result<idb::TransactionResult, Error> {
 		Ok(self.0.commit()?.await?)
 	}
+
+	/// Called once the transaction has committed.
+	pub fn on_complete(&self, callback: impl FnOnce() + 'static) -> Result<(), Error> {
+		self.listen("complete", move |_| callback())
+	}
+
+	/// Called if the transaction is aborted, with the reason unless it was aborted explicitly.
+	pub fn on_abort(&self, callback: impl FnOnce(Option<Error>) + 'static) -> Result<(), Error> {
+		self.listen("abort", move |event| {
+			let error = target_error(&event);
+			callback((!error.is_null()).then(|| crate::error::from_exception(&error)));
+		})
+	}
+
+	/// Called with the first request of the transaction to fail.
+	pub fn on_error(&self, callback: impl FnOnce(Error) + 'static) -> Result<(), Error> {
+		self.listen("error", move |event| {
+			callback(crate::error::from_exception(&target_error(&event)))
+		})
+	}
+
+	/// Adds an event listener to the transaction, rather than replacing its event handler,
+	/// so that it doesn't interfere with awaiting the transaction.
+	/// The listener is called at most once, and is removed and dropped when the transaction finishes
+	/// (with either `complete` or `abort`), whether or not it was called.
+	fn listen(&self, event: &str, callback: impl FnOnce(JsValue) + 'static) -> Result<(), Error> {
+		use crate::js::call;
+		use std::{cell::RefCell, rc::Rc};
+		use wasm_bindgen::closure::Closure;
+		type Listener = Closure<dyn FnMut(JsValue)>;
+		const FINISHED: [&str; 2] = ["complete", "abort"];
+
+		// idb only hands out the IDBTransaction by value, but each store of the transaction can produce another handle
+		let Some(store) = self.0.store_names().into_iter().next() else {
+			return Err(Error::Internal("the transaction has no object stores".into()));
+		};
+		let handle = JsValue::from(self.0.object_store(&store)?.transaction());
+
+		let mut callback = Some(callback);
+		let listener = Listener::new(move |event| {
+			if let Some(callback) = callback.take() {
+				callback(event);
+			}
+		});
+		let listeners = Rc::new(RefCell::new(None::<(Listener, Listener)>));
+		let cleanup = Listener::new({
+			let (handle, event, listeners) = (handle.clone(), event.to_owned(), listeners.clone());
+			move |_| {
+				let Some((listener, cleanup)) = listeners.borrow_mut().take() else {
+					return;
+				};
+				let _ = call(
+					&handle,
+					"removeEventListener",
+					&[event.as_str().into(), listener.as_ref().clone()],
+				);
+				for finished in FINISHED {
+					let _ = call(
+						&handle,
+						"removeEventListener",
+						&[finished.into(), cleanup.as_ref().clone()],
+					);
+				}
+				// a closure cannot be dropped while it is running, so both are dropped once this one returns
+				wasm_bindgen_futures::spawn_local(async move { drop((listener, cleanup)) });
+			}
+		});
+
+		let options = web_sys::AddEventListenerOptions::new();
+		options.set_once(true);
+		call(
+			&handle,
+			"addEventListener",
+			&[event.into(), listener.as_ref().clone(), options.into()],
+		)?;
+		// added after the listener, so that a listener for `complete` or `abort` runs before it is removed
+		for finished in FINISHED {
+			call(
+				&handle,
+				"addEventListener",
+				&[finished.into(), cleanup.as_ref().clone()],
+			)?;
+		}
+		*listeners.borrow_mut() = Some((listener, cleanup));
+		Ok(())
+	}
+}
+
+/// The `error` of the transaction or request which an event was sent to, or null.
+fn target_error(event: &JsValue) -> JsValue {
+	let target = js_sys::Reflect::get(event, &"target".into()).unwrap_or(JsValue::NULL);
+	js_sys::Reflect::get(&target, &"error".into()).unwrap_or(JsValue::NULL)
 }
 
 impl std::ops::Deref for Transaction {
@@ -0,0 +1,439 @@
+use super::{Client, Connection, ConnectionState, Reopen};
+use crate::{Error, MissingVersion, Schema, Transaction};
+use futures::channel::oneshot;
+use futures_util::future::{self, Either, LocalBoxFuture};
+use idb::event::VersionChangeEvent;
+use std::{
+	cell::{Cell, RefCell},
+	future::{Future, IntoFuture},
+	rc::Rc,
+	time::Duration,
+};
+
+/// What to do when the database has a newer version than the schema supports,
+/// such as when an old build is opened after a newer build (in another tab) upgraded the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VersionPolicy {
+	/// Fail to open with [`Error::VersionTooNew`].
+	#[default]
+	Refuse,
+	/// Open the database at its current version, only allowing read-only transactions.
+	ReadOnly,
+	/// Delete the database and create it again at the supported version. All data in the database is lost.
+	Recreate,
+}
+
+type UpgradeFn = Rc<dyn Fn(&VersionChangeEvent) -> Result<(), Error>>;
+/// Called with the old and new versions of a database.
+type VersionCallback = Rc<dyn Fn(u32, Option<u32>)>;
+type SeedFn = Rc<dyn Fn(Transaction) -> LocalBoxFuture<'static, Result<(), Error>>>;
+/// Receives the result of the `on_created` hook, once it has finished.
+type Seeding = Rc<RefCell<Option<oneshot::Receiver<Result<(), Error>>>>>;
+
+/// Opens a [`Client`] with options beyond those of [`Client::open`]. Created by [`Client::builder`].
+/// ```ignore
+/// let client = Client::builder("app")
+///   .version_policy(VersionPolicy::ReadOnly)
+///   .open::<Version>()
+///   .await?;
+/// ```
+#[derive(Clone)]
+pub struct ClientBuilder {
+	name: String,
+	version_policy: VersionPolicy,
+	on_blocked: Option<VersionCallback>,
+	on_version_change: Option<VersionCallback>,
+	close_on_version_change: bool,
+	on_close: Option<Rc<dyn Fn()>>,
+	on_created: Option<SeedFn>,
+	timeout: Option<Duration>,
+	broadcast_changes: bool,
+}
+
+impl ClientBuilder {
+	pub(crate) fn new(name: String) -> Self {
+		Self {
+			name,
+			version_policy: VersionPolicy::default(),
+			on_blocked: None,
+			on_version_change: None,
+			close_on_version_change: false,
+			on_close: None,
+			on_created: None,
+			timeout: None,
+			broadcast_changes: false,
+		}
+	}
+
+	/// Prepends `prefix` to the name of the database, such as to keep the databases of each account
+	/// or environment apart. See [`Client::list_databases_with_prefix`] and [`Client::delete_databases_with_prefix`].
+	/// ```ignore
+	/// let client = Client::builder("settings").prefix(format!("account-{id}/")).open::<Version>().await?;
+	/// ```
+	pub fn prefix(mut self, prefix: impl AsRef<str>) -> Self {
+		self.name = format!("{}{}", prefix.as_ref(), self.name);
+		self
+	}
+
+	/// The name of the database, including any [prefix](Self::prefix).
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn version_policy(mut self, policy: VersionPolicy) -> Self {
+		self.version_policy = policy;
+		self
+	}
+
+	/// Called with the current and requested versions when the upgrade cannot start
+	/// because another connection (such as in another tab) still has the database open.
+	/// The open does not complete until those connections are closed.
+	pub fn on_blocked(mut self, callback: impl Fn(u32, Option<u32>) + 'static) -> Self {
+		self.on_blocked = Some(Rc::new(callback));
+		self
+	}
+
+	/// Called with the current and requested versions when another connection wants to upgrade
+	/// (or delete) the database. The opened client should be closed so that the other connection isn't blocked.
+	pub fn on_version_change(mut self, callback: impl Fn(u32, Option<u32>) + 'static) -> Self {
+		self.on_version_change = Some(Rc::new(callback));
+		self
+	}
+
+	/// Called when the browser forcibly closes the connection, such as when the database is deleted
+	/// in the devtools or the device runs low on storage, so that the app can switch to a degraded mode.
+	/// Afterwards, operations fail with [`Error::DatabaseGone`] until the client is [reopened](Client::reopen).
+	/// Not called when the app closes the client itself.
+	pub fn on_close(mut self, callback: impl Fn() + 'static) -> Self {
+		self.on_close = Some(Rc::new(callback));
+		self
+	}
+
+	/// Fails with [`Error::OpenTimedOut`] if the database hasn't opened within `timeout`, such as when
+	/// another connection which doesn't close on version changes blocks the upgrade. By default, opening waits forever.
+	pub fn timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = Some(timeout);
+		self
+	}
+
+	/// Broadcasts every record written through a [`Store`](crate::Store) of the client to the other tabs,
+	/// which can subscribe with [`Client::changes`].
+	pub fn broadcast_changes(mut self) -> Self {
+		self.broadcast_changes = true;
+		self
+	}
+
+	/// Closes the connection as soon as another connection wants to upgrade (or delete) the database,
+	/// so that the other connection is never blocked. [`on_version_change`](Self::on_version_change)
+	/// is still called afterwards, such as to tell the user to reload the page.
+	/// ```ignore
+	/// Client::builder("app")
+	///   .close_on_version_change()
+	///   .on_version_change(|_old, _new| show_reload_prompt())
+	///   .open::<Version>()
+	///   .await?;
+	/// ```
+	pub fn close_on_version_change(mut self) -> Self {
+		self.close_on_version_change = true;
+		self
+	}
+
+	/// Called with the upgrade transaction when the database is created for the first time, after the schema
+	/// is applied, so that default records can be seeded exactly once. If seeding fails, the database is not created
+	/// and opening fails with the seeding error. The hook must only await requests of its transaction, since the
+	/// transaction commits once no request is pending; opening fails if the hook is still running by then.
+	/// ```ignore
+	/// Client::builder("app")
+	///   .on_created(|transaction| async move {
+	///     transaction.put_ref(&Settings::default()).await
+	///   })
+	///   .open::<Version>()
+	///   .await?;
+	/// ```
+	pub fn on_created<F, Fut>(mut self, seed: F) -> Self
+	where
+		F: Fn(Transaction) -> Fut + 'static,
+		Fut: Future<Output = Result<(), Error>> + 'static,
+	{
+		self.on_created = Some(Rc::new(move |transaction| Box::pin(seed(transaction))));
+		self
+	}
+
+	/// Opens the database at the latest version of the schema `V`, applying each version the database is missing.
+	pub async fn open<V>(self) -> Result<Client, Error>
+	where
+		V: 'static + Schema + TryFrom<u32, Error = MissingVersion>,
+	{
+		self.open_at::<V>(V::latest()).await
+	}
+
+	/// Opens the database at `version` of the schema `V`, which may be older than its latest version.
+	pub async fn open_at<V>(self, version: u32) -> Result<Client, Error>
+	where
+		V: 'static + Schema + TryFrom<u32, Error = MissingVersion>,
+	{
+		self.open_upgrading(version, Rc::new(Client::upgrade_database::<V>))
+			.await
+	}
+
+	/// Opens the database at the latest version of `migrations`, running any steps the stored database is missing.
+	pub async fn open_with(self, migrations: crate::Migrations) -> Result<Client, Error> {
+		let version = migrations.latest();
+		self.open_upgrading(version, Rc::new(move |event| migrations.upgrade(event)))
+			.await
+	}
+
+	/// Opens the database at `version`, and if it is being created or upgraded,
+	/// creates and removes stores and indexes so the database matches `layout`.
+	pub async fn open_layout(self, version: u32, layout: crate::SchemaLayout) -> Result<Client, Error> {
+		let upgrade = move |event: &VersionChangeEvent| {
+			let transaction = Client::upgrade_transaction(event)?;
+			let upgraded = layout.upgrade(&transaction);
+			upgraded.map_err(|err| Error::migration_failed(event.new_version().ok().flatten().unwrap_or(version), err))
+		};
+		self.open_upgrading(version, Rc::new(upgrade)).await
+	}
+
+	/// Deletes the database and all of its data, such as to reset local data.
+	/// Succeeds if the database does not exist. If other connections have the database open,
+	/// [`on_blocked`](Self::on_blocked) is called and the deletion waits until they are closed.
+	pub async fn delete(self) -> Result<(), Error> {
+		let factory = idb::Factory::new()?;
+		self.delete_request(&factory).await
+	}
+
+	async fn delete_request(&self, factory: &idb::Factory) -> Result<(), Error> {
+		let mut request = factory.delete(&self.name)?;
+		if let Some(callback) = self.on_blocked.clone() {
+			request.on_blocked(move |event| {
+				let (old, new) = versions(&event);
+				callback(old, new);
+			});
+		}
+		request.into_future().await?;
+		Ok(())
+	}
+
+	async fn open_upgrading(self, version: u32, upgrade: UpgradeFn) -> Result<Client, Error> {
+		let reopen: Reopen = {
+			let builder = self.clone();
+			let upgrade = upgrade.clone();
+			Rc::new(move || Box::pin(builder.clone().open_upgrading(version, upgrade.clone())))
+		};
+		let factory = idb::Factory::new()?;
+		if let Some(database) = self.open_request(&factory, version, upgrade.clone()).await? {
+			return Ok(self.connect(database, false, reopen));
+		}
+
+		// The database was upgraded past the version of the schema, so open it at whatever version it is.
+		let database = factory.open(&self.name, None)?.await?;
+		match self.version_policy {
+			VersionPolicy::Refuse => {
+				let found = database.version()?;
+				database.close();
+				Err(Error::VersionTooNew {
+					found,
+					supported: version,
+				})
+			}
+			VersionPolicy::ReadOnly => Ok(self.connect(database, true, reopen)),
+			VersionPolicy::Recreate => {
+				database.close();
+				self.delete_request(&factory).await?;
+				match self.open_request(&factory, version, upgrade).await? {
+					Some(database) => Ok(self.connect(database, false, reopen)),
+					None => Err(Error::Internal(
+						"the database was recreated by another connection".into(),
+					)),
+				}
+			}
+		}
+	}
+
+	/// Opens the database at `version`, returning `None` if the database is already at a newer version.
+	async fn open_request(
+		&self,
+		factory: &idb::Factory,
+		version: u32,
+		upgrade: UpgradeFn,
+	) -> Result<Option<idb::Database>, Error> {
+		let mut request = factory.open(&self.name, Some(version))?;
+		let failure = Rc::new(RefCell::new(None));
+		let seeding = Seeding::default();
+		// called when the database is being created for the first time, or when the version is old
+		request.on_upgrade_needed({
+			let failure = failure.clone();
+			let seeding = seeding.clone();
+			let on_created = self.on_created.clone();
+			move |event| {
+				let err = match upgrade(&event) {
+					Ok(()) => match (on_created, event.old_version()) {
+						// the database was just created, so seed it once the stores exist
+						(Some(on_created), Ok(0)) => match seed(&event, on_created, &seeding) {
+							Ok(()) => return,
+							Err(err) => err,
+						},
+						_ => return,
+					},
+					Err(err) => err,
+				};
+				let old = event.old_version().unwrap();
+				let new = event
+					.new_version()
+					.unwrap()
+					.map(|v| format!(" to v{v}"))
+					.unwrap_or_default();
+				log::error!(target: "database::client", "Failed to upgrade database from v{old}{new}: {err:?}");
+				abort(&event);
+				*failure.borrow_mut() = Some(err);
+			}
+		});
+		let blocked = Rc::new(Cell::new(false));
+		request.on_blocked({
+			let blocked = blocked.clone();
+			let callback = self.on_blocked.clone();
+			move |event| {
+				blocked.set(true);
+				if let Some(callback) = callback {
+					let (old, new) = versions(&event);
+					callback(old, new);
+				}
+			}
+		});
+		let result = match self.timeout {
+			None => request.into_future().await,
+			Some(timeout) => {
+				let opening = Box::pin(request.into_future());
+				let timer = Box::pin(crate::retry::sleep(timeout));
+				match future::select(opening, timer).await {
+					Either::Left((result, _)) => result,
+					Either::Right((slept, opening)) => {
+						// the request cannot be cancelled, so close the connection if it opens after all
+						wasm_bindgen_futures::spawn_local(async move {
+							if let Ok(database) = opening.await {
+								database.close();
+							}
+						});
+						slept?;
+						return Err(Error::OpenTimedOut {
+							timeout,
+							blocked: blocked.get(),
+						});
+					}
+				}
+			}
+		};
+		if let Some(err) = failure.take() {
+			return Err(err);
+		}
+		// the upgrade transaction has finished by the time the request succeeds, and so must the seeding
+		let seeded = seeding.take().map(|mut seeded| match seeded.try_recv() {
+			Ok(Some(seeded)) => seeded,
+			Ok(None) => Err(Error::Internal(
+				"the database was created while its on_created hook was still running".into(),
+			)),
+			Err(oneshot::Canceled) => Err(Error::Internal("the on_created hook was dropped".into())),
+		});
+		if let Some(Err(err)) = seeded {
+			if let Ok(database) = &result {
+				database.close();
+			}
+			return Err(err);
+		}
+		match result {
+			Ok(database) => Ok(Some(database)),
+			Err(err) if crate::error::dom_exception_name(&err).as_deref() == Some("VersionError") => Ok(None),
+			Err(err) => Err(err.into()),
+		}
+	}
+
+	fn connect(&self, database: idb::Database, read_only: bool, reopen: Reopen) -> Client {
+		let mut connection = Connection::new(database, read_only);
+		connection.reopen = Some(reopen);
+		if self.broadcast_changes {
+			match crate::changes::Broadcaster::new(&self.name) {
+				Ok(broadcaster) => connection.broadcaster = Some(broadcaster),
+				Err(err) => log::error!(target: "database::client", "Failed to broadcast changes: {err}"),
+			}
+		}
+		let state = connection.state.clone();
+		let database = &mut connection.database;
+		// the browser may close the connection, such as when the database is deleted in the browser's settings
+		database.on_close({
+			let state = state.clone();
+			let callback = self.on_close.clone();
+			move |_| {
+				state.set(ConnectionState::Gone);
+				if let Some(callback) = callback {
+					callback();
+				}
+			}
+		});
+		let close = self.close_on_version_change;
+		let callback = self.on_version_change.clone();
+		if close || callback.is_some() {
+			database.on_version_change(move |event| {
+				let event = wasm_bindgen::JsValue::from(event);
+				if close {
+					close_target(&event);
+					state.set(ConnectionState::Closed);
+				}
+				let Some(callback) = callback else {
+					return;
+				};
+				match VersionChangeEvent::try_from(event) {
+					Ok(event) => {
+						let (old, new) = versions(&event);
+						callback(old, new);
+					}
+					Err(err) => log::error!(target: "database::client", "Failed to read version change: {err:?}"),
+				}
+			});
+		}
+		Client(Rc::new(connection))
+	}
+}
+
+/// Runs the `on_created` hook in the upgrade transaction, sending its result to `seeding`.
+/// If the hook fails, the upgrade is aborted so that the database is not left half-seeded.
+fn seed(event: &VersionChangeEvent, on_created: SeedFn, seeding: &Seeding) -> Result<(), Error> {
+	let seeded = on_created(Client::upgrade_transaction(event)?);
+	let (sender, receiver) = oneshot::channel();
+	*seeding.borrow_mut() = Some(receiver);
+	let event = event.clone();
+	// the hook is polled as its requests succeed, while the upgrade transaction is still active
+	wasm_bindgen_futures::spawn_local(async move {
+		let seeded = seeded.await;
+		if let Err(err) = &seeded {
+			log::error!(target: "database::client", "Failed to seed new database: {err:?}");
+			abort(&event);
+		}
+		let _ = sender.send(seeded);
+	});
+	Ok(())
+}
+
+/// Aborting the upgrade leaves the database at its previous version, and fails the open request.
+fn abort(event: &VersionChangeEvent) {
+	match Client::upgrade_transaction(event) {
+		Ok(transaction) => {
+			let _ = transaction.0.abort();
+		}
+		Err(err) => log::error!(target: "database::client", "Failed to abort upgrade: {err:?}"),
+	}
+}
+
+/// Closes the database which a `versionchange` event was sent to.
+fn close_target(event: &wasm_bindgen::JsValue) {
+	let target = js_sys::Reflect::get(event, &wasm_bindgen::JsValue::from("target")).unwrap_or_default();
+	match idb::Database::try_from(target) {
+		Ok(database) => database.close(),
+		Err(err) => log::error!(target: "database::client", "Failed to close database for version change: {err:?}"),
+	}
+}
+
+fn versions(event: &VersionChangeEvent) -> (u32, Option<u32>) {
+	let old = event.old_version().unwrap_or_default();
+	let new = event.new_version().ok().flatten();
+	(old, new)
+}
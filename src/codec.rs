@@ -0,0 +1,132 @@
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use std::marker::PhantomData;
+
+/// Encodes records to bytes, for storing them as binary data with [`Encoded`] instead of as JS objects.
+/// Records which are not wrapped in [`Encoded`] are converted to JS objects with `serde_wasm_bindgen`.
+/// `Postcard` and `Cbor` are provided behind the `postcard` and `cbor` features,
+/// and other binary formats can implement this to store large records more quickly.
+pub trait Codec {
+	fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, String>;
+	fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String>;
+}
+
+/// Encodes records as JSON.
+pub struct Json;
+
+impl Codec for Json {
+	fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, String> {
+		serde_json::to_vec(value).map_err(|err| err.to_string())
+	}
+
+	fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+		serde_json::from_slice(bytes).map_err(|err| err.to_string())
+	}
+}
+
+/// Encodes records with [postcard](https://docs.rs/postcard), a compact format which is fast to encode and decode.
+/// The encoding is not self-describing, so changing the fields of a record makes existing records unreadable.
+#[cfg(feature = "postcard")]
+pub struct Postcard;
+
+#[cfg(feature = "postcard")]
+impl Codec for Postcard {
+	fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, String> {
+		postcard::to_allocvec(value).map_err(|err| err.to_string())
+	}
+
+	fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+		postcard::from_bytes(bytes).map_err(|err| err.to_string())
+	}
+}
+
+/// Encodes records as [CBOR](https://cbor.io), a self-describing binary format, so fields can be added to records
+/// the same way as with [`Json`].
+#[cfg(feature = "cbor")]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl Codec for Cbor {
+	fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, String> {
+		let mut bytes = Vec::new();
+		ciborium::into_writer(value, &mut bytes).map_err(|err| err.to_string())?;
+		Ok(bytes)
+	}
+
+	fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+		ciborium::from_reader(bytes).map_err(|err| err.to_string())
+	}
+}
+
+/// A record which is stored as a `Uint8Array` of its encoding with the codec `C`,
+/// which IndexedDB copies much faster than a deep JS object. It is a [wrapped record](crate::Store#wrapped-records).
+/// ```ignore
+/// static ASSETS: StoreDescriptor<Encoded<Asset, Json>> = StoreDescriptor::new("assets");
+///
+/// client.store(&ASSETS).put_with_key(&Encoded::new(asset), "logo").await?;
+/// let asset = client.store(&ASSETS).get("logo").await?.map(Encoded::into_inner);
+/// ```
+pub struct Encoded<T, C> {
+	value: T,
+	codec: PhantomData<fn() -> C>,
+}
+
+impl<T, C> Encoded<T, C> {
+	pub fn new(value: T) -> Self {
+		Self {
+			value,
+			codec: PhantomData,
+		}
+	}
+
+	pub fn into_inner(self) -> T {
+		self.value
+	}
+}
+
+impl<T, C> std::ops::Deref for Encoded<T, C> {
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.value
+	}
+}
+
+impl<T, C> std::ops::DerefMut for Encoded<T, C> {
+	fn deref_mut(&mut self) -> &mut T {
+		&mut self.value
+	}
+}
+
+impl<T: Clone, C> Clone for Encoded<T, C> {
+	fn clone(&self) -> Self {
+		Self::new(self.value.clone())
+	}
+}
+
+impl<T: std::fmt::Debug, C> std::fmt::Debug for Encoded<T, C> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		self.value.fmt(f)
+	}
+}
+
+impl<T: PartialEq, C> PartialEq for Encoded<T, C> {
+	fn eq(&self, other: &Self) -> bool {
+		self.value == other.value
+	}
+}
+
+impl<T: Serialize, C: Codec> Serialize for Encoded<T, C> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let bytes = C::encode(&self.value).map_err(serde::ser::Error::custom)?;
+		// stored as a Uint8Array regardless of the serializer options, which default to arrays of numbers
+		serde_wasm_bindgen::preserve::serialize(&js_sys::Uint8Array::from(bytes.as_slice()), serializer)
+	}
+}
+
+impl<'de, T: DeserializeOwned, C: Codec> Deserialize<'de> for Encoded<T, C> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let bytes = serde_wasm_bindgen::preserve::deserialize::<D, js_sys::Uint8Array>(deserializer)?;
+		let value = C::decode(&bytes.to_vec()).map_err(serde::de::Error::custom)?;
+		Ok(Self::new(value))
+	}
+}
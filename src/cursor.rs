@@ -1,11 +1,79 @@
-use super::Error;
+use super::{Error, IndexedKey, SerializerOptions};
 use futures_util::Future;
 use serde::{Deserialize, Serialize};
+use std::future::IntoFuture;
+use std::task::ready;
 use std::{pin::Pin, task::Poll};
+use wasm_bindgen::JsValue;
+
+pub use idb::CursorDirection;
+
+mod batch;
+use batch::Batch;
+
+/// The in-flight read-and-advance operation of a cursor stream,
+/// resolving to the cursor pointing at the next entry (if any) and the item that was read.
+pub(crate) type PendingAdvance<C, T> = Pin<Box<dyn Future<Output = Result<(Option<C>, T), idb::Error>>>>;
+
+/// An in-flight request which moves a cursor, resolving to the cursor if it has not reached its end.
+type PendingMove = Pin<Box<dyn Future<Output = Result<Option<idb::Cursor>, idb::Error>>>>;
+
+/// A predicate over the unparsed value of an entry, deciding if the entry is yielded.
+type RawFilter = Box<dyn Fn(&JsValue) -> bool>;
+
+/// The unparsed keys and value of the entry a cursor was pointing at.
+/// Yielded by [`Cursor::raw`], so that entries are only deserialized if the caller needs them.
+#[derive(Debug, Clone)]
+pub struct RawEntry {
+	pub(crate) key: JsValue,
+	/// The key of the record in its object store. This is the same as `key` unless the cursor is over an index.
+	pub(crate) primary_key: JsValue,
+	pub(crate) value: JsValue,
+}
+
+impl RawEntry {
+	/// The key of the entry. For cursors over an index, this is the index key of the entry.
+	pub fn key(&self) -> &JsValue {
+		&self.key
+	}
+
+	/// The key of the record in its object store.
+	pub fn primary_key(&self) -> &JsValue {
+		&self.primary_key
+	}
+
+	pub fn value(&self) -> &JsValue {
+		&self.value
+	}
+
+	/// Deserializes the key of the entry.
+	pub fn parse_key<K>(&self) -> Result<K, Error>
+	where
+		K: for<'de> Deserialize<'de>,
+	{
+		Ok(serde_wasm_bindgen::from_value::<K>(self.key.clone())?)
+	}
+
+	/// Deserializes the primary key of the entry.
+	pub fn parse_primary_key<K>(&self) -> Result<K, Error>
+	where
+		K: for<'de> Deserialize<'de>,
+	{
+		Ok(serde_wasm_bindgen::from_value::<K>(self.primary_key.clone())?)
+	}
+
+	/// Deserializes the value of the entry.
+	pub fn parse<V>(&self) -> Result<V, Error>
+	where
+		V: for<'de> Deserialize<'de>,
+	{
+		Ok(serde_wasm_bindgen::from_value::<V>(self.value.clone())?)
+	}
+}
 
 /// Iterates over the contents of a cursor provided by one of the `open_cursor` functions.
 /// You can iterate over it like an async iterator / stream:
-/// ```no_run
+/// ```ignore
 /// while let Some(entry) = cursor.next().await {
 ///   // ...
 /// }
@@ -14,127 +82,811 @@ use std::{pin::Pin, task::Poll};
 /// the database entry the cursor is during iteration:
 /// ```ignore
 /// while let Some(entry) = cursor.value()? {
-///   //let entry = cursor.update_value(new_value).await?;
-///   //cursor.delete_value().await?;
+///   cursor.update_value(&new_value).await?;
+///   // or cursor.delete_value().await?;
 ///   cursor.advance().await?;
 /// }
 /// ```
+/// The two styles should not be mixed on the same cursor, because the stream reads ahead:
+/// after an entry is yielded by the stream, the cursor is already pointing at the entry after it.
 pub struct Cursor<V> {
 	cursor: Option<idb::Cursor>,
-	marker: std::marker::PhantomData<V>,
-	pending: Option<Pin<Box<dyn Future<Output = Result<(Option<idb::Cursor>, wasm_bindgen::JsValue), idb::Error>>>>>,
+	direction: CursorDirection,
+	// `fn() -> V` keeps the cursor `Unpin` regardless of the value type, since values are never stored.
+	marker: std::marker::PhantomData<fn() -> V>,
+	/// The request moving the cursor to its next position, started when the previous entry was yielded.
+	pending: Option<PendingMove>,
+	skip: u32,
+	seek: Option<JsValue>,
+	/// The upper bound of the query the cursor was opened with, and if that bound is excluded.
+	/// The outer `None` means the query is not known, so the cursor cannot be batched.
+	upper: Option<Option<(JsValue, bool)>>,
+	batch: Option<Batch>,
+	/// The primary key of the entry which was most recently yielded.
+	primary_key: Option<JsValue>,
+	/// The number of entries which can still be yielded, if the cursor is limited.
+	remaining: Option<u32>,
+	filter: Option<RawFilter>,
+	/// The number of entries left in the cursor, if known, for the stream's size hint.
+	len: Option<u32>,
+	/// How values written through the cursor are serialized.
+	serializer: SerializerOptions,
 }
 
 impl<V> Cursor<V> {
 	pub fn new(cursor: Option<idb::Cursor>) -> Self {
+		// The direction is stashed because the cursor is moved out while it is advancing.
+		let direction = cursor.as_ref().and_then(|cursor| cursor.direction().ok());
 		Self {
 			cursor,
+			direction: direction.unwrap_or_default(),
 			marker: Default::default(),
 			pending: None,
+			skip: 0,
+			seek: None,
+			upper: None,
+			batch: None,
+			primary_key: None,
+			remaining: None,
+			filter: None,
+			len: None,
+			serializer: SerializerOptions::new(),
+		}
+	}
+
+	/// Records the query the cursor was opened with (`None` for the whole source),
+	/// so that [batched](Cursor::with_batch_size) reads stay within its range.
+	pub fn with_query(mut self, query: Option<&idb::Query>) -> Self {
+		self.upper = Some(match query {
+			None => None,
+			Some(idb::Query::Key(key)) => Some((key.clone(), false)),
+			Some(idb::Query::KeyRange(range)) => match range.upper() {
+				Ok(upper) if !upper.is_undefined() => Some((upper, range.upper_open())),
+				_ => None,
+			},
+		});
+		self
+	}
+
+	/// Reads `size` entries per IDB request (using `getAll`) and yields them from an in-memory buffer,
+	/// instead of making a round-trip for every entry.
+	///
+	/// Batching is only possible for forward cursors over an object store whose query is known
+	/// (see [`Cursor::with_query`]) and when called before the cursor is first polled;
+	/// other cursors are left unchanged and keep reading one entry per request. While batching, the cursor does not point at the
+	/// yielded entries, so `update_value` and `delete_value` have no effect.
+	pub fn with_batch_size(mut self, size: u32) -> Self {
+		let forward = matches!(self.direction, CursorDirection::Next | CursorDirection::NextUnique);
+		if !forward || self.pending.is_some() {
+			return self;
+		}
+		// without the query, getAll could read past the end of the cursor's range
+		let Some(upper) = self.upper.clone() else {
+			return self;
+		};
+		let Some(cursor) = &self.cursor else {
+			return self;
+		};
+		let Ok(store) = idb::ObjectStore::try_from(JsValue::from(cursor.source())) else {
+			return self;
+		};
+		let Ok(start) = cursor.key() else {
+			return self;
+		};
+		self.cursor = None;
+		self.batch = Some(Batch::new(store, size, start, upper));
+		self
+	}
+
+	/// Serializes the values written by [`Cursor::update_value`] and [`Cursor::for_each_mut`] with `options`,
+	/// such as the [`StoreDescriptor::serializer`](crate::StoreDescriptor::serializer) of the cursor's store.
+	pub fn with_serializer(mut self, options: SerializerOptions) -> Self {
+		self.serializer = options;
+		self
+	}
+
+	/// Provides the number of entries in the cursor (such as from `count()` on the same query and transaction),
+	/// so that the stream can report an accurate size hint.
+	pub fn with_len(mut self, len: u32) -> Self {
+		self.len = Some(len);
+		self
+	}
+
+	/// The direction the cursor was opened with.
+	pub fn direction(&self) -> CursorDirection {
+		self.direction
+	}
+
+	/// Skips the next `count` entries without reading or deserializing them.
+	/// The underlying cursor advances past all of them in a single request the next time the cursor is polled.
+	pub fn advance_by(&mut self, count: u32) {
+		self.skip = self.skip.saturating_add(count);
+	}
+
+	/// Skips the first `count` entries of the cursor, for offset-style pagination.
+	/// Unlike `StreamExt::skip`, the skipped entries are never deserialized.
+	pub fn skip(mut self, count: u32) -> Self {
+		self.advance_by(count);
+		self
+	}
+
+	/// Stops the stream after `count` entries have been yielded,
+	/// or manual iteration (including [`Cursor::for_each_mut`]) after `count` entries have been visited.
+	/// Unlike `StreamExt::take`, the underlying cursor is released as soon as the limit is reached,
+	/// so the transaction can complete without waiting for the stream to be dropped.
+	pub fn take(mut self, count: u32) -> Self {
+		self.remaining = Some(count);
+		self
+	}
+
+	/// Only yields entries whose unparsed value passes `predicate`, so that irrelevant records
+	/// can be skipped before they are deserialized. Filtered entries do not count towards [`Cursor::take`].
+	/// The predicate only applies to the stream, so manually iterating a filtered cursor
+	/// (with [`Cursor::value`], [`Cursor::advance`] or [`Cursor::for_each_mut`]) fails instead.
+	/// ```ignore
+	/// let cursor = cursor.filter_raw(|value| {
+	///   js_sys::Reflect::get(value, &"kind".into()).map_or(false, |kind| kind == "module")
+	/// });
+	/// ```
+	pub fn filter_raw(mut self, predicate: impl Fn(&JsValue) -> bool + 'static) -> Self {
+		self.filter = Some(Box::new(predicate));
+		self
+	}
+
+	/// Jumps forward to the first entry at or after `key` (in the direction of the cursor),
+	/// such as to resume processing after a checkpoint.
+	/// Seeking to a key the cursor has already passed has no effect.
+	pub fn seek<K: IndexedKey>(&mut self, key: &K) -> Result<(), Error> {
+		self.seek = Some(crate::key_range::to_key(key)?);
+		// the number of entries passed over by the seek is not known
+		self.len = None;
+		Ok(())
+	}
+
+	/// Returns true if there are no more entries to visit.
+	pub fn is_done(&self) -> bool {
+		match &self.batch {
+			Some(batch) => batch.is_done(),
+			None => self.cursor.is_none() && self.pending.is_none(),
 		}
 	}
 
-	pub async fn update_value(&self, new_value: &V) -> Result<(), Error>
+	/// The value of the entry the cursor is pointing at, or `None` if the cursor has reached the end.
+	pub fn value(&self) -> Result<Option<V>, Error>
 	where
-		V: Serialize + for<'de> Deserialize<'de>,
+		V: for<'de> Deserialize<'de>,
 	{
+		let Some(cursor) = &self.cursor else {
+			return Ok(None);
+		};
+		self.check_unfiltered()?;
+		if self.remaining == Some(0) {
+			return Ok(None);
+		}
+		// a cursor past its end has no key, while a stored value may itself be null
+		if cursor.key()?.is_undefined() {
+			return Ok(None);
+		}
+		Ok(Some(serde_wasm_bindgen::from_value::<V>(cursor.value()?)?))
+	}
+
+	/// The key of the entry the cursor is pointing at, or `None` if the cursor has reached the end.
+	pub fn key<K>(&self) -> Result<Option<K>, Error>
+	where
+		K: for<'de> Deserialize<'de>,
+	{
+		let Some(cursor) = &self.cursor else {
+			return Ok(None);
+		};
+		Ok(Some(serde_wasm_bindgen::from_value::<K>(cursor.key()?)?))
+	}
+
+	/// Moves the cursor to the next entry (applying any pending [`Cursor::seek`] or [`Cursor::advance_by`]),
+	/// for use when manually iterating with [`Cursor::value`].
+	pub async fn advance(&mut self) -> Result<(), Error> {
+		self.check_unfiltered()?;
+		// The stream has already started moving past the entry it last yielded.
+		if let Some(pending) = self.pending.take() {
+			self.cursor = pending.await?;
+			return Ok(());
+		}
+		if self.cursor.is_none() || self.visited() {
+			return Ok(());
+		}
 		let Some(cursor) = &self.cursor else {
 			return Ok(());
 		};
-		let js_value = serde_wasm_bindgen::to_value(new_value)?;
-		cursor.update(&js_value)?.await?;
+		// a seek replaces the step to the next entry, unless the cursor has already reached its target
+		let seek = match self.seek.take() {
+			Some(target) if !has_reached(self.direction, &target, &cursor.key()?)? => Some(target),
+			_ => None,
+		};
+		let Some(mut cursor) = self.cursor.take() else {
+			return Ok(());
+		};
+		let mut count = 1u32;
+		if let Some(target) = seek {
+			let Some(sought) = start_seek(cursor, &target).await? else {
+				return Ok(());
+			};
+			cursor = sought;
+			count = 0;
+		}
+		let count = count.saturating_add(std::mem::take(&mut self.skip));
+		if count == 0 {
+			self.cursor = Some(cursor);
+			return Ok(());
+		}
+		self.consumed(count);
+		self.cursor = start_advance(cursor, count).await?;
 		Ok(())
 	}
 
-	pub async fn delete_value(&self) -> Result<(), idb::Error> {
-		if let Some(cursor) = &self.cursor {
-			cursor.delete()?.await?;
+	/// Visits every remaining entry, allowing the callback to update or delete each record
+	/// through the provided handle before the cursor advances past it.
+	/// ```ignore
+	/// cursor.for_each_mut(|entry| {
+	///   if entry.value().is_expired() {
+	///     entry.delete();
+	///   }
+	/// }).await?;
+	/// ```
+	pub async fn for_each_mut<F>(&mut self, mut callback: F) -> Result<(), Error>
+	where
+		V: Serialize + for<'de> Deserialize<'de>,
+		F: FnMut(&mut CursorEntryMut<V>),
+	{
+		if self.batch.is_some() {
+			return Err(Error::Internal(
+				"batched cursors cannot modify the entries they visit".into(),
+			));
+		}
+		self.check_unfiltered()?;
+		// the stream may have already started moving to the next entry, which must land before it is read
+		if let Some(pending) = self.pending.take() {
+			self.cursor = pending.await?;
+		}
+		while let Some(value) = self.value()? {
+			let mut entry = CursorEntryMut { value, change: None };
+			callback(&mut entry);
+			match entry.change {
+				None => {}
+				Some(EntryChange::Update) => {
+					self.update_value(&entry.value).await?;
+				}
+				Some(EntryChange::Delete) => {
+					self.delete_value().await?;
+				}
+			}
+			self.advance().await?;
 		}
 		Ok(())
 	}
+
+	/// Replaces the record the cursor is pointing at.
+	/// Returns false if there was no record to update (the cursor is advancing or has reached its end).
+	pub async fn update_value(&self, new_value: &V) -> Result<bool, Error>
+	where
+		V: Serialize + for<'de> Deserialize<'de>,
+	{
+		let Some(cursor) = &self.cursor else {
+			return Ok(false);
+		};
+		let js_value = self.serializer.serialize(new_value)?;
+		cursor.update(&js_value)?.await?;
+		Ok(true)
+	}
+
+	/// Deletes the record the cursor is pointing at.
+	/// Returns false if there was no record to delete (the cursor is advancing or has reached its end).
+	pub async fn delete_value(&self) -> Result<bool, Error> {
+		let Some(cursor) = &self.cursor else {
+			return Ok(false);
+		};
+		cursor.delete()?.await?;
+		Ok(true)
+	}
+
+	/// Deletes the record the cursor is pointing at and moves to the next entry, returning its value.
+	/// Both requests are made together, so the cursor cannot be observed between them.
+	/// Returns `None` if the deleted record was the last one,
+	/// and fails if the cursor is not pointing at a record (it is batched or has reached its end).
+	/// ```ignore
+	/// let mut entry = cursor.value()?;
+	/// while let Some(value) = entry {
+	///   entry = match value.is_expired() {
+	///     true => cursor.delete_current_and_advance().await?,
+	///     false => { cursor.advance().await?; cursor.value()? }
+	///   };
+	/// }
+	/// ```
+	pub async fn delete_current_and_advance(&mut self) -> Result<Option<V>, Error>
+	where
+		V: for<'de> Deserialize<'de>,
+	{
+		self.check_unfiltered()?;
+		// the record to delete is the one the stream's pending move is heading to
+		if let Some(pending) = self.pending.take() {
+			self.cursor = pending.await?;
+		}
+		let Some(cursor) = self.cursor.take() else {
+			return Err(Error::Internal("the cursor is not pointing at a record".into()));
+		};
+		let delete = match cursor.delete() {
+			Ok(delete) => delete,
+			Err(err) => {
+				self.cursor = Some(cursor);
+				return Err(err.into());
+			}
+		};
+		if self.visited() {
+			delete.await?;
+			return Ok(None);
+		}
+		let count = 1u32.saturating_add(std::mem::take(&mut self.skip));
+		self.consumed(count);
+		let (_, cursor) = futures_util::future::try_join(delete.into_future(), start_advance(cursor, count)).await?;
+		self.cursor = cursor;
+		self.value()
+	}
 }
 
-impl<V> futures_util::stream::Stream for Cursor<V>
-where
-	V: for<'de> Deserialize<'de> + Unpin,
-{
-	type Item = V;
+/// A handle to the entry being visited by [`Cursor::for_each_mut`].
+/// Any update or deletion is applied to the record once the callback returns.
+pub struct CursorEntryMut<V> {
+	value: V,
+	change: Option<EntryChange>,
+}
 
-	fn poll_next(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Option<Self::Item>> {
-		// Find the pending query
-		let mut pending = match self.pending.take() {
-			// existing pending query means an operation took longer than immediate
-			Some(pending) => pending,
-			// the first poll and any poll following a successful first result,
-			// will have an invalid pending in this structure.
-			None => match self.cursor.take() {
-				// an empty cursor on first poll means no elements
-				None => return Poll::Ready(None),
-				// Construct the new pending operation using the stashed cursor.
-				Some(cursor) => Box::pin(async move {
-					// move the cursor in so this future can have a static lifetime
-					let cursor = cursor;
-					// due to changes in idb crate, we must advance to the next entry
-					// before determining if the current entry is not a duplicate (the end of the cursor).
-					let js_value = cursor.value()?;
-					// advance to the next entry
-					let adv_request = cursor.advance(1);
-					// if this causes and advancement failure, then we've reached the end of the cursor
-					if let Err(idb::Error::CursorAdvanceFailed(_)) = &adv_request {
-						return Ok((None, js_value));
-					}
-					// other errors must be bubbled up
-					let adv_request = adv_request?;
-					let adv_request = adv_request.await;
-					// if this causes and advancement failure, then we've reached the end of the cursor
-					if let Err(idb::Error::CursorAdvanceFailed(_)) = &adv_request {
-						return Ok((None, js_value));
-					}
-					// other errors must be bubbled up
-					let cursor = adv_request?;
-					// no errors during advancement, return the current entry and the cursor pointing to the next entry
-					Ok((cursor, js_value))
-				}),
-			},
+enum EntryChange {
+	Update,
+	Delete,
+}
+
+impl<V> CursorEntryMut<V> {
+	pub fn value(&self) -> &V {
+		&self.value
+	}
+
+	/// Provides mutable access to the value, which will be written back to the record.
+	pub fn value_mut(&mut self) -> &mut V {
+		self.change = Some(EntryChange::Update);
+		&mut self.value
+	}
+
+	/// Replaces the record with a new value.
+	pub fn update(&mut self, value: V) {
+		self.value = value;
+		self.change = Some(EntryChange::Update);
+	}
+
+	/// Deletes the record, discarding any prior update.
+	pub fn delete(&mut self) {
+		self.change = Some(EntryChange::Delete);
+	}
+}
+
+impl<V> Cursor<V> {
+	/// Converts this cursor into a stream which yields any error encountered while reading or parsing entries,
+	/// rather than logging the error and ending the stream.
+	/// The cursor is released when an error is yielded (including a record which fails to deserialize),
+	/// so the next poll returns `None`. To keep going past records which do not parse, read them through
+	/// [`Cursor::raw`] and parse each [`RawEntry`] instead.
+	pub fn into_try_stream(self) -> TryCursor<V> {
+		TryCursor(self)
+	}
+
+	/// The primary key of the entry which was most recently yielded by the stream.
+	/// For cursors over an index, this is the key of the record in the object store,
+	/// which can be used for follow-up updates or deletes on the store.
+	pub fn primary_key<K>(&self) -> Result<Option<K>, Error>
+	where
+		K: for<'de> Deserialize<'de>,
+	{
+		match &self.primary_key {
+			None => Ok(None),
+			Some(key) => Ok(Some(serde_wasm_bindgen::from_value::<K>(key.clone())?)),
+		}
+	}
+
+	/// Converts this cursor into a stream of unparsed entries, which can be inspected or counted cheaply
+	/// and deserialized with [`RawEntry::parse`] only when needed.
+	pub fn raw(self) -> RawCursor<V> {
+		RawCursor(self)
+	}
+
+	/// Converts this cursor into a stream of the key and value of each entry.
+	/// For cursors over an index, the key is the index key of the entry.
+	pub fn entries<K>(self) -> CursorEntries<K, V> {
+		CursorEntries {
+			cursor: self,
+			primary: false,
+			marker: Default::default(),
+		}
+	}
+
+	/// Converts this cursor into a stream of the primary key and value of each entry.
+	/// For cursors over an object store, this is the same as [`Cursor::entries`].
+	pub fn primary_entries<K>(self) -> CursorEntries<K, V> {
+		CursorEntries {
+			cursor: self,
+			primary: true,
+			marker: Default::default(),
+		}
+	}
+
+	fn poll_next_value(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Option<Result<V, Error>>>
+	where
+		V: for<'de> Deserialize<'de>,
+	{
+		let next = ready!(self.poll_next_raw(cx)).map(|entry| Ok(serde_wasm_bindgen::from_value::<V>(entry?.value)?));
+		if let Some(Err(_)) = &next {
+			self.release();
+		}
+		Poll::Ready(next)
+	}
+
+	/// Stops the cursor, so that it yields no more entries.
+	fn release(&mut self) {
+		self.cursor = None;
+		self.pending = None;
+		self.batch = None;
+	}
+
+	/// Records that the cursor has moved past `count` entries.
+	fn consumed(&mut self, count: u32) {
+		if let Some(len) = &mut self.len {
+			*len = len.saturating_sub(count);
+		}
+	}
+
+	/// Fails if the cursor has a [`Cursor::filter_raw`] predicate, which manual iteration cannot apply.
+	fn check_unfiltered(&self) -> Result<(), Error> {
+		match self.filter {
+			Some(_) => Err(Error::Internal("filtered cursors can only be read as a stream".into())),
+			None => Ok(()),
+		}
+	}
+
+	/// Counts the entry the cursor is pointing at towards [`Cursor::take`] when iterating manually,
+	/// returning true (and releasing the cursor) if no more entries may be visited.
+	fn visited(&mut self) -> bool {
+		let Some(remaining) = &mut self.remaining else {
+			return false;
 		};
+		*remaining = remaining.saturating_sub(1);
+		let done = *remaining == 0;
+		if done {
+			self.release();
+		}
+		done
+	}
 
-		// Process any pending advancement future first.
-		// If there is a future here, it means we are waiting for the underlying IDB cursor
-		// to finish advancing before parsing the current value.
-		// This operation may have been from a previous poll, or was just created above.
-		let js_value = match pending.as_mut().poll(cx) {
-			// the cursor is still advancing, poll the stream later
-			Poll::Pending => {
-				self.pending = Some(pending);
-				return Poll::Pending;
+	fn is_terminated(&self) -> bool {
+		self.remaining == Some(0) || self.is_done()
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		if self.is_terminated() {
+			return (0, Some(0));
+		}
+		let len = self.len.map(|len| len.saturating_sub(self.skip));
+		let upper = match (len, self.remaining) {
+			(Some(len), Some(remaining)) => Some(len.min(remaining)),
+			(len, remaining) => len.or(remaining),
+		};
+		// entries may be filtered or sought past, so only the upper bound is known
+		let lower = match (len, self.filter.is_some() || self.seek.is_some()) {
+			(Some(_), false) => upper.unwrap_or_default(),
+			_ => 0,
+		};
+		(lower as usize, upper.map(|upper| upper as usize))
+	}
+
+	/// Reads the next entry. The cursor is stopped after an error, so streams always end after the first error.
+	pub(crate) fn poll_next_raw(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Option<Result<RawEntry, Error>>> {
+		let next = self.poll_next_unfiltered(cx);
+		if let Poll::Ready(Some(Err(_))) = &next {
+			self.release();
+		}
+		next
+	}
+
+	fn poll_next_unfiltered(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Option<Result<RawEntry, Error>>> {
+		loop {
+			if self.remaining == Some(0) {
+				return Poll::Ready(None);
 			}
-			// found an error either getting a value or advancing to the next item
-			Poll::Ready(Err(err)) => {
-				log::error!(target: "cursor", "Failed to query next entry from cursor: {err:?}");
+
+			let entry = match &mut self.batch {
+				Some(batch) => ready!(batch.poll_next(cx)),
+				None => ready!(self.poll_cursor(cx)),
+			};
+			let entry = match entry {
+				Ok(entry) => entry,
+				Err(err) => return Poll::Ready(Some(Err(err))),
+			};
+
+			// No entry or no key, so we've reached end-of-stream.
+			let Some(entry) = entry.filter(|entry| !entry.key.is_undefined()) else {
 				return Poll::Ready(None);
+			};
+			self.consumed(1);
+
+			// A seek or skip requested while this entry was being read still applies to it.
+			if let Some(target) = &self.seek {
+				match has_reached(self.direction, target, &entry.key) {
+					Ok(true) => self.seek = None,
+					Ok(false) => continue,
+					Err(err) => return Poll::Ready(Some(Err(err.into()))),
+				}
 			}
-			// we found a value; the next cursor and the current value are provided from the query
-			Poll::Ready(Ok((cursor, value))) => {
-				self.cursor = cursor;
-				value
+			if self.skip > 0 {
+				self.skip -= 1;
+				continue;
+			}
+			if let Some(filter) = &self.filter {
+				if !filter(&entry.value) {
+					continue;
+				}
 			}
-		};
 
-		// Value is empty, so we've reached end-of-stream.
-		if js_value.is_null() {
-			return Poll::Ready(None);
+			if let Some(remaining) = &mut self.remaining {
+				*remaining -= 1;
+				if *remaining == 0 {
+					// Release the cursor so no more requests are made against the transaction.
+					self.release();
+				}
+			}
+
+			// Return the found entry, while advancement run in the background.
+			self.primary_key = Some(entry.primary_key.clone());
+			return Poll::Ready(Some(Ok(entry)));
 		}
+	}
 
-		// Parse the valid JSValue as the desired struct type.
-		let value = match serde_wasm_bindgen::from_value::<V>(js_value) {
-			Ok(value) => value,
-			Err(err) => {
-				log::error!(target: "cursor", "Failed to parse database value: {err:?}");
-				return Poll::Ready(None);
+	fn poll_cursor(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Result<Option<RawEntry>, Error>> {
+		loop {
+			// Wait for the cursor to finish moving, if it was advanced or repositioned by a previous poll.
+			if let Some(pending) = &mut self.pending {
+				let cursor = match pending.as_mut().poll(cx) {
+					Poll::Pending => return Poll::Pending,
+					Poll::Ready(result) => result,
+				};
+				self.pending = None;
+				self.cursor = cursor?;
+			}
+
+			// An empty cursor means there are no more elements.
+			let Some(cursor) = self.cursor.take() else {
+				return Poll::Ready(Ok(None));
+			};
+
+			// Apply any seek or skip before reading the entry the cursor is pointing at.
+			if let Some(target) = self.seek.take() {
+				// continuing to a key which is not ahead of the cursor is an error, so only do so if the cursor is behind it
+				if !has_reached(self.direction, &target, &cursor.key()?)? {
+					self.pending = Some(start_seek(cursor, &target));
+					continue;
+				}
+			}
+			if self.skip > 0 {
+				let count = std::mem::take(&mut self.skip);
+				self.consumed(count);
+				self.pending = Some(start_advance(cursor, count));
+				continue;
+			}
+
+			let entry = RawEntry {
+				key: cursor.key()?,
+				primary_key: cursor.primary_key()?,
+				value: cursor.value()?,
+			};
+
+			// Begin advancing to the next entry while the consumer handles this one,
+			// unless this is the last entry the cursor is allowed to yield (and it cannot be filtered out).
+			// If the request cannot be made, the error is reported on the next poll so this entry is not lost.
+			if self.remaining != Some(1) || self.filter.is_some() {
+				self.pending = Some(start_advance(cursor, 1));
+			}
+
+			return Poll::Ready(Ok(Some(entry)));
+		}
+	}
+}
+
+/// Moves the cursor forward by `count` entries. The request is made immediately,
+/// and the returned future resolves to `None` if the cursor has reached its end.
+fn start_advance(cursor: idb::Cursor, count: u32) -> PendingMove {
+	let request = match cursor.advance(count) {
+		// the transaction finishing is not the end of the cursor, so must be reported
+		Err(err) if crate::error::is_transaction_inactive(&err) => {
+			return Box::pin(futures_util::future::ready(Err(err)))
+		}
+		// if this causes and advancement failure, then we've reached the end of the cursor
+		Err(idb::Error::CursorAdvanceFailed(_)) => return Box::pin(futures_util::future::ready(Ok(None))),
+		// other errors must be bubbled up
+		Err(err) => return Box::pin(futures_util::future::ready(Err(err))),
+		Ok(request) => request,
+	};
+	Box::pin(async move {
+		match request.await {
+			Err(err) if crate::error::is_transaction_inactive(&err) => Err(err),
+			Err(idb::Error::CursorAdvanceFailed(_)) => Ok(None),
+			result => result,
+		}
+	})
+}
+
+/// Moves the cursor to the first entry at or after `target`. The request is made immediately.
+fn start_seek(cursor: idb::Cursor, target: &JsValue) -> PendingMove {
+	match cursor.next(Some(target)) {
+		Err(err) => Box::pin(futures_util::future::ready(Err(err))),
+		Ok(request) => Box::pin(async move { request.await }),
+	}
+}
+
+/// Returns true if `key` is at or beyond `target`, when moving in the provided direction.
+fn has_reached(direction: CursorDirection, target: &JsValue, key: &JsValue) -> Result<bool, idb::Error> {
+	let remaining = match direction {
+		CursorDirection::Next | CursorDirection::NextUnique => idb::KeyRange::lower_bound(target, None)?,
+		CursorDirection::Prev | CursorDirection::PrevUnique => idb::KeyRange::upper_bound(target, None)?,
+	};
+	remaining.includes(key)
+}
+
+impl<V> futures_util::stream::Stream for Cursor<V>
+where
+	V: for<'de> Deserialize<'de>,
+{
+	type Item = V;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Option<Self::Item>> {
+		match self.poll_next_value(cx) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(None) => Poll::Ready(None),
+			Poll::Ready(Some(Ok(value))) => Poll::Ready(Some(value)),
+			Poll::Ready(Some(Err(err))) => {
+				log::error!(target: "cursor", "Failed to read next entry from cursor: {err:?}");
+				Poll::Ready(None)
 			}
+		}
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		Cursor::size_hint(self)
+	}
+}
+
+impl<V> futures_util::stream::FusedStream for Cursor<V>
+where
+	V: for<'de> Deserialize<'de>,
+{
+	fn is_terminated(&self) -> bool {
+		Cursor::is_terminated(self)
+	}
+}
+
+/// A [`Cursor`] which yields `Result<V, Error>`, so that failures can be distinguished from the end of the data.
+/// Created by [`Cursor::into_try_stream`].
+pub struct TryCursor<V>(Cursor<V>);
+
+impl<V> TryCursor<V> {
+	pub fn into_inner(self) -> Cursor<V> {
+		self.0
+	}
+}
+
+impl<V> futures_util::stream::Stream for TryCursor<V>
+where
+	V: for<'de> Deserialize<'de>,
+{
+	type Item = Result<V, Error>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Option<Self::Item>> {
+		self.0.poll_next_value(cx)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.0.size_hint()
+	}
+}
+
+impl<V> futures_util::stream::FusedStream for TryCursor<V>
+where
+	V: for<'de> Deserialize<'de>,
+{
+	fn is_terminated(&self) -> bool {
+		self.0.is_terminated()
+	}
+}
+
+/// A [`Cursor`] which yields the key (or primary key) and value of each entry.
+/// Created by [`Cursor::entries`] and [`Cursor::primary_entries`].
+pub struct CursorEntries<K, V> {
+	cursor: Cursor<V>,
+	primary: bool,
+	marker: std::marker::PhantomData<fn() -> K>,
+}
+
+impl<K, V> futures_util::stream::Stream for CursorEntries<K, V>
+where
+	K: for<'de> Deserialize<'de>,
+	V: for<'de> Deserialize<'de>,
+{
+	type Item = (K, V);
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Option<Self::Item>> {
+		let entry = match self.cursor.poll_next_raw(cx) {
+			Poll::Pending => return Poll::Pending,
+			Poll::Ready(None) => return Poll::Ready(None),
+			Poll::Ready(Some(entry)) => entry,
 		};
+		let primary = self.primary;
+		let parsed = entry.and_then(|entry| {
+			let key = match primary {
+				true => entry.primary_key,
+				false => entry.key,
+			};
+			let key = serde_wasm_bindgen::from_value::<K>(key)?;
+			let value = serde_wasm_bindgen::from_value::<V>(entry.value)?;
+			Ok((key, value))
+		});
+		match parsed {
+			Ok(entry) => Poll::Ready(Some(entry)),
+			Err(err) => {
+				log::error!(target: "cursor", "Failed to read next entry from cursor: {err:?}");
+				self.cursor.release();
+				Poll::Ready(None)
+			}
+		}
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.cursor.size_hint()
+	}
+}
+
+impl<K, V> futures_util::stream::FusedStream for CursorEntries<K, V>
+where
+	K: for<'de> Deserialize<'de>,
+	V: for<'de> Deserialize<'de>,
+{
+	fn is_terminated(&self) -> bool {
+		self.cursor.is_terminated()
+	}
+}
+
+/// A [`Cursor`] which yields each entry without deserializing it. Created by [`Cursor::raw`].
+pub struct RawCursor<V>(Cursor<V>);
+
+impl<V> RawCursor<V> {
+	pub fn into_inner(self) -> Cursor<V> {
+		self.0
+	}
+}
+
+impl<V> futures_util::stream::Stream for RawCursor<V> {
+	type Item = RawEntry;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Option<Self::Item>> {
+		match self.0.poll_next_raw(cx) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(None) => Poll::Ready(None),
+			Poll::Ready(Some(Ok(entry))) => Poll::Ready(Some(entry)),
+			Poll::Ready(Some(Err(err))) => {
+				log::error!(target: "cursor", "Failed to read next entry from cursor: {err:?}");
+				Poll::Ready(None)
+			}
+		}
+	}
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.0.size_hint()
+	}
+}
 
-		// Return the found value, while advancement run in the background.
-		return Poll::Ready(Some(value));
+impl<V> futures_util::stream::FusedStream for RawCursor<V> {
+	fn is_terminated(&self) -> bool {
+		self.0.is_terminated()
 	}
 }
@@ -0,0 +1,107 @@
+use super::RawEntry;
+use crate::Error;
+use futures_util::Future;
+use std::{collections::VecDeque, future::IntoFuture, pin::Pin, task::Poll};
+use wasm_bindgen::JsValue;
+
+type PendingBatch = Pin<Box<dyn Future<Output = Result<Vec<RawEntry>, idb::Error>>>>;
+
+/// Reads the entries of an object store in batches using `getAll`/`getAllKeys`,
+/// yielding them one at a time from an in-memory buffer.
+pub(crate) struct Batch {
+	store: idb::ObjectStore,
+	size: u32,
+	/// The key to start the next batch from, and if that key is excluded from the batch.
+	lower: Option<(JsValue, bool)>,
+	/// The bound of the range the cursor was opened with, and if that bound is excluded.
+	upper: Option<(JsValue, bool)>,
+	buffer: VecDeque<RawEntry>,
+	pending: Option<PendingBatch>,
+}
+
+impl Batch {
+	/// Starts batching from the entry a cursor is pointing at.
+	pub(crate) fn new(store: idb::ObjectStore, size: u32, start: JsValue, upper: Option<(JsValue, bool)>) -> Self {
+		Self {
+			store,
+			size: size.max(1),
+			lower: Some((start, false)),
+			upper,
+			buffer: VecDeque::new(),
+			pending: None,
+		}
+	}
+
+	pub(crate) fn is_done(&self) -> bool {
+		self.buffer.is_empty() && self.pending.is_none() && self.lower.is_none()
+	}
+
+	pub(crate) fn poll_next(&mut self, cx: &mut std::task::Context<'_>) -> Poll<Result<Option<RawEntry>, Error>> {
+		loop {
+			if let Some(entry) = self.buffer.pop_front() {
+				return Poll::Ready(Ok(Some(entry)));
+			}
+
+			let mut pending = match self.pending.take() {
+				Some(pending) => pending,
+				None => match self.lower.take() {
+					// the previous batch was the last one in the range
+					None => return Poll::Ready(Ok(None)),
+					Some(lower) => Box::pin(fetch(self.store.clone(), lower, self.upper.clone(), self.size)),
+				},
+			};
+
+			let entries = match pending.as_mut().poll(cx) {
+				Poll::Pending => {
+					self.pending = Some(pending);
+					return Poll::Pending;
+				}
+				Poll::Ready(Err(err)) => return Poll::Ready(Err(err.into())),
+				Poll::Ready(Ok(entries)) => entries,
+			};
+
+			// A full batch means there may be more entries after the last key.
+			if entries.len() as u32 >= self.size {
+				if let Some(last) = entries.last() {
+					if self.is_before_upper(&last.key)? {
+						self.lower = Some((last.key.clone(), true));
+					}
+				}
+			}
+			if entries.is_empty() {
+				return Poll::Ready(Ok(None));
+			}
+			self.buffer.extend(entries);
+		}
+	}
+
+	fn is_before_upper(&self, key: &JsValue) -> Result<bool, Error> {
+		let Some((upper, _)) = &self.upper else {
+			return Ok(true);
+		};
+		Ok(idb::KeyRange::upper_bound(upper, Some(true))?.includes(key)?)
+	}
+}
+
+async fn fetch(
+	store: idb::ObjectStore,
+	(lower, lower_open): (JsValue, bool),
+	upper: Option<(JsValue, bool)>,
+	size: u32,
+) -> Result<Vec<RawEntry>, idb::Error> {
+	let range = match upper {
+		None => idb::KeyRange::lower_bound(&lower, Some(lower_open))?,
+		Some((upper, upper_open)) => idb::KeyRange::bound(&lower, &upper, Some(lower_open), Some(upper_open))?,
+	};
+	// both requests are queued before awaiting either, so a batch costs a single round-trip
+	let keys = store.get_all_keys(Some(idb::Query::KeyRange(range.clone())), Some(size))?;
+	let values = store.get_all(Some(idb::Query::KeyRange(range)), Some(size))?;
+	let (keys, values) = futures_util::future::try_join(keys.into_future(), values.into_future()).await?;
+	let entries = keys.into_iter().zip(values);
+	let entries = entries.map(|(key, value)| RawEntry {
+		primary_key: key.clone(),
+		key,
+		value,
+	});
+	Ok(entries.collect())
+}
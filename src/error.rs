@@ -1,36 +1,189 @@
+use wasm_bindgen::JsValue;
+
 #[derive(thiserror::Error, Debug, Clone, PartialEq)]
 pub enum Error {
 	#[error("{0}")]
 	Internal(String),
 	#[error("{0}")]
 	Serialization(String),
+	#[error("The transaction has already finished.")]
+	TransactionInactive,
+	/// The browser aborted the transaction, such as under memory pressure. Trying again may succeed.
+	#[error("The transaction was aborted: {0}")]
+	TransactionAborted(String),
+	#[error("The database is at version {found}, which is newer than the latest supported version {supported}.")]
+	VersionTooNew { found: u32, supported: u32 },
+	#[error("The database was opened read-only.")]
+	ReadOnly,
+	/// The database didn't open within the [timeout](crate::ClientBuilder::timeout).
+	/// If `blocked`, another connection was keeping the database from being upgraded;
+	/// otherwise the open request (or the upgrade itself) was still pending.
+	#[error("The database did not open within {timeout:?} (blocked by another connection: {blocked}).")]
+	OpenTimedOut {
+		timeout: std::time::Duration,
+		blocked: bool,
+	},
+	#[error("The database connection is closed.")]
+	Closed,
+	/// The browser closed the connection, such as because the database was deleted in the devtools or by another tab.
+	/// The database can be created again with [`Client::reopen`](crate::Client::reopen).
+	#[error("The database connection was closed by the browser.")]
+	DatabaseGone,
+	#[error("The object store {0:?} is not in the scope of the transaction.")]
+	StoreNotInScope(String),
+	#[error("No database is registered with the name {0:?}.")]
+	UnregisteredDatabase(String),
+	/// The origin has used all of its storage quota, so nothing more can be written.
+	#[error("The storage quota has been exceeded.")]
+	QuotaExceeded,
+	/// The browser does not implement a feature, such as `indexedDB.databases()` in older browsers.
+	#[error("{0} is not supported by this browser.")]
+	Unsupported(String),
+	/// A record could not be added because one with the same key, or the same value of a unique index,
+	/// already exists. `key` is the key of the new record, as JSON.
+	#[error("A record with the key {key} already exists.")]
+	KeyAlreadyExists { key: String },
+	/// A conditional write was rejected because the record was changed since it was read.
+	/// `current` is the version of the stored record, or `None` if there is no record.
+	#[error("The record was changed by another write (current version: {current:?}).")]
+	Conflict { current: Option<u64> },
+	/// A record was written at a key of a store with a key path, but the key path of the record holds a different key.
+	/// Both keys are JSON.
+	#[error("The record has the key {found}, but was written at the key {expected}.")]
+	KeyMismatch { expected: String, found: String },
+	#[error(transparent)]
+	MissingVersion(#[from] MissingVersion),
+	/// Upgrading the database to `version` failed, so the upgrade was aborted and the database left at its previous version.
+	#[error("Failed to migrate database to v{version}: {source}")]
+	MigrationFailed { version: u32, source: Box<Error> },
+}
+
+impl Error {
+	/// True if the operation failed for a reason which may not happen again, so it is worth retrying.
+	pub fn is_transient(&self) -> bool {
+		matches!(self, Self::TransactionAborted(_))
+	}
+
+	pub(crate) fn migration_failed(version: u32, source: Error) -> Self {
+		Self::MigrationFailed {
+			version,
+			source: Box::new(source),
+		}
+	}
 }
 
 impl From<idb::Error> for Error {
 	fn from(value: idb::Error) -> Self {
-		Self::Internal(value.to_string())
+		match dom_exception_name(&value).as_deref() {
+			Some("TransactionInactiveError") => Self::TransactionInactive,
+			Some("AbortError" | "UnknownError") => Self::TransactionAborted(value.to_string()),
+			Some("QuotaExceededError") => Self::QuotaExceeded,
+			_ => Self::Internal(value.to_string()),
+		}
 	}
 }
 
-impl From<serde_wasm_bindgen::Error> for Error {
-	fn from(value: serde_wasm_bindgen::Error) -> Self {
-		Self::Serialization(value.to_string())
+/// Returns true if a request failed because its transaction had already committed or aborted.
+pub(crate) fn is_transaction_inactive(error: &idb::Error) -> bool {
+	dom_exception_name(error).as_deref() == Some("TransactionInactiveError")
+}
+
+/// The name of the DOM exception which caused a request to fail, if the error carries one.
+pub(crate) fn dom_exception_name(error: &idb::Error) -> Option<String> {
+	use idb::Error::*;
+	let exception = match error {
+		DomException(exception) => return Some(exception.name()),
+		AddFailed(value)
+		| ClearFailed(value)
+		| CountFailed(value)
+		| CursorAdvanceFailed(value)
+		| CursorContinueFailed(value)
+		| DeleteFailed(value)
+		| GetAllFailed(value)
+		| GetAllKeysFailed(value)
+		| GetFailed(value)
+		| GetKeyFailed(value)
+		| OpenCursorFailed(value)
+		| OpenKeyCursorFailed(value)
+		| TransactionCommitError(value)
+		| TransactionOpenFailed(value)
+		| UpdateFailed(value) => value,
+		_ => return None,
+	};
+	js_sys::Reflect::get(exception, &"name".into()).ok()?.as_string()
+}
+
+/// Converts the error of an `add` request, reporting a `ConstraintError` as [`Error::KeyAlreadyExists`].
+/// `key` is the key passed to the request, if the store has no key path.
+pub(crate) fn add_failed(error: idb::Error, store: &idb::ObjectStore, value: &JsValue, key: Option<&JsValue>) -> Error {
+	if dom_exception_name(&error).as_deref() != Some("ConstraintError") {
+		return error.into();
 	}
+	let key = match key {
+		Some(key) => Some(key.clone()),
+		None => store.key_path().ok().flatten().map(|key_path| key_in(value, &key_path)),
+	};
+	let key = key
+		.and_then(|key| js_sys::JSON::stringify(&key).ok())
+		.and_then(|key| key.as_string())
+		.unwrap_or_default();
+	Error::KeyAlreadyExists { key }
 }
 
-#[derive(thiserror::Error, Debug, Clone)]
-pub enum UpgradeError {
-	#[error(transparent)]
-	Internal(#[from] crate::Error),
-	#[error(transparent)]
-	MissingVersion(#[from] MissingVersion),
+/// Fails with [`Error::KeyMismatch`] unless the key of `value` at `key_path` is `expected`.
+pub(crate) fn check_key(value: &JsValue, key_path: &idb::KeyPath, expected: &JsValue) -> Result<(), Error> {
+	let found = key_in(value, key_path);
+	let factory = js_sys::Reflect::get(&js_sys::global(), &"indexedDB".into()).unwrap_or(JsValue::UNDEFINED);
+	// cmp throws if the record has no valid key at all
+	if let Ok(order) = crate::js::call(&factory, "cmp", &[found.clone(), expected.clone()]) {
+		if order.as_f64() == Some(0.0) {
+			return Ok(());
+		}
+	}
+	let json = |key: &JsValue| {
+		js_sys::JSON::stringify(key)
+			.ok()
+			.and_then(|key| key.as_string())
+			.unwrap_or_else(|| "undefined".into())
+	};
+	Err(Error::KeyMismatch {
+		expected: json(expected),
+		found: json(&found),
+	})
 }
-impl From<idb::Error> for UpgradeError {
-	fn from(value: idb::Error) -> Self {
-		Self::Internal(value.into())
+
+/// Reads the key of a record at `key_path`, the same way the browser does.
+fn key_in(value: &JsValue, key_path: &idb::KeyPath) -> JsValue {
+	let read = |path: &str| {
+		path.split('.').fold(value.clone(), |value, field| {
+			js_sys::Reflect::get(&value, &field.into()).unwrap_or(JsValue::UNDEFINED)
+		})
+	};
+	match key_path {
+		idb::KeyPath::Single(path) => read(path),
+		idb::KeyPath::Array(paths) => paths.iter().map(|path| read(path)).collect::<js_sys::Array>().into(),
+	}
+}
+
+/// Converts a `DOMException` (such as the `error` of a failed request or transaction) to an error.
+pub(crate) fn from_exception(exception: &JsValue) -> Error {
+	let read = |field: &str| js_sys::Reflect::get(exception, &field.into()).ok()?.as_string();
+	let name = read("name").unwrap_or_default();
+	let message = format!("{name}: {}", read("message").unwrap_or_default());
+	match name.as_str() {
+		"TransactionInactiveError" => Error::TransactionInactive,
+		"AbortError" | "UnknownError" => Error::TransactionAborted(message),
+		"QuotaExceededError" => Error::QuotaExceeded,
+		_ => Error::Internal(message),
 	}
 }
 
-#[derive(thiserror::Error, Debug, Clone)]
+impl From<serde_wasm_bindgen::Error> for Error {
+	fn from(value: serde_wasm_bindgen::Error) -> Self {
+		Self::Serialization(value.to_string())
+	}
+}
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
 #[error("Schema is missing version {0}.")]
 pub struct MissingVersion(pub u32);
@@ -1,18 +1,19 @@
-use super::super::{Cursor, Error, Index, IndexType, Record};
+use super::super::{Cursor, CursorDirection, Error, Index, IndexType, IndexedKey, KeyCursor, KeyRange, Record};
 use futures_util::future::LocalBoxFuture;
+use std::future::IntoFuture;
 use wasm_bindgen::JsValue;
 
 pub trait ObjectStoreExt {
 	fn get_record<'store, V>(
 		&'store self,
-		key: impl Into<JsValue> + 'store,
+		key: &'store (impl IndexedKey + ?Sized),
 	) -> LocalBoxFuture<'store, Result<Option<V>, Error>>
 	where
 		V: Record + serde::de::DeserializeOwned;
 
 	fn delete_record<'store>(
 		&'store self,
-		key: impl Into<JsValue> + 'store,
+		key: &'store (impl IndexedKey + ?Sized),
 	) -> LocalBoxFuture<'store, Result<(), Error>>;
 
 	fn add_record<'store, V>(&'store self, record: &'store V) -> LocalBoxFuture<'store, Result<(), Error>>
@@ -36,18 +37,44 @@ pub trait ObjectStoreExt {
 	fn create_index_of<T: IndexType>(&self, params: Option<idb::IndexParams>) -> Result<idb::Index, idb::Error>;
 	fn index_of<T: IndexType>(&self) -> Result<Index<T>, idb::Error>;
 	fn cursor_all<'store, V>(&'store self) -> LocalBoxFuture<'store, Result<Cursor<V>, Error>>;
+	/// Opens a cursor over the whole store which knows how many entries it has, for an accurate size hint.
+	fn cursor_counted<'store, V>(&'store self) -> LocalBoxFuture<'store, Result<Cursor<V>, Error>>;
+	fn key_cursor_all<'store, K>(&'store self) -> LocalBoxFuture<'store, Result<KeyCursor<K>, Error>>;
+	fn cursor_directed<'store, V>(
+		&'store self,
+		direction: CursorDirection,
+	) -> LocalBoxFuture<'store, Result<Cursor<V>, Error>>;
+	fn key_cursor_directed<'store, K>(
+		&'store self,
+		direction: CursorDirection,
+	) -> LocalBoxFuture<'store, Result<KeyCursor<K>, Error>>;
+	fn cursor_range<'store, K, V>(
+		&'store self,
+		range: &'store KeyRange<K>,
+		direction: Option<CursorDirection>,
+	) -> LocalBoxFuture<'store, Result<Cursor<V>, Error>>
+	where
+		K: crate::IndexedKey;
+	fn key_cursor_range<'store, K>(
+		&'store self,
+		range: &'store KeyRange<K>,
+		direction: Option<CursorDirection>,
+	) -> LocalBoxFuture<'store, Result<KeyCursor<K>, Error>>
+	where
+		K: crate::IndexedKey;
 }
 
 impl ObjectStoreExt for idb::ObjectStore {
 	fn get_record<'store, V>(
 		&'store self,
-		key: impl Into<JsValue> + 'store,
+		key: &'store (impl IndexedKey + ?Sized),
 	) -> LocalBoxFuture<'store, Result<Option<V>, Error>>
 	where
 		V: Record + serde::de::DeserializeOwned,
 	{
 		Box::pin(async move {
-			let Some(record_js) = self.get(idb::Query::Key(key.into()))?.await? else {
+			let key = crate::key_range::to_key(key)?;
+			let Some(record_js) = self.get(idb::Query::Key(key))?.await? else {
 				return Ok(None);
 			};
 			Ok(Some(serde_wasm_bindgen::from_value::<V>(record_js)?))
@@ -56,10 +83,10 @@ impl ObjectStoreExt for idb::ObjectStore {
 
 	fn delete_record<'store>(
 		&'store self,
-		key: impl Into<JsValue> + 'store,
+		key: &'store (impl IndexedKey + ?Sized),
 	) -> LocalBoxFuture<'store, Result<(), Error>> {
 		Box::pin(async move {
-			self.delete(idb::Query::Key(key.into()))?.await?;
+			self.delete(idb::Query::Key(crate::key_range::to_key(key)?))?.await?;
 			Ok(())
 		})
 	}
@@ -69,9 +96,12 @@ impl ObjectStoreExt for idb::ObjectStore {
 		V: Record,
 	{
 		Box::pin(async move {
-			let key = record.key().map(|key| JsValue::from(key));
+			let key = out_of_line_key(self, record)?;
 			let value = record.as_value()?;
-			let _ = self.add(&value, key.as_ref())?.await?;
+			let request = self.add(&value, key.as_ref())?;
+			if let Err(err) = request.await {
+				return Err(crate::error::add_failed(err, self, &value, key.as_ref()));
+			}
 			Ok(())
 		})
 	}
@@ -81,8 +111,9 @@ impl ObjectStoreExt for idb::ObjectStore {
 		V: Record,
 	{
 		Box::pin(async move {
+			let key = out_of_line_key(self, record)?;
 			let value = record.as_value()?;
-			let _ = self.put(&value, None)?.await?;
+			let _ = self.put(&value, key.as_ref())?.await?;
 			Ok(())
 		})
 	}
@@ -106,6 +137,13 @@ impl ObjectStoreExt for idb::ObjectStore {
 	}
 
 	fn create_index_of<T: IndexType>(&self, params: Option<idb::IndexParams>) -> Result<idb::Index, idb::Error> {
+		let params = params.or_else(|| {
+			T::multi_entry().then(|| {
+				let mut params = idb::IndexParams::new();
+				params.multi_entry(true);
+				params
+			})
+		});
 		self.create_index(T::name(), T::key_path(), params)
 	}
 
@@ -116,7 +154,81 @@ impl ObjectStoreExt for idb::ObjectStore {
 	fn cursor_all<'store, V>(&'store self) -> LocalBoxFuture<'store, Result<Cursor<V>, Error>> {
 		Box::pin(async move {
 			let cursor = self.open_cursor(None, None)?.await?;
-			Ok(Cursor::new(cursor))
+			Ok(Cursor::new(cursor).with_query(None))
 		})
 	}
+
+	fn cursor_counted<'store, V>(&'store self) -> LocalBoxFuture<'store, Result<Cursor<V>, Error>> {
+		Box::pin(async move {
+			// the count is requested alongside the cursor, so it counts the entries the cursor visits
+			let count = self.count(None)?;
+			let cursor = self.open_cursor(None, None)?;
+			let (count, cursor) = futures_util::future::try_join(count.into_future(), cursor.into_future()).await?;
+			Ok(Cursor::new(cursor).with_query(None).with_len(count))
+		})
+	}
+
+	fn key_cursor_all<'store, K>(&'store self) -> LocalBoxFuture<'store, Result<KeyCursor<K>, Error>> {
+		Box::pin(async move {
+			let cursor = self.open_key_cursor(None, None)?.await?;
+			Ok(KeyCursor::new(cursor))
+		})
+	}
+
+	fn cursor_directed<'store, V>(
+		&'store self,
+		direction: CursorDirection,
+	) -> LocalBoxFuture<'store, Result<Cursor<V>, Error>> {
+		Box::pin(async move {
+			let cursor = self.open_cursor(None, Some(direction))?.await?;
+			Ok(Cursor::new(cursor).with_query(None))
+		})
+	}
+
+	fn key_cursor_directed<'store, K>(
+		&'store self,
+		direction: CursorDirection,
+	) -> LocalBoxFuture<'store, Result<KeyCursor<K>, Error>> {
+		Box::pin(async move {
+			let cursor = self.open_key_cursor(None, Some(direction))?.await?;
+			Ok(KeyCursor::new(cursor))
+		})
+	}
+
+	fn cursor_range<'store, K, V>(
+		&'store self,
+		range: &'store KeyRange<K>,
+		direction: Option<CursorDirection>,
+	) -> LocalBoxFuture<'store, Result<Cursor<V>, Error>>
+	where
+		K: crate::IndexedKey,
+	{
+		Box::pin(async move {
+			let query = range.to_query()?;
+			let cursor = self.open_cursor(Some(query.clone()), direction)?.await?;
+			Ok(Cursor::new(cursor).with_query(Some(&query)))
+		})
+	}
+
+	fn key_cursor_range<'store, K>(
+		&'store self,
+		range: &'store KeyRange<K>,
+		direction: Option<CursorDirection>,
+	) -> LocalBoxFuture<'store, Result<KeyCursor<K>, Error>>
+	where
+		K: crate::IndexedKey,
+	{
+		Box::pin(async move {
+			let cursor = self.open_key_cursor(Some(range.to_query()?), direction)?.await?;
+			Ok(KeyCursor::new(cursor))
+		})
+	}
+}
+
+/// Stores with a key path read the key from the value, and reject a key passed alongside it.
+fn out_of_line_key<V: Record>(store: &idb::ObjectStore, record: &V) -> Result<Option<JsValue>, Error> {
+	match store.key_path()? {
+		Some(_) => Ok(None),
+		None => record.key_value(),
+	}
 }
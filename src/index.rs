@@ -1,4 +1,4 @@
-use super::{Cursor, Error};
+use super::{Cursor, CursorDirection, Error, IndexedKey, KeyCursor, KeyRange};
 use serde::Deserialize;
 
 pub struct Index<T: IndexType>(idb::Index, std::marker::PhantomData<T>);
@@ -32,7 +32,52 @@ impl<T: IndexType> Index<T> {
 		Ok(values)
 	}
 
+	/// The primary keys of the matching records, or of every record in the index if there are no params,
+	/// without reading the record values. At most `limit` keys are returned, in index order.
+	pub async fn get_all_keys<K>(&self, params: Option<&T>, limit: Option<u32>) -> Result<Vec<K>, Error>
+	where
+		K: for<'de> Deserialize<'de>,
+	{
+		let query = match params {
+			Some(params) => Some(params.as_query()?),
+			None => None,
+		};
+		let js_keys = self.0.get_all_keys(query, limit)?.await?;
+		let mut keys = Vec::with_capacity(js_keys.len());
+		for js_key in js_keys {
+			keys.push(serde_wasm_bindgen::from_value::<K>(js_key)?);
+		}
+		Ok(keys)
+	}
+
+	/// Counts the matching records, or every record in the index if there are no params.
+	pub async fn count(&self, params: Option<&T>) -> Result<u32, Error> {
+		let query = match params {
+			Some(params) => Some(params.as_query()?),
+			None => None,
+		};
+		Ok(self.0.count(query)?.await?)
+	}
+
+	/// Counts the records whose index keys are in `range`, or every record in the index if there is no range.
+	pub async fn count_in<K: IndexedKey>(&self, range: Option<&KeyRange<K>>) -> Result<u32, Error> {
+		let query = range.map(KeyRange::to_query).transpose()?;
+		Ok(self.0.count(query)?.await?)
+	}
+
 	pub async fn open_cursor(&self, params: Option<&T>) -> Result<Cursor<T::Record>, idb::Error>
+	where
+		T::Record: for<'de> Deserialize<'de>,
+	{
+		self.open_cursor_directed(params, CursorDirection::Next).await
+	}
+
+	/// Opens a cursor over the matching entries, visiting them in `direction`.
+	pub async fn open_cursor_directed(
+		&self,
+		params: Option<&T>,
+		direction: CursorDirection,
+	) -> Result<Cursor<T::Record>, idb::Error>
 	where
 		T::Record: for<'de> Deserialize<'de>,
 	{
@@ -40,10 +85,82 @@ impl<T: IndexType> Index<T> {
 			Some(params) => Some(params.as_query()?),
 			None => None,
 		};
-		let cursor = self.0.open_cursor(query, None)?.await?;
-		let cursor = Cursor::<T::Record>::new(cursor);
+		let cursor = self.0.open_cursor(query.clone(), Some(direction))?.await?;
+		let cursor = Cursor::<T::Record>::new(cursor).with_query(query.as_ref());
 		Ok(cursor)
 	}
+
+	/// Every record whose index key contains `value`. For a [multi-entry](IndexType::multi_entry) index
+	/// on a list field, these are the records which have `value` as one of the elements of the list.
+	/// ```ignore
+	/// let tagged: Vec<Post> = store.index_of::<PostsByTag>()?.contains(&"rust").await?;
+	/// ```
+	pub async fn contains<K>(&self, value: &K) -> Result<Vec<T::Record>, Error>
+	where
+		K: IndexedKey + ?Sized,
+		T::Record: for<'de> Deserialize<'de>,
+	{
+		let query = idb::Query::Key(crate::key_range::to_key(value)?);
+		let js_values = self.0.get_all(Some(query), None)?.await?;
+		let mut values = Vec::with_capacity(js_values.len());
+		for js_value in js_values {
+			values.push(serde_wasm_bindgen::from_value::<T::Record>(js_value)?);
+		}
+		Ok(values)
+	}
+
+	/// Opens a cursor over the index keys of the matching entries, without reading the record values.
+	pub async fn open_key_cursor<K>(&self, params: Option<&T>) -> Result<KeyCursor<K>, idb::Error>
+	where
+		K: for<'de> Deserialize<'de>,
+	{
+		self.open_key_cursor_directed(params, CursorDirection::Next).await
+	}
+
+	/// Opens a cursor over the index keys of the matching entries, visiting them in `direction`.
+	pub async fn open_key_cursor_directed<K>(
+		&self,
+		params: Option<&T>,
+		direction: CursorDirection,
+	) -> Result<KeyCursor<K>, idb::Error>
+	where
+		K: for<'de> Deserialize<'de>,
+	{
+		let query = match params {
+			Some(params) => Some(params.as_query()?),
+			None => None,
+		};
+		let cursor = self.0.open_key_cursor(query, Some(direction))?.await?;
+		Ok(KeyCursor::<K>::new(cursor))
+	}
+
+	/// Opens a cursor over the entries whose index keys are within `range`.
+	pub async fn open_cursor_range<K>(
+		&self,
+		range: &KeyRange<K>,
+		direction: Option<CursorDirection>,
+	) -> Result<Cursor<T::Record>, Error>
+	where
+		K: IndexedKey,
+		T::Record: for<'de> Deserialize<'de>,
+	{
+		let query = range.to_query()?;
+		let cursor = self.0.open_cursor(Some(query.clone()), direction)?.await?;
+		Ok(Cursor::<T::Record>::new(cursor).with_query(Some(&query)))
+	}
+
+	/// Opens a cursor over the index keys which are within `range`, without reading the record values.
+	pub async fn open_key_cursor_range<K>(
+		&self,
+		range: &KeyRange<K>,
+		direction: Option<CursorDirection>,
+	) -> Result<KeyCursor<K>, Error>
+	where
+		K: IndexedKey + for<'de> Deserialize<'de>,
+	{
+		let cursor = self.0.open_key_cursor(Some(range.to_query()?), direction)?.await?;
+		Ok(KeyCursor::<K>::new(cursor))
+	}
 }
 
 pub trait IndexType {
@@ -53,6 +170,13 @@ pub trait IndexType {
 	fn keys() -> &'static [&'static str];
 	fn as_query(&self) -> Result<idb::Query, idb::Error>;
 
+	/// True if the key path of the index is a list, and each element of the list should be indexed separately,
+	/// rather than the list as a whole. Used by [`create_index_of`](crate::ObjectStoreExt::create_index_of)
+	/// when no params are given.
+	fn multi_entry() -> bool {
+		false
+	}
+
 	fn key_path() -> idb::KeyPath {
 		let keys = Self::keys();
 		if keys.len() == 1 {
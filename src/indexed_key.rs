@@ -0,0 +1,101 @@
+use super::Error;
+use wasm_bindgen::JsValue;
+
+/// A type which IndexedDB accepts as a key: strings, numbers, dates, binary data,
+/// and arrays or tuples of keys (such as the key of a compound key path).
+/// Key parameters of the typed API are bound by this trait, so that passing something which is not a key,
+/// such as a struct, fails to compile instead of failing with a `DataError` at runtime.
+///
+/// This trait is sealed. Keys made of other types can still be passed as a [`JsValue`]
+/// through [`ObjectStoreExt`](crate::ObjectStoreExt).
+pub trait IndexedKey: sealed::Sealed {
+	/// Converts the key the same way that [`Record`](crate::Record) values are serialized,
+	/// so that keys compare equal to the key paths of stored values.
+	#[doc(hidden)]
+	fn to_js_key(&self) -> Result<JsValue, Error>;
+}
+
+pub(crate) mod sealed {
+	pub trait Sealed {}
+}
+
+impl<K: IndexedKey + ?Sized> sealed::Sealed for &K {}
+impl<K: IndexedKey + ?Sized> IndexedKey for &K {
+	fn to_js_key(&self) -> Result<JsValue, Error> {
+		(**self).to_js_key()
+	}
+}
+
+macro_rules! serialized_key {
+	($($ty:ty),*) => {
+		$(
+			impl sealed::Sealed for $ty {}
+			impl IndexedKey for $ty {
+				fn to_js_key(&self) -> Result<JsValue, Error> {
+					Ok(crate::record::to_value(self)?)
+				}
+			}
+		)*
+	};
+}
+serialized_key!(str, String, u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+macro_rules! js_key {
+	($($ty:ty),*) => {
+		$(
+			impl sealed::Sealed for $ty {}
+			impl IndexedKey for $ty {
+				fn to_js_key(&self) -> Result<JsValue, Error> {
+					Ok(self.into())
+				}
+			}
+		)*
+	};
+}
+js_key!(js_sys::Date, js_sys::ArrayBuffer, js_sys::Uint8Array);
+
+impl<K: IndexedKey> sealed::Sealed for [K] {}
+impl<K: IndexedKey> IndexedKey for [K] {
+	fn to_js_key(&self) -> Result<JsValue, Error> {
+		let array = js_sys::Array::new();
+		for key in self {
+			array.push(&key.to_js_key()?);
+		}
+		Ok(array.into())
+	}
+}
+
+impl<K: IndexedKey, const N: usize> sealed::Sealed for [K; N] {}
+impl<K: IndexedKey, const N: usize> IndexedKey for [K; N] {
+	fn to_js_key(&self) -> Result<JsValue, Error> {
+		self.as_slice().to_js_key()
+	}
+}
+
+impl<K: IndexedKey> sealed::Sealed for Vec<K> {}
+impl<K: IndexedKey> IndexedKey for Vec<K> {
+	fn to_js_key(&self) -> Result<JsValue, Error> {
+		self.as_slice().to_js_key()
+	}
+}
+
+macro_rules! tuple_key {
+	($($name:ident),+) => {
+		impl<$($name: IndexedKey),+> sealed::Sealed for ($($name,)+) {}
+		impl<$($name: IndexedKey),+> IndexedKey for ($($name,)+) {
+			#[allow(non_snake_case)]
+			fn to_js_key(&self) -> Result<JsValue, Error> {
+				let ($($name,)+) = self;
+				let array = js_sys::Array::new();
+				$(array.push(&$name.to_js_key()?);)+
+				Ok(array.into())
+			}
+		}
+	};
+}
+tuple_key!(A);
+tuple_key!(A, B);
+tuple_key!(A, B, C);
+tuple_key!(A, B, C, D);
+tuple_key!(A, B, C, D, E);
+tuple_key!(A, B, C, D, E, F);
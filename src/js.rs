@@ -0,0 +1,11 @@
+use crate::Error;
+use wasm_bindgen::{JsCast, JsValue};
+
+/// Calls the JS method `method` of `target`, for browser APIs which have no bindings.
+pub(crate) fn call(target: &JsValue, method: &str, arguments: &[JsValue]) -> Result<JsValue, Error> {
+	let js_error = |err: JsValue| Error::Internal(format!("{err:?}"));
+	let function = js_sys::Reflect::get(target, &method.into()).map_err(js_error)?;
+	let function = function.unchecked_into::<js_sys::Function>();
+	let arguments = arguments.iter().collect::<js_sys::Array>();
+	function.apply(target, &arguments).map_err(js_error)
+}
@@ -0,0 +1,98 @@
+use super::{CursorDirection, PendingAdvance};
+use serde::Deserialize;
+use std::{pin::Pin, task::Poll};
+use wasm_bindgen::JsValue;
+
+/// Iterates over the keys of a cursor provided by one of the `open_key_cursor` functions,
+/// without reading or deserializing the values of the entries.
+/// This makes key enumeration and existence scans much cheaper than a full [`Cursor`](super::Cursor).
+/// ```ignore
+/// while let Some(key) = key_cursor.next().await {
+///   // ...
+/// }
+/// ```
+pub struct KeyCursor<K> {
+	cursor: Option<idb::KeyCursor>,
+	direction: CursorDirection,
+	marker: std::marker::PhantomData<fn() -> K>,
+	pending: Option<PendingAdvance<idb::KeyCursor, JsValue>>,
+}
+
+impl<K> KeyCursor<K> {
+	pub fn new(cursor: Option<idb::KeyCursor>) -> Self {
+		let direction = cursor.as_ref().and_then(|cursor| cursor.direction().ok());
+		Self {
+			cursor,
+			direction: direction.unwrap_or_default(),
+			marker: Default::default(),
+			pending: None,
+		}
+	}
+
+	/// The direction the cursor was opened with.
+	pub fn direction(&self) -> CursorDirection {
+		self.direction
+	}
+}
+
+impl<K> futures_util::stream::Stream for KeyCursor<K>
+where
+	K: for<'de> Deserialize<'de>,
+{
+	type Item = K;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Option<Self::Item>> {
+		let mut pending = match self.pending.take() {
+			Some(pending) => pending,
+			None => match self.cursor.take() {
+				// an empty cursor on first poll means no elements
+				None => return Poll::Ready(None),
+				Some(cursor) => Box::pin(async move {
+					let cursor = cursor;
+					// read the key before advancing, the same as `Cursor` does for values
+					let js_key = cursor.key()?;
+					let adv_request = cursor.advance(1);
+					// if this causes and advancement failure, then we've reached the end of the cursor
+					if let Err(idb::Error::CursorAdvanceFailed(_)) = &adv_request {
+						return Ok((None, js_key));
+					}
+					let adv_request = adv_request?.await;
+					if let Err(idb::Error::CursorAdvanceFailed(_)) = &adv_request {
+						return Ok((None, js_key));
+					}
+					Ok((adv_request?, js_key))
+				}),
+			},
+		};
+
+		let js_key = match pending.as_mut().poll(cx) {
+			Poll::Pending => {
+				self.pending = Some(pending);
+				return Poll::Pending;
+			}
+			Poll::Ready(Err(err)) => {
+				log::error!(target: "cursor", "Failed to query next key from cursor: {err:?}");
+				return Poll::Ready(None);
+			}
+			Poll::Ready(Ok((cursor, key))) => {
+				self.cursor = cursor;
+				key
+			}
+		};
+
+		// Key is empty, so we've reached end-of-stream.
+		if js_key.is_undefined() || js_key.is_null() {
+			return Poll::Ready(None);
+		}
+
+		let key = match serde_wasm_bindgen::from_value::<K>(js_key) {
+			Ok(key) => key,
+			Err(err) => {
+				log::error!(target: "cursor", "Failed to parse database key: {err:?}");
+				return Poll::Ready(None);
+			}
+		};
+
+		Poll::Ready(Some(key))
+	}
+}
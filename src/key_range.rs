@@ -0,0 +1,114 @@
+use super::{Error, IndexedKey};
+use wasm_bindgen::JsValue;
+
+/// A typed range of keys, used to limit cursors and queries to a slice of a store or index.
+/// ```ignore
+/// let range = KeyRange::bound("a".to_owned(), "m".to_owned()).upper_open();
+/// let cursor = store.cursor_range::<String, Entry>(&range, None).await?;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyRange<K> {
+	bounds: Bounds<K>,
+	lower_open: bool,
+	upper_open: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Bounds<K> {
+	Only(K),
+	Lower(K),
+	Upper(K),
+	Both(K, K),
+}
+
+impl<K> KeyRange<K> {
+	fn new(bounds: Bounds<K>) -> Self {
+		Self {
+			bounds,
+			lower_open: false,
+			upper_open: false,
+		}
+	}
+
+	/// A range which contains exactly one key.
+	pub fn only(key: K) -> Self {
+		Self::new(Bounds::Only(key))
+	}
+
+	/// A range of all keys at or after `lower`.
+	pub fn lower_bound(lower: K) -> Self {
+		Self::new(Bounds::Lower(lower))
+	}
+
+	/// A range of all keys at or before `upper`.
+	pub fn upper_bound(upper: K) -> Self {
+		Self::new(Bounds::Upper(upper))
+	}
+
+	/// A range of all keys between `lower` and `upper` (inclusive by default).
+	pub fn bound(lower: K, upper: K) -> Self {
+		Self::new(Bounds::Both(lower, upper))
+	}
+
+	/// Excludes the lower bound from the range. Has no effect on ranges without a lower bound.
+	pub fn lower_open(mut self) -> Self {
+		self.lower_open = true;
+		self
+	}
+
+	/// Excludes the upper bound from the range. Has no effect on ranges without an upper bound.
+	pub fn upper_open(mut self) -> Self {
+		self.upper_open = true;
+		self
+	}
+
+	pub fn lower(&self) -> Option<&K> {
+		match &self.bounds {
+			Bounds::Only(key) | Bounds::Lower(key) | Bounds::Both(key, _) => Some(key),
+			Bounds::Upper(_) => None,
+		}
+	}
+
+	pub fn upper(&self) -> Option<&K> {
+		match &self.bounds {
+			Bounds::Only(key) | Bounds::Upper(key) | Bounds::Both(_, key) => Some(key),
+			Bounds::Lower(_) => None,
+		}
+	}
+
+	pub fn is_lower_open(&self) -> bool {
+		self.lower_open
+	}
+
+	pub fn is_upper_open(&self) -> bool {
+		self.upper_open
+	}
+}
+
+impl<K: IndexedKey> KeyRange<K> {
+	/// Serializes the bounds of the range into an IndexedDB key range.
+	pub fn to_key_range(&self) -> Result<idb::KeyRange, Error> {
+		let range = match &self.bounds {
+			Bounds::Only(key) => idb::KeyRange::only(&to_key(key)?)?,
+			Bounds::Lower(lower) => idb::KeyRange::lower_bound(&to_key(lower)?, Some(self.lower_open))?,
+			Bounds::Upper(upper) => idb::KeyRange::upper_bound(&to_key(upper)?, Some(self.upper_open))?,
+			Bounds::Both(lower, upper) => idb::KeyRange::bound(
+				&to_key(lower)?,
+				&to_key(upper)?,
+				Some(self.lower_open),
+				Some(self.upper_open),
+			)?,
+		};
+		Ok(range)
+	}
+
+	pub fn to_query(&self) -> Result<idb::Query, Error> {
+		Ok(idb::Query::KeyRange(self.to_key_range()?))
+	}
+}
+
+/// Serializes a key the same way that [`Record`](super::Record) values are serialized,
+/// so that keys compare equal to the key paths of stored values.
+pub fn to_key<K: IndexedKey + ?Sized>(key: &K) -> Result<JsValue, Error> {
+	key.to_js_key()
+}
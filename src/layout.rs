@@ -0,0 +1,307 @@
+use super::{Error, MigrationOp};
+use serde::{Deserialize, Serialize};
+
+pub use idb::KeyPath;
+
+mod builder;
+pub use builder::*;
+
+/// The object stores and indexes of a database.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SchemaLayout {
+	/// The stores of the database, sorted by name.
+	pub stores: Vec<StoreLayout>,
+}
+
+/// The definition of an object store.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoreLayout {
+	pub name: String,
+	/// The path of the key in each record, or `None` if keys are provided separately from values.
+	#[serde(with = "key_path")]
+	pub key_path: Option<KeyPath>,
+	pub auto_increment: bool,
+	/// The indexes of the store, sorted by name.
+	pub indexes: Vec<IndexLayout>,
+}
+
+/// The definition of an index on an object store.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexLayout {
+	pub name: String,
+	#[serde(with = "key_path")]
+	pub key_path: Option<KeyPath>,
+	pub unique: bool,
+	pub multi_entry: bool,
+}
+
+impl SchemaLayout {
+	/// Reads the layout of every store in scope of the transaction.
+	/// In an upgrade transaction, this is every store in the database.
+	pub fn read(transaction: &idb::Transaction) -> Result<Self, Error> {
+		let mut stores = Vec::new();
+		for name in transaction.store_names() {
+			stores.push(StoreLayout::read(&transaction.object_store(&name)?)?);
+		}
+		stores.sort_by(|a, b| a.name.cmp(&b.name));
+		Ok(Self { stores })
+	}
+
+	pub fn store(&self, name: &str) -> Option<&StoreLayout> {
+		self.stores.iter().find(|store| store.name == name)
+	}
+
+	/// Serializes the layout and the schema version it belongs to as JSON, such as for diagnostics
+	/// or comparing the schema of different releases.
+	pub fn to_json(&self, version: u32) -> Result<String, Error> {
+		let export = VersionedLayout {
+			version,
+			layout: self.clone(),
+		};
+		serde_json::to_string_pretty(&export).map_err(|err| Error::Serialization(err.to_string()))
+	}
+
+	/// Parses a layout and its version from JSON written by [`SchemaLayout::to_json`].
+	pub fn from_json(json: &str) -> Result<(u32, Self), Error> {
+		let export =
+			serde_json::from_str::<VersionedLayout>(json).map_err(|err| Error::Serialization(err.to_string()))?;
+		Ok((export.version, export.layout))
+	}
+
+	/// Compares this declared layout to the `actual` layout of a database,
+	/// returning everything the database is missing or has defined differently.
+	/// Stores and indexes which exist but are not declared are not reported.
+	pub fn mismatches(&self, actual: &SchemaLayout) -> Vec<SchemaMismatch> {
+		let mut mismatches = Vec::new();
+		for declared in &self.stores {
+			match actual.store(&declared.name) {
+				None => mismatches.push(SchemaMismatch::MissingStore(declared.name.clone())),
+				Some(actual) => declared.compare(actual, &mut mismatches),
+			}
+		}
+		mismatches
+	}
+
+	/// Lists the operations which would bring a database with the `existing` layout in line with this layout:
+	/// creating declared stores and indexes which are missing and deleting any which are not declared.
+	/// Indexes which are defined differently are recreated, but the key of an existing store cannot be changed.
+	pub fn plan(&self, existing: &SchemaLayout) -> Result<Vec<MigrationOp>, Error> {
+		let mut operations = Vec::new();
+		for store in &existing.stores {
+			if self.store(&store.name).is_none() {
+				operations.push(MigrationOp::DeleteStore(store.name.clone()));
+			}
+		}
+		for declared in &self.stores {
+			match existing.store(&declared.name) {
+				None => operations.push(MigrationOp::CreateStore(declared.clone())),
+				Some(existing) => declared.plan_update(existing, &mut operations)?,
+			}
+		}
+		Ok(operations)
+	}
+
+	/// Brings the database of an upgrade transaction in line with this layout, as described by [`SchemaLayout::plan`].
+	pub fn upgrade(&self, transaction: &idb::Transaction) -> Result<(), Error> {
+		for operation in self.plan(&Self::read(transaction)?)? {
+			operation.apply(transaction)?;
+		}
+		Ok(())
+	}
+}
+
+impl StoreLayout {
+	pub fn read(store: &idb::ObjectStore) -> Result<Self, Error> {
+		let mut indexes = Vec::new();
+		for name in store.index_names() {
+			let index = store.index(&name)?;
+			indexes.push(IndexLayout {
+				key_path: index.key_path()?,
+				unique: index.unique(),
+				multi_entry: index.multi_entry(),
+				name,
+			});
+		}
+		indexes.sort_by(|a, b| a.name.cmp(&b.name));
+		Ok(Self {
+			name: store.name(),
+			key_path: store.key_path()?,
+			auto_increment: store.auto_increment(),
+			indexes,
+		})
+	}
+
+	pub fn index(&self, name: &str) -> Option<&IndexLayout> {
+		self.indexes.iter().find(|index| index.name == name)
+	}
+
+	pub(crate) fn create(&self, database: &idb::Database) -> Result<(), Error> {
+		let mut params = idb::ObjectStoreParams::new();
+		params.auto_increment(self.auto_increment);
+		params.key_path(self.key_path.clone());
+		let store = database.create_object_store(&self.name, params)?;
+		for index in &self.indexes {
+			index.create(&store)?;
+		}
+		Ok(())
+	}
+
+	fn plan_update(&self, existing: &StoreLayout, operations: &mut Vec<MigrationOp>) -> Result<(), Error> {
+		if self.key_path != existing.key_path || self.auto_increment != existing.auto_increment {
+			return Err(Error::Internal(format!(
+				"the key of object store {:?} cannot be changed after it is created",
+				self.name
+			)));
+		}
+		for index in &existing.indexes {
+			if self.index(&index.name) != Some(index) {
+				operations.push(MigrationOp::DeleteIndex {
+					store: self.name.clone(),
+					index: index.name.clone(),
+				});
+			}
+		}
+		for index in &self.indexes {
+			if existing.index(&index.name) != Some(index) {
+				operations.push(MigrationOp::CreateIndex {
+					store: self.name.clone(),
+					index: index.clone(),
+				});
+			}
+		}
+		Ok(())
+	}
+
+	fn compare(&self, actual: &StoreLayout, mismatches: &mut Vec<SchemaMismatch>) {
+		let store = &self.name;
+		if self.key_path != actual.key_path {
+			mismatches.push(SchemaMismatch::StoreKeyPath {
+				store: store.clone(),
+				expected: self.key_path.clone(),
+				actual: actual.key_path.clone(),
+			});
+		}
+		if self.auto_increment != actual.auto_increment {
+			mismatches.push(SchemaMismatch::AutoIncrement {
+				store: store.clone(),
+				expected: self.auto_increment,
+			});
+		}
+		for declared in &self.indexes {
+			let Some(actual) = actual.index(&declared.name) else {
+				mismatches.push(SchemaMismatch::MissingIndex {
+					store: store.clone(),
+					index: declared.name.clone(),
+				});
+				continue;
+			};
+			if declared.key_path != actual.key_path {
+				mismatches.push(SchemaMismatch::IndexKeyPath {
+					store: store.clone(),
+					index: declared.name.clone(),
+					expected: declared.key_path.clone(),
+					actual: actual.key_path.clone(),
+				});
+			}
+			if declared.unique != actual.unique {
+				mismatches.push(SchemaMismatch::IndexUnique {
+					store: store.clone(),
+					index: declared.name.clone(),
+					expected: declared.unique,
+				});
+			}
+			if declared.multi_entry != actual.multi_entry {
+				mismatches.push(SchemaMismatch::IndexMultiEntry {
+					store: store.clone(),
+					index: declared.name.clone(),
+					expected: declared.multi_entry,
+				});
+			}
+		}
+	}
+}
+
+impl IndexLayout {
+	pub(crate) fn create(&self, store: &idb::ObjectStore) -> Result<(), Error> {
+		let Some(key_path) = self.key_path.clone() else {
+			return Err(Error::Internal(format!("index {:?} has no key path", self.name)));
+		};
+		let mut params = idb::IndexParams::new();
+		params.unique(self.unique);
+		params.multi_entry(self.multi_entry);
+		store.create_index(&self.name, key_path, Some(params))?;
+		Ok(())
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+struct VersionedLayout {
+	version: u32,
+	#[serde(flatten)]
+	layout: SchemaLayout,
+}
+
+/// Serializes key paths as a string, or an array of strings for compound key paths.
+mod key_path {
+	use super::KeyPath;
+	use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+	#[derive(Serialize, Deserialize)]
+	#[serde(untagged)]
+	enum Repr {
+		Single(String),
+		Array(Vec<String>),
+	}
+
+	pub fn serialize<S: Serializer>(key_path: &Option<KeyPath>, serializer: S) -> Result<S::Ok, S::Error> {
+		let repr = key_path.as_ref().map(|key_path| match key_path {
+			KeyPath::Single(key) => Repr::Single(key.clone()),
+			KeyPath::Array(keys) => Repr::Array(keys.clone()),
+		});
+		repr.serialize(serializer)
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<KeyPath>, D::Error> {
+		let repr = Option::<Repr>::deserialize(deserializer)?;
+		Ok(repr.map(|repr| match repr {
+			Repr::Single(key) => KeyPath::Single(key),
+			Repr::Array(keys) => KeyPath::Array(keys),
+		}))
+	}
+}
+
+/// A difference between a declared [`SchemaLayout`] and the layout of the database.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum SchemaMismatch {
+	#[error("Missing object store {0:?}.")]
+	MissingStore(String),
+	#[error("Missing index {index:?} on object store {store:?}.")]
+	MissingIndex { store: String, index: String },
+	#[error("Object store {store:?} has key path {actual:?}, expected {expected:?}.")]
+	StoreKeyPath {
+		store: String,
+		expected: Option<KeyPath>,
+		actual: Option<KeyPath>,
+	},
+	#[error("Object store {store:?} should have auto-increment set to {expected}.")]
+	AutoIncrement { store: String, expected: bool },
+	#[error("Index {index:?} on object store {store:?} has key path {actual:?}, expected {expected:?}.")]
+	IndexKeyPath {
+		store: String,
+		index: String,
+		expected: Option<KeyPath>,
+		actual: Option<KeyPath>,
+	},
+	#[error("Index {index:?} on object store {store:?} should have unique set to {expected}.")]
+	IndexUnique {
+		store: String,
+		index: String,
+		expected: bool,
+	},
+	#[error("Index {index:?} on object store {store:?} should have multi-entry set to {expected}.")]
+	IndexMultiEntry {
+		store: String,
+		index: String,
+		expected: bool,
+	},
+}
@@ -0,0 +1,104 @@
+use super::{IndexLayout, KeyPath, SchemaLayout, StoreLayout};
+
+/// Declares a [`SchemaLayout`] fluently, for opening a database with
+/// [`Client::open_layout`](crate::Client::open_layout) without implementing [`Schema`](crate::Schema).
+/// ```ignore
+/// let layout = SchemaBuilder::new()
+///   .store("users", |store| store.key_path("id").unique_index("email", "email"))
+///   .store("posts", |store| store.key_path("id").auto_increment().index("author", "author"))
+///   .build();
+/// let client = Client::open_layout("app", 1, layout).await?;
+/// ```
+#[derive(Default)]
+pub struct SchemaBuilder {
+	stores: Vec<StoreLayout>,
+}
+
+impl SchemaBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Declares a store, replacing any store already declared with the same name.
+	pub fn store(mut self, name: impl Into<String>, build: impl FnOnce(StoreBuilder) -> StoreBuilder) -> Self {
+		let store = build(StoreBuilder::new(name.into())).0;
+		self.stores.retain(|existing| existing.name != store.name);
+		self.stores.push(store);
+		self
+	}
+
+	pub fn build(mut self) -> SchemaLayout {
+		self.stores.sort_by(|a, b| a.name.cmp(&b.name));
+		SchemaLayout { stores: self.stores }
+	}
+}
+
+/// Declares a single store of a [`SchemaBuilder`].
+pub struct StoreBuilder(StoreLayout);
+
+impl StoreBuilder {
+	fn new(name: String) -> Self {
+		Self(StoreLayout {
+			name,
+			key_path: None,
+			auto_increment: false,
+			indexes: Vec::new(),
+		})
+	}
+
+	pub fn key_path(mut self, key_path: &str) -> Self {
+		self.0.key_path = Some(KeyPath::new_single(key_path));
+		self
+	}
+
+	pub fn compound_key_path(mut self, key_path: &[&str]) -> Self {
+		self.0.key_path = Some(KeyPath::new_array(key_path.iter().copied()));
+		self
+	}
+
+	pub fn auto_increment(mut self) -> Self {
+		self.0.auto_increment = true;
+		self
+	}
+
+	pub fn index(self, name: &str, key_path: &str) -> Self {
+		self.with_index(name, key_path, false, false)
+	}
+
+	/// Declares an index which rejects records that have the same key as another record.
+	pub fn unique_index(self, name: &str, key_path: &str) -> Self {
+		self.with_index(name, key_path, true, false)
+	}
+
+	/// Declares an index which has an entry for every element, when the key of a record is an array.
+	pub fn multi_entry_index(self, name: &str, key_path: &str) -> Self {
+		self.with_index(name, key_path, false, true)
+	}
+
+	/// Declares the index on when [`Tracked`](crate::Tracked) records were last updated.
+	pub fn updated_at_index(self) -> Self {
+		self.index(crate::UPDATED_AT_INDEX, crate::UPDATED_AT_INDEX)
+	}
+
+	/// Declares the index on when [`Expiring`](crate::Expiring) records expire.
+	pub fn expires_at_index(self) -> Self {
+		self.index(crate::EXPIRES_AT_INDEX, crate::EXPIRES_AT_INDEX)
+	}
+
+	/// Declares the index on when [`SoftDeleted`](crate::SoftDeleted) records were deleted.
+	pub fn deleted_at_index(self) -> Self {
+		self.index(crate::DELETED_AT_INDEX, crate::DELETED_AT_INDEX)
+	}
+
+	fn with_index(mut self, name: &str, key_path: &str, unique: bool, multi_entry: bool) -> Self {
+		self.0.indexes.retain(|index| index.name != name);
+		self.0.indexes.push(IndexLayout {
+			name: name.to_owned(),
+			key_path: Some(KeyPath::new_single(key_path)),
+			unique,
+			multi_entry,
+		});
+		self.0.indexes.sort_by(|a, b| a.name.cmp(&b.name));
+		self
+	}
+}
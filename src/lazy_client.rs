@@ -0,0 +1,60 @@
+use super::{Client, Error};
+use futures_util::future::{FutureExt, LocalBoxFuture, Shared};
+use std::{cell::RefCell, future::Future, rc::Rc};
+
+type Opening = LocalBoxFuture<'static, Result<Client, Error>>;
+type Opener = Rc<dyn Fn() -> Opening>;
+
+/// A [`Client`] which isn't opened until it is first used, so that startup doesn't wait on a database
+/// that may never be needed. Every caller waiting on the client shares the same in-flight open.
+/// Cloning is cheap, and clones share the connection.
+/// ```ignore
+/// let lazy = LazyClient::new(|| Client::open::<Version>("app"));
+/// // nothing has been opened yet
+/// let users = lazy.client().await?.store(&USERS);
+/// ```
+#[derive(Clone)]
+pub struct LazyClient {
+	open: Opener,
+	state: Rc<RefCell<Option<Shared<Opening>>>>,
+}
+
+impl LazyClient {
+	pub fn new<F, Fut>(open: F) -> Self
+	where
+		F: Fn() -> Fut + 'static,
+		Fut: Future<Output = Result<Client, Error>> + 'static,
+	{
+		Self {
+			open: Rc::new(move || Box::pin(open())),
+			state: Rc::new(RefCell::new(None)),
+		}
+	}
+
+	/// The client, opening the database if this is the first use. If opening fails, the next use tries again;
+	/// the database is also opened again if the client has since been closed.
+	pub async fn client(&self) -> Result<Client, Error> {
+		let opening = {
+			let mut state = self.state.borrow_mut();
+			let stale = match state.as_ref().and_then(|opening| opening.peek()) {
+				Some(Ok(client)) => !client.is_open(),
+				Some(Err(_)) => true,
+				None => false,
+			};
+			if stale {
+				*state = None;
+			}
+			state.get_or_insert_with(|| (self.open)().shared()).clone()
+		};
+		opening.await
+	}
+
+	/// The client, if the database has already been opened.
+	pub fn get(&self) -> Option<Client> {
+		let state = self.state.borrow();
+		match state.as_ref()?.peek()? {
+			Ok(client) => Some(client.clone()),
+			Err(_) => None,
+		}
+	}
+}
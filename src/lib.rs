@@ -1,15 +1,70 @@
 mod schema;
+#[cfg(feature = "derive")]
+pub use database_derive::Schema;
 pub use schema::*;
+mod migrations;
+pub use migrations::*;
+mod layout;
+pub use layout::*;
+mod plan;
+pub use plan::*;
 mod record;
+#[cfg(feature = "derive")]
+pub use database_derive::Record;
 pub use record::*;
+mod store;
+pub use store::*;
+mod structured_clone;
+pub use structured_clone::*;
+mod scoped_transaction;
+pub use scoped_transaction::*;
 
 mod client;
 pub use client::*;
+mod registry;
+pub use registry::*;
+mod lazy_client;
+pub use lazy_client::*;
+mod retry;
+pub use retry::*;
+mod storage;
+pub use storage::*;
+mod locks;
+pub use locks::*;
+mod changes;
+pub use changes::*;
+mod codec;
+pub use codec::*;
 mod cursor;
 pub use cursor::*;
+mod key_cursor;
+pub use key_cursor::*;
+mod resumable_cursor;
+pub use resumable_cursor::*;
 mod error;
 pub use error::*;
 mod ext;
+mod js;
 pub use ext::*;
 mod index;
 pub use index::*;
+mod binary_key;
+pub use binary_key::*;
+mod indexed_key;
+pub use indexed_key::*;
+mod key_range;
+pub use key_range::*;
+mod ordered_key;
+pub use ordered_key::*;
+mod timestamp;
+pub use timestamp::*;
+#[cfg(feature = "test-util")]
+mod test_util;
+#[cfg(feature = "test-util")]
+pub use test_util::*;
+
+/// Paths used by the code which the derive macros generate. Not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+	pub use wasm_bindgen::JsValue;
+}
@@ -0,0 +1,102 @@
+use super::Error;
+use futures_util::future::Either;
+use std::future::Future;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+/// Whether other holders of a lock are allowed at the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LockMode {
+	/// Only one holder at a time, across every tab and worker of the origin.
+	#[default]
+	Exclusive,
+	/// Any number of shared holders at a time, but never at the same time as an exclusive holder.
+	Shared,
+}
+
+impl LockMode {
+	fn as_str(&self) -> &'static str {
+		match self {
+			Self::Exclusive => "exclusive",
+			Self::Shared => "shared",
+		}
+	}
+}
+
+/// Runs `scope` while holding the exclusive Web Lock called `name`, so that no other tab or worker of the origin
+/// runs a scope with the same lock at the same time. Waits until the lock is available.
+/// ```ignore
+/// database::with_lock("compaction", || async {
+///   compact(&client).await
+/// }).await?;
+/// ```
+pub async fn with_lock<F, Fut, R>(name: &str, scope: F) -> Result<R, Error>
+where
+	F: FnOnce() -> Fut,
+	Fut: Future<Output = Result<R, Error>>,
+{
+	with_lock_mode(name, LockMode::Exclusive, scope).await
+}
+
+/// Runs `scope` while holding the Web Lock called `name` in the given mode. See [`with_lock`].
+pub async fn with_lock_mode<F, Fut, R>(name: &str, mode: LockMode, scope: F) -> Result<R, Error>
+where
+	F: FnOnce() -> Fut,
+	Fut: Future<Output = Result<R, Error>>,
+{
+	let js_error = |err: JsValue| Error::Internal(format!("{err:?}"));
+	let unsupported = || Error::Unsupported("navigator.locks.request()".into());
+	let navigator = js_sys::Reflect::get(&js_sys::global(), &"navigator".into()).map_err(|_| unsupported())?;
+	let locks = js_sys::Reflect::get(&navigator, &"locks".into()).map_err(|_| unsupported())?;
+	let request = js_sys::Reflect::get(&locks, &"request".into()).map_err(|_| unsupported())?;
+	let request = request.dyn_into::<js_sys::Function>().map_err(|_| unsupported())?;
+
+	// the lock is held until the promise returned to the lock manager settles
+	let (release, held) = deferred();
+	let (granted, on_granted) = deferred();
+	let callback = Closure::once_into_js(move |_lock: JsValue| {
+		let _ = granted.call0(&JsValue::UNDEFINED);
+		held
+	});
+	let options = js_sys::Object::new();
+	js_sys::Reflect::set(&options, &"mode".into(), &mode.as_str().into()).map_err(js_error)?;
+	let requested = request
+		.call3(&locks, &name.into(), &options, &callback)
+		.map_err(js_error)?;
+	let requested = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::from(requested));
+
+	let guard = Release(release);
+	// the request rejects without ever granting the lock if it is invalid or aborted
+	let on_granted = wasm_bindgen_futures::JsFuture::from(on_granted);
+	let requested = match futures_util::future::select(on_granted, requested).await {
+		Either::Left((granted, requested)) => {
+			granted.map_err(js_error)?;
+			requested
+		}
+		Either::Right((Err(err), _)) => return Err(js_error(err)),
+		Either::Right((Ok(_), _)) => {
+			return Err(Error::Internal(format!(
+				"the request for lock {name:?} finished without being granted"
+			)));
+		}
+	};
+	let output = scope().await;
+	drop(guard);
+	requested.await.map_err(js_error)?;
+	output
+}
+
+/// A promise and the function which resolves it.
+fn deferred() -> (js_sys::Function, js_sys::Promise) {
+	let mut resolve = None;
+	let promise = js_sys::Promise::new(&mut |resolve_promise, _reject| resolve = Some(resolve_promise));
+	(resolve.expect("the promise executor runs immediately"), promise)
+}
+
+/// Releases the lock when dropped, including when the scope is dropped before it finishes.
+struct Release(js_sys::Function);
+
+impl Drop for Release {
+	fn drop(&mut self) {
+		let _ = self.0.call0(&JsValue::UNDEFINED);
+	}
+}
@@ -0,0 +1,78 @@
+use super::{Error, Transaction};
+use idb::event::VersionChangeEvent;
+use std::collections::BTreeMap;
+use wasm_bindgen::JsValue;
+
+/// A single step of a [`Migrations`] registry, called with the version the database is being upgraded from,
+/// the version it is being upgraded to, and the upgrade transaction.
+pub type MigrationStep = Box<dyn Fn(u32, u32, &Transaction) -> Result<(), Error>>;
+
+/// A registry of upgrade steps, keyed by the version each step brings the database up to.
+/// When the database is opened with [`Client::open_with`](crate::Client::open_with),
+/// only the steps after the stored version (up to and including [`Migrations::latest`]) are run,
+/// in order and inside the upgrade transaction.
+/// ```ignore
+/// let migrations = Migrations::new()
+///   .with(1, |_old, _new, transaction| {
+///     transaction.database().create_object_store("entries", idb::ObjectStoreParams::new())?;
+///     Ok(())
+///   })
+///   .with(2, add_entry_index);
+/// let client = Client::open_with("app", migrations).await?;
+/// ```
+#[derive(Default)]
+pub struct Migrations {
+	steps: BTreeMap<u32, MigrationStep>,
+}
+
+impl Migrations {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Registers the step which upgrades the database to `version`, replacing any step already registered for it.
+	pub fn with<F>(mut self, version: u32, step: F) -> Self
+	where
+		F: Fn(u32, u32, &Transaction) -> Result<(), Error> + 'static,
+	{
+		self.steps.insert(version, Box::new(step));
+		self
+	}
+
+	/// The highest version which has a registered step.
+	pub fn latest(&self) -> u32 {
+		self.steps.keys().next_back().copied().unwrap_or(1)
+	}
+
+	/// Runs each step after `old_version`, up to and including `new_version`.
+	pub fn run(&self, old_version: u32, new_version: u32, transaction: &Transaction) -> Result<(), Error> {
+		for (version, step) in self.steps.range((old_version + 1)..=new_version) {
+			step(old_version, new_version, transaction).map_err(|err| Error::migration_failed(*version, err))?;
+		}
+		Ok(())
+	}
+
+	pub(crate) fn upgrade(&self, event: &VersionChangeEvent) -> Result<(), Error> {
+		// This is always 0 for database initialization, and is otherwise the previous version.
+		let old_version = event.old_version()?;
+		let Some(new_version) = event.new_version()? else {
+			return Ok(());
+		};
+		let transaction = crate::Client::upgrade_transaction(event)?;
+		self.run(old_version, new_version, &transaction)?;
+		Ok(())
+	}
+}
+
+/// Renames an object store in an upgrade transaction, keeping its records, key and indexes.
+/// Fails if there is already a store called `to`.
+/// ```ignore
+/// let migrations = Migrations::new()
+///   .with(3, |_old, _new, transaction| rename_store(transaction, "entries", "notes"));
+/// ```
+pub fn rename_store(transaction: &idb::Transaction, from: &str, to: &str) -> Result<(), Error> {
+	let store = JsValue::from(transaction.object_store(from)?);
+	// the name of a store is writable during an upgrade
+	js_sys::Reflect::set(&store, &"name".into(), &to.into()).map_err(|err| crate::error::from_exception(&err))?;
+	Ok(())
+}
@@ -0,0 +1,318 @@
+use super::{BinaryKey, Error, IndexedKey, KeyRange, Timestamp};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use wasm_bindgen::JsValue;
+
+/// A value which can be encoded to bytes which sort in the same order as the values,
+/// since IndexedDB compares binary keys byte by byte.
+/// Numbers are encoded big-endian with their sign flipped, and strings and bytes are escaped and terminated,
+/// so that a shorter value sorts before any longer value it is a prefix of, and tuples sort field by field.
+pub trait OrderedKey {
+	fn encode_ordered(&self, out: &mut Vec<u8>);
+}
+
+/// A key which can be read back from its [ordered encoding](OrderedKey).
+pub trait DecodeOrdered: OrderedKey + Sized {
+	/// Reads a value from the start of `input`, advancing `input` past it.
+	fn decode_ordered(input: &mut &[u8]) -> Result<Self, Error>;
+}
+
+/// Encodes a composite key as a [`BinaryKey`] which sorts in the order of its fields.
+/// ```ignore
+/// let key = encode_ordered(&(user_id, Descending(sent_at)));
+/// client.store(&MESSAGES).put_with_key(&message, &key).await?;
+/// // the messages of a user, newest first
+/// let newest = client.store(&MESSAGES).query().range(&ordered_prefix(&(user_id,))).limit(20).collect().await?;
+/// ```
+pub fn encode_ordered<K: OrderedKey + ?Sized>(key: &K) -> BinaryKey {
+	let mut out = Vec::new();
+	key.encode_ordered(&mut out);
+	BinaryKey(out)
+}
+
+/// The range of every encoded key which starts with the fields of `prefix`.
+pub fn ordered_prefix<K: OrderedKey + ?Sized>(prefix: &K) -> KeyRange<BinaryKey> {
+	let lower = encode_ordered(prefix);
+	// the first key after every key starting with the prefix increments its last byte which can be incremented
+	let mut upper = lower.clone().into_bytes();
+	while let Some(byte) = upper.pop() {
+		if byte < u8::MAX {
+			upper.push(byte + 1);
+			return KeyRange::bound(lower, BinaryKey(upper)).upper_open();
+		}
+	}
+	KeyRange::lower_bound(lower)
+}
+
+/// Decodes a key written by [`encode_ordered`].
+pub fn decode_ordered<K: DecodeOrdered>(mut bytes: &[u8]) -> Result<K, Error> {
+	let key = K::decode_ordered(&mut bytes)?;
+	match bytes.is_empty() {
+		true => Ok(key),
+		false => Err(invalid("the key has trailing bytes")),
+	}
+}
+
+fn invalid(message: &str) -> Error {
+	Error::Serialization(format!("invalid ordered key: {message}"))
+}
+
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+	if input.len() < len {
+		return Err(invalid("the key ended early"));
+	}
+	let (bytes, rest) = input.split_at(len);
+	*input = rest;
+	Ok(bytes)
+}
+
+macro_rules! unsigned_key {
+	($($ty:ty),*) => {
+		$(
+			impl OrderedKey for $ty {
+				fn encode_ordered(&self, out: &mut Vec<u8>) {
+					out.extend_from_slice(&self.to_be_bytes());
+				}
+			}
+
+			impl DecodeOrdered for $ty {
+				fn decode_ordered(input: &mut &[u8]) -> Result<Self, Error> {
+					let bytes = take(input, std::mem::size_of::<$ty>())?;
+					Ok(<$ty>::from_be_bytes(bytes.try_into().unwrap()))
+				}
+			}
+		)*
+	};
+}
+unsigned_key!(u8, u16, u32, u64, u128);
+
+macro_rules! signed_key {
+	($($ty:ty => $unsigned:ty),*) => {
+		$(
+			impl OrderedKey for $ty {
+				// flipping the sign bit sorts negative numbers before positive ones
+				fn encode_ordered(&self, out: &mut Vec<u8>) {
+					((*self as $unsigned) ^ (1 << (<$unsigned>::BITS - 1))).encode_ordered(out);
+				}
+			}
+
+			impl DecodeOrdered for $ty {
+				fn decode_ordered(input: &mut &[u8]) -> Result<Self, Error> {
+					let bits = <$unsigned>::decode_ordered(input)?;
+					Ok((bits ^ (1 << (<$unsigned>::BITS - 1))) as $ty)
+				}
+			}
+		)*
+	};
+}
+signed_key!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128);
+
+macro_rules! float_key {
+	($($ty:ty => $unsigned:ty),*) => {
+		$(
+			impl OrderedKey for $ty {
+				// positive numbers have their sign bit set, and negative numbers have every bit flipped
+				// so that larger magnitudes sort first
+				fn encode_ordered(&self, out: &mut Vec<u8>) {
+					let bits = self.to_bits();
+					let sign = 1 << (<$unsigned>::BITS - 1);
+					match bits & sign {
+						0 => bits | sign,
+						_ => !bits,
+					}
+					.encode_ordered(out);
+				}
+			}
+
+			impl DecodeOrdered for $ty {
+				fn decode_ordered(input: &mut &[u8]) -> Result<Self, Error> {
+					let bits = <$unsigned>::decode_ordered(input)?;
+					let sign = 1 << (<$unsigned>::BITS - 1);
+					Ok(<$ty>::from_bits(match bits & sign {
+						0 => !bits,
+						_ => bits & !sign,
+					}))
+				}
+			}
+		)*
+	};
+}
+float_key!(f32 => u32, f64 => u64);
+
+impl OrderedKey for bool {
+	fn encode_ordered(&self, out: &mut Vec<u8>) {
+		out.push(*self as u8);
+	}
+}
+
+impl DecodeOrdered for bool {
+	fn decode_ordered(input: &mut &[u8]) -> Result<Self, Error> {
+		match take(input, 1)? {
+			[0] => Ok(false),
+			[1] => Ok(true),
+			_ => Err(invalid("expected a bool")),
+		}
+	}
+}
+
+impl OrderedKey for Timestamp {
+	fn encode_ordered(&self, out: &mut Vec<u8>) {
+		self.as_millis().encode_ordered(out);
+	}
+}
+
+impl DecodeOrdered for Timestamp {
+	fn decode_ordered(input: &mut &[u8]) -> Result<Self, Error> {
+		Ok(Self::from_millis(f64::decode_ordered(input)?))
+	}
+}
+
+// Bytes are terminated by `0x00 0x01`, and zero bytes within them are escaped as `0x00 0xFF`.
+fn encode_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+	for byte in bytes {
+		match byte {
+			0 => out.extend_from_slice(&[0, 0xFF]),
+			byte => out.push(*byte),
+		}
+	}
+	out.extend_from_slice(&[0, 1]);
+}
+
+fn decode_bytes(input: &mut &[u8]) -> Result<Vec<u8>, Error> {
+	let mut bytes = Vec::new();
+	loop {
+		match take(input, 1)? {
+			[0] => match take(input, 1)? {
+				[1] => return Ok(bytes),
+				[0xFF] => bytes.push(0),
+				_ => return Err(invalid("unknown escape")),
+			},
+			[byte] => bytes.push(*byte),
+			_ => unreachable!(),
+		}
+	}
+}
+
+impl OrderedKey for [u8] {
+	fn encode_ordered(&self, out: &mut Vec<u8>) {
+		encode_bytes(self, out);
+	}
+}
+
+impl OrderedKey for BinaryKey {
+	fn encode_ordered(&self, out: &mut Vec<u8>) {
+		encode_bytes(self.as_bytes(), out);
+	}
+}
+
+impl DecodeOrdered for BinaryKey {
+	fn decode_ordered(input: &mut &[u8]) -> Result<Self, Error> {
+		Ok(Self(decode_bytes(input)?))
+	}
+}
+
+impl OrderedKey for str {
+	fn encode_ordered(&self, out: &mut Vec<u8>) {
+		encode_bytes(self.as_bytes(), out);
+	}
+}
+
+impl OrderedKey for String {
+	fn encode_ordered(&self, out: &mut Vec<u8>) {
+		encode_bytes(self.as_bytes(), out);
+	}
+}
+
+impl DecodeOrdered for String {
+	fn decode_ordered(input: &mut &[u8]) -> Result<Self, Error> {
+		String::from_utf8(decode_bytes(input)?).map_err(|_| invalid("the string is not UTF-8"))
+	}
+}
+
+impl<K: OrderedKey + ?Sized> OrderedKey for &K {
+	fn encode_ordered(&self, out: &mut Vec<u8>) {
+		(**self).encode_ordered(out);
+	}
+}
+
+macro_rules! tuple_key {
+	($($name:ident),+) => {
+		impl<$($name: OrderedKey),+> OrderedKey for ($($name,)+) {
+			#[allow(non_snake_case)]
+			fn encode_ordered(&self, out: &mut Vec<u8>) {
+				let ($($name,)+) = self;
+				$($name.encode_ordered(out);)+
+			}
+		}
+
+		impl<$($name: DecodeOrdered),+> DecodeOrdered for ($($name,)+) {
+			fn decode_ordered(input: &mut &[u8]) -> Result<Self, Error> {
+				Ok(($($name::decode_ordered(input)?,)+))
+			}
+		}
+	};
+}
+tuple_key!(A);
+tuple_key!(A, B);
+tuple_key!(A, B, C);
+tuple_key!(A, B, C, D);
+tuple_key!(A, B, C, D, E);
+tuple_key!(A, B, C, D, E, F);
+
+/// A key which sorts in reverse, such as a timestamp for scanning the newest records first.
+/// Its [ordered encoding](OrderedKey) has every bit flipped, and it is stored as a binary key of that encoding,
+/// both when it is used directly as a key (such as in a tuple) and when it is a field of a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Descending<K>(pub K);
+
+impl<K: PartialOrd> PartialOrd for Descending<K> {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		other.0.partial_cmp(&self.0)
+	}
+}
+
+impl<K: Ord> Ord for Descending<K> {
+	fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+		other.0.cmp(&self.0)
+	}
+}
+
+impl<K: OrderedKey> OrderedKey for Descending<K> {
+	fn encode_ordered(&self, out: &mut Vec<u8>) {
+		let start = out.len();
+		self.0.encode_ordered(out);
+		for byte in &mut out[start..] {
+			*byte = !*byte;
+		}
+	}
+}
+
+impl<K: DecodeOrdered> DecodeOrdered for Descending<K> {
+	fn decode_ordered(input: &mut &[u8]) -> Result<Self, Error> {
+		// encodings never depend on what follows them, so the rest of the input is flipped and decoded
+		let flipped = input.iter().map(|byte| !byte).collect::<Vec<_>>();
+		let mut rest = flipped.as_slice();
+		let key = K::decode_ordered(&mut rest)?;
+		*input = &input[flipped.len() - rest.len()..];
+		Ok(Self(key))
+	}
+}
+
+impl<K: OrderedKey> Serialize for Descending<K> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		encode_ordered(self).serialize(serializer)
+	}
+}
+
+impl<'de, K: DecodeOrdered> Deserialize<'de> for Descending<K> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let key = BinaryKey::deserialize(deserializer)?;
+		decode_ordered(key.as_bytes()).map_err(serde::de::Error::custom)
+	}
+}
+
+impl<K: OrderedKey> crate::indexed_key::sealed::Sealed for Descending<K> {}
+impl<K: OrderedKey> IndexedKey for Descending<K> {
+	fn to_js_key(&self) -> Result<JsValue, Error> {
+		encode_ordered(self).to_js_key()
+	}
+}
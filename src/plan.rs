@@ -0,0 +1,53 @@
+use super::{Error, IndexLayout, StoreLayout};
+
+/// An operation which upgrading a database performs, as listed by [`Schema::plan`](crate::Schema::plan)
+/// or [`SchemaLayout::plan`](crate::SchemaLayout::plan).
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationOp {
+	CreateStore(StoreLayout),
+	DeleteStore(String),
+	CreateIndex {
+		store: String,
+		index: IndexLayout,
+	},
+	DeleteIndex {
+		store: String,
+		index: String,
+	},
+	/// Any other step, such as a data migration, described for display.
+	Custom(String),
+}
+
+impl MigrationOp {
+	/// Performs the operation in an upgrade transaction. [`MigrationOp::Custom`] operations do nothing,
+	/// since they only describe work done by a schema.
+	pub fn apply(&self, transaction: &idb::Transaction) -> Result<(), Error> {
+		match self {
+			Self::CreateStore(store) => store.create(&transaction.database())?,
+			Self::DeleteStore(name) => transaction.database().delete_object_store(name)?,
+			Self::CreateIndex { store, index } => index.create(&transaction.object_store(store)?)?,
+			Self::DeleteIndex { store, index } => transaction.object_store(store)?.delete_index(index)?,
+			Self::Custom(_) => {}
+		}
+		Ok(())
+	}
+}
+
+impl std::fmt::Display for MigrationOp {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::CreateStore(store) => write!(f, "create store {:?}", store.name),
+			Self::DeleteStore(name) => write!(f, "delete store {name:?}"),
+			Self::CreateIndex { store, index } => write!(f, "create index {:?} on store {store:?}", index.name),
+			Self::DeleteIndex { store, index } => write!(f, "delete index {index:?} on store {store:?}"),
+			Self::Custom(description) => write!(f, "{description}"),
+		}
+	}
+}
+
+/// The operations which upgrading to a version of a [`Schema`](crate::Schema) performs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedVersion {
+	pub version: u32,
+	pub operations: Vec<MigrationOp>,
+}
@@ -1,10 +1,97 @@
+use crate::Error;
+use wasm_bindgen::JsValue;
+
 // The data type for all entries in a given ObjectStore.
 pub trait Record: serde::Serialize {
 	fn store_id() -> &'static str;
 	fn key(&self) -> Option<&String> {
 		None
 	}
+	/// The key of this record, used when writing to a store without a key path.
+	/// Defaults to [`Record::key`]; types which derive `Record` can key by a field of any serializable type.
+	fn key_value(&self) -> Result<Option<JsValue>, Error> {
+		Ok(self.key().map(JsValue::from))
+	}
 	fn as_value(&self) -> Result<wasm_bindgen::JsValue, serde_wasm_bindgen::Error> {
-		Ok(self.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?)
+		to_value(self)
+	}
+}
+
+/// A record with a version field, which is changed on every write,
+/// for conditional writes with [`Store::put_if`](crate::Store::put_if).
+pub trait Versioned {
+	fn version(&self) -> u64;
+}
+
+/// Serializes a value into the form it is stored in the database.
+pub(crate) fn to_value<T: serde::Serialize + ?Sized>(
+	value: &T,
+) -> Result<wasm_bindgen::JsValue, serde_wasm_bindgen::Error> {
+	SerializerOptions::new().serialize(value)
+}
+
+/// How records are converted to JS values, set for a store with
+/// [`StoreDescriptor::serializer`](crate::StoreDescriptor::serializer).
+/// The defaults are JSON compatible: maps become plain objects (so key paths can read their fields),
+/// `None` and unit values become `null`, and bytes become arrays of numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerializerOptions {
+	missing_as_null: bool,
+	maps_as_objects: bool,
+	large_numbers_as_bigints: bool,
+	bytes_as_arrays: bool,
+}
+
+impl Default for SerializerOptions {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl SerializerOptions {
+	pub const fn new() -> Self {
+		Self {
+			missing_as_null: true,
+			maps_as_objects: true,
+			large_numbers_as_bigints: false,
+			bytes_as_arrays: true,
+		}
+	}
+
+	/// Serializes `None` and unit values as `null` instead of `undefined`.
+	pub const fn missing_as_null(mut self, value: bool) -> Self {
+		self.missing_as_null = value;
+		self
+	}
+
+	/// Serializes maps as plain objects instead of JS `Map`s, which key paths cannot read.
+	pub const fn maps_as_objects(mut self, value: bool) -> Self {
+		self.maps_as_objects = value;
+		self
+	}
+
+	/// Serializes `i64` and `u64` as `BigInt`s, instead of numbers (which fail if the value does not fit exactly).
+	/// Note that `BigInt`s are not valid keys.
+	pub const fn large_numbers_as_bigints(mut self, value: bool) -> Self {
+		self.large_numbers_as_bigints = value;
+		self
+	}
+
+	/// Serializes bytes as arrays of numbers instead of `Uint8Array`s.
+	pub const fn bytes_as_arrays(mut self, value: bool) -> Self {
+		self.bytes_as_arrays = value;
+		self
+	}
+
+	pub(crate) fn serialize<T: serde::Serialize + ?Sized>(
+		&self,
+		value: &T,
+	) -> Result<wasm_bindgen::JsValue, serde_wasm_bindgen::Error> {
+		let serializer = serde_wasm_bindgen::Serializer::new()
+			.serialize_missing_as_null(self.missing_as_null)
+			.serialize_maps_as_objects(self.maps_as_objects)
+			.serialize_large_number_types_as_bigints(self.large_numbers_as_bigints)
+			.serialize_bytes_as_arrays(self.bytes_as_arrays);
+		value.serialize(&serializer)
 	}
 }
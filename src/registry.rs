@@ -0,0 +1,99 @@
+use super::{Client, ClientBuilder, Error, LazyClient, MissingVersion, Schema};
+use futures_util::future::LocalBoxFuture;
+use std::{cell::RefCell, collections::BTreeMap, future::Future, rc::Rc};
+
+type Opener = Rc<dyn Fn(ClientBuilder) -> LocalBoxFuture<'static, Result<Client, Error>>>;
+
+/// Opens and upgrades several named databases, each with its own schema,
+/// and hands out a shared [`Client`] for each by name.
+/// ```ignore
+/// let registry = DatabaseRegistry::new()
+///   .register::<UserVersion>("user")
+///   .register_with("cache", |builder| builder.version_policy(VersionPolicy::Recreate).open::<CacheVersion>());
+/// registry.open_all().await?;
+/// let cache = registry.client("cache").await?;
+/// ```
+#[derive(Default)]
+pub struct DatabaseRegistry {
+	openers: BTreeMap<String, Opener>,
+	/// The client of each database which has been requested, shared so that concurrent requests open it once.
+	clients: RefCell<BTreeMap<String, LazyClient>>,
+	prefix: String,
+}
+
+impl DatabaseRegistry {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Prepends `prefix` to the name of every database when it is opened, see [`ClientBuilder::prefix`].
+	/// Databases are still looked up by their unprefixed names.
+	pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+		self.prefix = prefix.into();
+		self
+	}
+
+	/// Registers a database which is opened at the latest version of the schema `V`.
+	pub fn register<V>(self, name: impl Into<String>) -> Self
+	where
+		V: 'static + Schema + TryFrom<u32, Error = MissingVersion>,
+	{
+		self.register_with(name, |builder| builder.open::<V>())
+	}
+
+	/// Registers a database which is opened by `open`, such as to configure the builder
+	/// or open the database with [`Migrations`](crate::Migrations).
+	pub fn register_with<F, Fut>(mut self, name: impl Into<String>, open: F) -> Self
+	where
+		F: Fn(ClientBuilder) -> Fut + 'static,
+		Fut: Future<Output = Result<Client, Error>> + 'static,
+	{
+		self.openers
+			.insert(name.into(), Rc::new(move |builder| Box::pin(open(builder))));
+		self
+	}
+
+	/// The names of every registered database.
+	pub fn names(&self) -> impl Iterator<Item = &str> {
+		self.openers.keys().map(String::as_str)
+	}
+
+	/// Opens (and upgrades) every registered database which isn't already open.
+	pub async fn open_all(&self) -> Result<(), Error> {
+		for name in self.openers.keys() {
+			self.client(name).await?;
+		}
+		Ok(())
+	}
+
+	/// The client for the database registered as `name`, opening it if it isn't already open
+	/// (or if its connection has since been closed). Concurrent calls share the same open.
+	pub async fn client(&self, name: &str) -> Result<Client, Error> {
+		let lazy = {
+			let mut clients = self.clients.borrow_mut();
+			match clients.get(name) {
+				Some(lazy) => lazy.clone(),
+				None => {
+					let Some(open) = self.openers.get(name).cloned() else {
+						return Err(Error::UnregisteredDatabase(name.to_owned()));
+					};
+					let (database, prefix) = (name.to_owned(), self.prefix.clone());
+					let lazy = LazyClient::new(move || open(Client::builder(&database).prefix(&prefix)));
+					clients.insert(name.to_owned(), lazy.clone());
+					lazy
+				}
+			}
+		};
+		lazy.client().await
+	}
+
+	/// The client for the database registered as `name`, if it has been opened.
+	pub fn get(&self, name: &str) -> Option<Client> {
+		self.clients.borrow().get(name)?.get()
+	}
+
+	/// Releases the registry's client for `name`. The connection closes once every other clone of it is dropped.
+	pub fn close(&self, name: &str) {
+		self.clients.borrow_mut().remove(name);
+	}
+}
@@ -0,0 +1,131 @@
+use super::{Client, Cursor, CursorDirection, Error, Record, TransactionExt};
+use futures_util::future::LocalBoxFuture;
+use serde::Deserialize;
+use std::{
+	pin::Pin,
+	task::{ready, Poll},
+};
+use wasm_bindgen::JsValue;
+
+/// A stream over every record in the store of `V`, which outlives the transaction it started in.
+/// If the transaction finishes while the stream is being consumed (such as when the consumer awaits
+/// something other than the database between entries), a new read-only transaction is opened
+/// and iteration resumes after the key of the last yielded record.
+///
+/// Created by [`Client::resumable_cursor`].
+/// ```ignore
+/// let mut entries = client.resumable_cursor::<Entry>(None);
+/// while let Some(entry) = entries.next().await {
+///   upload(entry?).await;
+/// }
+/// ```
+pub struct ResumableCursor<V> {
+	client: Client,
+	direction: CursorDirection,
+	/// The key of the record which was most recently yielded, which the next cursor is opened after.
+	last_key: Option<JsValue>,
+	cursor: Option<Cursor<V>>,
+	opening: Option<LocalBoxFuture<'static, Result<Cursor<V>, Error>>>,
+	/// True if the current cursor has yielded a record since it was opened.
+	/// A cursor which fails before yielding anything is not reopened, so that it cannot retry forever.
+	progressed: bool,
+	done: bool,
+}
+
+impl<V: Record + 'static> ResumableCursor<V> {
+	pub(crate) fn new(client: Client, direction: Option<CursorDirection>) -> Self {
+		Self {
+			client,
+			direction: direction.unwrap_or_default(),
+			last_key: None,
+			cursor: None,
+			opening: None,
+			progressed: false,
+			done: false,
+		}
+	}
+
+	/// The direction the cursor was opened with.
+	pub fn direction(&self) -> CursorDirection {
+		self.direction
+	}
+
+	/// Opens a cursor in a new transaction, starting after the last yielded key.
+	fn open(&self) -> LocalBoxFuture<'static, Result<Cursor<V>, Error>> {
+		let client = self.client.clone();
+		let direction = self.direction;
+		let last_key = self.last_key.clone();
+		Box::pin(async move {
+			let query = match &last_key {
+				None => None,
+				Some(key) => Some(idb::Query::KeyRange(match direction {
+					CursorDirection::Next | CursorDirection::NextUnique => idb::KeyRange::lower_bound(key, Some(true))?,
+					CursorDirection::Prev | CursorDirection::PrevUnique => idb::KeyRange::upper_bound(key, Some(true))?,
+				})),
+			};
+			let transaction = client.read_only::<V>()?;
+			let store = transaction.object_store_of::<V>()?;
+			let cursor = store.open_cursor(query.clone(), Some(direction))?.await?;
+			Ok(Cursor::new(cursor).with_query(query.as_ref()))
+		})
+	}
+}
+
+impl<V> futures_util::stream::Stream for ResumableCursor<V>
+where
+	V: Record + for<'de> Deserialize<'de> + 'static,
+{
+	type Item = Result<V, Error>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = self.get_mut();
+		loop {
+			if let Some(opening) = &mut this.opening {
+				let opened = ready!(opening.as_mut().poll(cx));
+				this.opening = None;
+				match opened {
+					Ok(cursor) => {
+						this.cursor = Some(cursor);
+						this.progressed = false;
+					}
+					Err(err) => {
+						this.done = true;
+						return Poll::Ready(Some(Err(err)));
+					}
+				}
+			}
+
+			let Some(cursor) = &mut this.cursor else {
+				if this.done {
+					return Poll::Ready(None);
+				}
+				this.opening = Some(this.open());
+				continue;
+			};
+
+			match ready!(cursor.poll_next_raw(cx)) {
+				None => {
+					this.cursor = None;
+					this.done = true;
+					return Poll::Ready(None);
+				}
+				// The transaction committed while the consumer was busy, so continue in a new one.
+				Some(Err(Error::TransactionInactive)) if this.progressed => {
+					this.cursor = None;
+				}
+				Some(Err(err)) => {
+					this.cursor = None;
+					this.done = true;
+					return Poll::Ready(Some(Err(err)));
+				}
+				Some(Ok(entry)) => {
+					this.last_key = Some(entry.key);
+					this.progressed = true;
+					return Poll::Ready(Some(
+						serde_wasm_bindgen::from_value::<V>(entry.value).map_err(Error::from),
+					));
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,68 @@
+use super::Error;
+use std::time::Duration;
+use wasm_bindgen::{JsCast, JsValue};
+
+/// How [`Client::retrying`](crate::Client::retrying) retries transactions which fail with a
+/// [transient](Error::is_transient) error. The delay doubles after each failed attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+	/// The most times the transaction is attempted, including the first attempt.
+	pub attempts: u32,
+	/// How long to wait before the first retry.
+	pub backoff: Duration,
+	/// The longest to wait between attempts.
+	pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self {
+			attempts: 3,
+			backoff: Duration::from_millis(50),
+			max_backoff: Duration::from_secs(2),
+		}
+	}
+}
+
+impl RetryPolicy {
+	/// Never retries.
+	pub fn none() -> Self {
+		Self {
+			attempts: 1,
+			..Default::default()
+		}
+	}
+
+	pub fn with_attempts(mut self, attempts: u32) -> Self {
+		self.attempts = attempts;
+		self
+	}
+
+	pub fn with_backoff(mut self, backoff: Duration) -> Self {
+		self.backoff = backoff;
+		self
+	}
+
+	/// The delay before `retry` (starting at 0 for the first retry).
+	pub fn delay(&self, retry: u32) -> Duration {
+		let delay = self.backoff.saturating_mul(2u32.saturating_pow(retry));
+		delay.min(self.max_backoff)
+	}
+}
+
+/// Waits for `duration` using the `setTimeout` of the global scope.
+pub(crate) async fn sleep(duration: Duration) -> Result<(), Error> {
+	let js_error = |err: JsValue| Error::Internal(format!("{err:?}"));
+	let set_timeout = js_sys::Reflect::get(&js_sys::global(), &"setTimeout".into()).map_err(js_error)?;
+	let set_timeout = set_timeout
+		.dyn_into::<js_sys::Function>()
+		.map_err(|_| Error::Unsupported("setTimeout".into()))?;
+	let millis = JsValue::from(duration.as_millis().min(i32::MAX as u128) as i32);
+	let mut scheduled = Ok(JsValue::UNDEFINED);
+	let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+		scheduled = set_timeout.call2(&JsValue::UNDEFINED, &resolve, &millis);
+	});
+	scheduled.map_err(js_error)?;
+	wasm_bindgen_futures::JsFuture::from(promise).await.map_err(js_error)?;
+	Ok(())
+}
@@ -1,4 +1,64 @@
+use super::{Error, MigrationOp, MissingVersion, PlannedVersion, SchemaLayout};
+
 pub trait Schema {
 	fn latest() -> u32;
 	fn apply(&self, database: &crate::Client) -> Result<(), crate::Error>;
+
+	/// Applies this version with access to the upgrade transaction, so that existing records
+	/// can be read, transformed, and written back atomically with any structural changes.
+	/// Defaults to [`Schema::apply`].
+	fn apply_with_tx(&self, database: &crate::Client, _transaction: &crate::Transaction) -> Result<(), crate::Error> {
+		self.apply(database)
+	}
+
+	/// Applies this version knowing the version the database is being upgraded from (`None` if it is being created)
+	/// and the version the upgrade ends at. Defaults to [`Schema::apply_with_tx`].
+	fn apply_versioned(
+		&self,
+		database: &crate::Client,
+		transaction: &crate::Transaction,
+		_old: Option<u32>,
+		_new: u32,
+	) -> Result<(), crate::Error> {
+		self.apply_with_tx(database, transaction)
+	}
+
+	/// The stores and indexes of the database once this version has been applied, if the schema declares them.
+	/// Defaults to `None`.
+	fn layout(&self) -> Option<SchemaLayout> {
+		None
+	}
+
+	/// Describes the operations which applying this version performs, for [`Schema::plan`].
+	/// Defaults to no operations, in which case the plan lists the difference between the [layout](Schema::layout)
+	/// of this version and the version before it (if both declare one).
+	fn operations(&self) -> Vec<MigrationOp> {
+		Vec::new()
+	}
+
+	/// Lists the versions (and their operations) which would be applied to a database at `from_version`,
+	/// without opening the database. Fails if a version is missing, or if the layouts of two versions
+	/// differ in a way which cannot be migrated, such as the key of a store.
+	fn plan(from_version: u32) -> Result<Vec<PlannedVersion>, Error>
+	where
+		Self: Sized + TryFrom<u32, Error = MissingVersion>,
+	{
+		// a new database has no stores
+		let mut previous = match from_version {
+			0 => Some(SchemaLayout::default()),
+			version => Self::try_from(version).ok().and_then(|version| version.layout()),
+		};
+		let mut plan = Vec::new();
+		for version in (from_version + 1)..=Self::latest() {
+			let schema = Self::try_from(version)?;
+			let layout = schema.layout();
+			let mut operations = schema.operations();
+			if let (true, Some(previous), Some(layout)) = (operations.is_empty(), &previous, &layout) {
+				operations = layout.plan(previous)?;
+			}
+			plan.push(PlannedVersion { version, operations });
+			previous = layout;
+		}
+		Ok(plan)
+	}
 }
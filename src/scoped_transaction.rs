@@ -0,0 +1,206 @@
+use super::{Error, StoreDescriptor, Transaction, TransactionMode, TransactionStore};
+use futures::channel::oneshot;
+use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+use wasm_bindgen::JsValue;
+
+/// The access mode of a [`ScopedTransaction`], as a type, so that writes cannot be made in read-only transactions.
+pub trait Mode: sealed::Sealed {
+	const MODE: TransactionMode;
+}
+
+/// Transactions which may only read records.
+pub struct ReadOnly;
+
+/// Transactions which may read and write records.
+pub struct ReadWrite;
+
+impl Mode for ReadOnly {
+	const MODE: TransactionMode = TransactionMode::ReadOnly;
+}
+
+impl Mode for ReadWrite {
+	const MODE: TransactionMode = TransactionMode::ReadWrite;
+}
+
+mod sealed {
+	pub trait Sealed {}
+	impl Sealed for super::ReadOnly {}
+	impl Sealed for super::ReadWrite {}
+}
+
+/// A transaction over a fixed set of object stores, created by [`Client::read_transaction`](crate::Client::read_transaction)
+/// or [`Client::write_transaction`](crate::Client::write_transaction).
+/// Accessing a store which the transaction was not created with fails with [`Error::StoreNotInScope`]
+/// without reaching IndexedDB, and the stores of a [`ReadOnly`] transaction have no methods for writing.
+/// ```ignore
+/// let transaction = client.write_transaction(&[&USERS, &POSTS])?;
+/// transaction.store(&USERS)?.put(&user).await?;
+/// transaction.store(&POSTS)?.put(&post).await?;
+/// transaction.commit().await?;
+/// ```
+pub struct ScopedTransaction<M> {
+	transaction: Transaction,
+	stores: Vec<&'static str>,
+	mode: PhantomData<M>,
+}
+
+impl<M: Mode> ScopedTransaction<M> {
+	pub(crate) fn new(transaction: Transaction, stores: Vec<&'static str>) -> Self {
+		Self {
+			transaction,
+			stores,
+			mode: PhantomData,
+		}
+	}
+
+	/// The names of the stores which this transaction can access.
+	pub fn stores(&self) -> &[&'static str] {
+		&self.stores
+	}
+
+	pub fn mode(&self) -> TransactionMode {
+		M::MODE
+	}
+
+	/// A typed handle to one of the stores of the transaction.
+	pub fn store<T>(&self, descriptor: &StoreDescriptor<T>) -> Result<TransactionStore<T, M>, Error> {
+		let name = descriptor.name();
+		if !self.stores.contains(&name) {
+			return Err(Error::StoreNotInScope(name.to_owned()));
+		}
+		Ok(TransactionStore::new(
+			self.transaction.object_store(name)?,
+			descriptor.serializer_options(),
+		))
+	}
+
+	/// Commits the transaction as soon as its pending requests finish, instead of waiting for it to
+	/// auto-commit once it has no more work, and waits until the changes are written.
+	/// Fails with [`Error::TransactionAborted`] if the transaction was aborted.
+	pub async fn commit(self) -> Result<(), Error> {
+		commit(self.transaction.0).await
+	}
+
+	/// Discards every change made in the transaction.
+	pub fn abort(self) -> Result<(), Error> {
+		self.transaction.0.abort()?;
+		Ok(())
+	}
+
+	/// Called once the transaction has committed. See [`Transaction::on_complete`].
+	pub fn on_complete(&self, callback: impl FnOnce() + 'static) -> Result<(), Error> {
+		self.transaction.on_complete(callback)
+	}
+
+	/// Called if the transaction is aborted. See [`Transaction::on_abort`].
+	pub fn on_abort(&self, callback: impl FnOnce(Option<Error>) + 'static) -> Result<(), Error> {
+		self.transaction.on_abort(callback)
+	}
+
+	/// Called with the first request of the transaction to fail. See [`Transaction::on_error`].
+	pub fn on_error(&self, callback: impl FnOnce(Error) + 'static) -> Result<(), Error> {
+		self.transaction.on_error(callback)
+	}
+
+	/// The untyped transaction, for accessing stores through [`TransactionExt`](crate::TransactionExt).
+	pub fn into_inner(self) -> Transaction {
+		self.transaction
+	}
+}
+
+/// Aborts a transaction when dropped, unless it was committed first.
+/// Holds one of the transaction's stores, since the transaction itself is moved into the caller's scope.
+pub(crate) struct AbortGuard {
+	store: Option<idb::ObjectStore>,
+	finished: Option<oneshot::Receiver<idb::TransactionResult>>,
+}
+
+impl AbortGuard {
+	pub(crate) fn new<M>(transaction: &ScopedTransaction<M>) -> Result<Self, Error> {
+		let Some(name) = transaction.stores.first() else {
+			return Ok(Self {
+				store: None,
+				finished: None,
+			});
+		};
+		let store = transaction.transaction.object_store(name)?;
+		// the transaction's events are the only record of an explicit abort once it has finished
+		let (sender, receiver) = oneshot::channel();
+		let sender = Rc::new(RefCell::new(Some(sender)));
+		let send = |sender: Rc<RefCell<Option<oneshot::Sender<_>>>>, result| {
+			if let Some(sender) = sender.borrow_mut().take() {
+				let _ = sender.send(result);
+			}
+		};
+		let on_complete = sender.clone();
+		transaction
+			.transaction
+			.on_complete(move || send(on_complete, idb::TransactionResult::Committed))?;
+		transaction
+			.transaction
+			.on_abort(move |_| send(sender, idb::TransactionResult::Aborted))?;
+		Ok(Self {
+			store: Some(store),
+			finished: Some(receiver),
+		})
+	}
+
+	pub(crate) async fn commit(&mut self) -> Result<(), Error> {
+		let (Some(store), Some(finished)) = (self.store.take(), self.finished.take()) else {
+			return Ok(());
+		};
+		match store.transaction().commit() {
+			Ok(_) => {}
+			// the transaction is no longer active, because it is already committing or aborting on its own
+			Err(err) if crate::error::dom_exception_name(&err).as_deref() == Some("InvalidStateError") => {}
+			Err(err) => return Err(err.into()),
+		}
+		match finished.await {
+			Ok(idb::TransactionResult::Committed) => Ok(()),
+			Ok(idb::TransactionResult::Aborted) => Err(Error::TransactionAborted("the commit was aborted".into())),
+			Err(oneshot::Canceled) => Err(Error::Internal("the transaction finished without an event".into())),
+		}
+	}
+}
+
+/// Commits the transaction without waiting for it to run out of requests, and waits until the changes are written.
+/// Fails with [`Error::TransactionAborted`] if the transaction was aborted instead.
+pub(crate) async fn commit(transaction: idb::Transaction) -> Result<(), Error> {
+	// commit() consumes the handle, but the outcome must still be awaited if it throws
+	let handle = JsValue::from(transaction);
+	let result = match idb::Transaction::try_from(handle.clone())?.commit() {
+		Ok(transaction) => transaction.await?,
+		// the transaction is no longer active, because it is already committing or aborting on its own
+		Err(err) if crate::error::dom_exception_name(&err).as_deref() == Some("InvalidStateError") => {
+			outcome(idb::Transaction::try_from(handle)?).await?
+		}
+		Err(err) => return Err(err.into()),
+	};
+	match result {
+		idb::TransactionResult::Committed => Ok(()),
+		idb::TransactionResult::Aborted => Err(Error::TransactionAborted("the commit was aborted".into())),
+	}
+}
+
+/// Waits for a transaction which is no longer active to commit or abort. A transaction which has already finished
+/// is only known to have aborted if it failed with an error, since an explicit abort leaves no trace.
+async fn outcome(transaction: idb::Transaction) -> Result<idb::TransactionResult, Error> {
+	let name = transaction.store_names().into_iter().next();
+	// objectStore() only fails once the transaction has finished, when its events have already been sent
+	if name.is_none_or(|name| transaction.object_store(&name).is_err()) {
+		return Ok(match transaction.error() {
+			Some(_) => idb::TransactionResult::Aborted,
+			None => idb::TransactionResult::Committed,
+		});
+	}
+	Ok(transaction.await?)
+}
+
+impl Drop for AbortGuard {
+	fn drop(&mut self) {
+		if let Some(store) = self.store.take() {
+			// fails if the transaction already finished, in which case there is nothing to abort
+			let _ = store.transaction().abort();
+		}
+	}
+}
@@ -0,0 +1,113 @@
+use super::{Client, Error, SchemaBuilder, TransactionMode};
+use std::time::Duration;
+use wasm_bindgen::{JsCast, JsValue};
+
+/// How much storage the origin is using, from [`Client::storage_estimate`](crate::Client::storage_estimate).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+pub struct StorageEstimate {
+	/// The bytes used by the origin, across IndexedDB and other storage.
+	pub usage: u64,
+	/// The bytes the origin may use before writes fail with a quota error.
+	pub quota: u64,
+}
+
+impl StorageEstimate {
+	/// The bytes which can still be written before reaching the quota.
+	pub fn available(&self) -> u64 {
+		self.quota.saturating_sub(self.usage)
+	}
+
+	/// The fraction of the quota which is used, from 0 to 1.
+	pub fn fraction_used(&self) -> f64 {
+		match self.quota {
+			0 => 1.0,
+			quota => self.usage as f64 / quota as f64,
+		}
+	}
+}
+
+/// Whether IndexedDB can be used in this environment, from [`Client::probe`](crate::Client::probe).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Availability {
+	/// Databases can be created and written to.
+	Available,
+	/// Databases can be created, but the quota is too small to write to them,
+	/// as in some private browsing modes.
+	QuotaRestricted,
+	/// IndexedDB is missing or cannot be opened, such as in private browsing modes which disable it.
+	Unavailable(Error),
+}
+
+/// The result of [`Client::probe`](crate::Client::probe).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeReport {
+	pub availability: Availability,
+	/// The storage estimate of the origin, if the browser provides one.
+	pub estimate: Option<StorageEstimate>,
+}
+
+impl ProbeReport {
+	pub fn is_available(&self) -> bool {
+		self.availability == Availability::Available
+	}
+}
+
+const PROBE_DATABASE: &str = "__database_probe";
+
+pub(crate) async fn probe() -> ProbeReport {
+	let availability = match write_probe().await {
+		Ok(()) => Availability::Available,
+		Err(Error::QuotaExceeded) => Availability::QuotaRestricted,
+		Err(err) => Availability::Unavailable(err),
+	};
+	if let Err(err) = Client::delete_database(PROBE_DATABASE).await {
+		log::warn!(target: "database::storage", "Failed to delete probe database: {err}");
+	}
+	ProbeReport {
+		availability,
+		estimate: estimate().await.ok(),
+	}
+}
+
+/// Creates a database and writes a record to it.
+async fn write_probe() -> Result<(), Error> {
+	let layout = SchemaBuilder::new().store("probe", |store| store).build();
+	let client = Client::builder(PROBE_DATABASE)
+		.timeout(Duration::from_secs(5))
+		.open_layout(1, layout)
+		.await?;
+	let transaction = client.transaction(&["probe"], TransactionMode::ReadWrite)?;
+	let store = transaction.object_store("probe")?;
+	store.put(&JsValue::from("probe"), Some(&JsValue::from(0)))?.await?;
+	crate::scoped_transaction::commit(transaction.0).await?;
+	client.close();
+	Ok(())
+}
+
+pub(crate) async fn estimate() -> Result<StorageEstimate, Error> {
+	let estimate = call_storage("estimate").await?;
+	Ok(serde_wasm_bindgen::from_value(estimate)?)
+}
+
+/// Asks the browser not to evict the origin's storage under storage pressure, returning true if it agreed.
+pub(crate) async fn persist() -> Result<bool, Error> {
+	Ok(call_storage("persist").await?.as_bool().unwrap_or_default())
+}
+
+/// True if the origin's storage has been made persistent.
+pub(crate) async fn persisted() -> Result<bool, Error> {
+	Ok(call_storage("persisted").await?.as_bool().unwrap_or_default())
+}
+
+/// Calls a method of `navigator.storage` and waits for the promise it returns.
+async fn call_storage(method: &str) -> Result<JsValue, Error> {
+	let unsupported = || Error::Unsupported(format!("navigator.storage.{method}()"));
+	let js_error = |err: JsValue| Error::Internal(format!("{err:?}"));
+	let navigator = js_sys::Reflect::get(&js_sys::global(), &"navigator".into()).map_err(|_| unsupported())?;
+	let storage = js_sys::Reflect::get(&navigator, &"storage".into()).map_err(|_| unsupported())?;
+	let function = js_sys::Reflect::get(&storage, &method.into()).map_err(|_| unsupported())?;
+	let function = function.dyn_into::<js_sys::Function>().map_err(|_| unsupported())?;
+	let promise = function.call0(&storage).map_err(js_error)?;
+	let promise = promise.dyn_into::<js_sys::Promise>().map_err(|_| unsupported())?;
+	wasm_bindgen_futures::JsFuture::from(promise).await.map_err(js_error)
+}
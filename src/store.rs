@@ -0,0 +1,1012 @@
+use super::{
+	ChangeEvent, ChangeKind, Client, Cursor, Error, Index, IndexType, IndexedKey, KeyRange, Mode, ReadOnly, ReadWrite,
+	SerializerOptions, StoreLayout, TransactionMode, Versioned,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{future::IntoFuture, marker::PhantomData};
+use wasm_bindgen::{JsCast, JsValue};
+
+/// Implements `Clone` for a handle whose record type `T` only appears behind a [`Store`],
+/// without the `T: Clone` bound which deriving would add.
+macro_rules! clone_handle {
+	($name:ident { $($field:ident),* }) => {
+		impl<T> Clone for $name<T> {
+			fn clone(&self) -> Self {
+				Self {
+					$($field: Clone::clone(&self.$field),)*
+				}
+			}
+		}
+	};
+}
+
+mod compressed;
+pub use compressed::*;
+mod encrypted;
+pub use encrypted::*;
+mod entry;
+pub use entry::*;
+mod expiring;
+pub use expiring::*;
+mod index;
+pub use index::*;
+mod large_value;
+pub use large_value::*;
+mod patch;
+mod query;
+pub use query::*;
+mod recode;
+pub use recode::*;
+mod revisioned;
+pub use revisioned::*;
+mod soft_delete;
+pub use soft_delete::*;
+mod tracked;
+pub use tracked::*;
+mod versioned;
+pub use versioned::*;
+
+/// Describes an object store whose records are all `T`, for creating the store in a schema
+/// and accessing it through a typed [`Store`] handle.
+/// ```ignore
+/// static USERS: StoreDescriptor<User> = StoreDescriptor::new("users").key_path(&["id"]);
+///
+/// // in Schema::apply
+/// USERS.create(database)?;
+/// // at runtime
+/// let user: Option<User> = client.store(&USERS).get("alice").await?;
+/// ```
+/// Stores with a compound key path are keyed by tuples, in the order of the key path:
+/// ```ignore
+/// static MEMBERS: StoreDescriptor<Member> = StoreDescriptor::new("members").key_path(&["team", "user"]);
+/// let member: Option<Member> = client.store(&MEMBERS).get(&("admins", "alice")).await?;
+/// ```
+pub struct StoreDescriptor<T> {
+	name: &'static str,
+	/// The record keys of the key path, or empty if keys are provided separately from values.
+	keys: &'static [&'static str],
+	auto_increment: bool,
+	serializer: SerializerOptions,
+	marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for StoreDescriptor<T> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<T> Copy for StoreDescriptor<T> {}
+
+impl<T> StoreDescriptor<T> {
+	pub const fn new(name: &'static str) -> Self {
+		Self {
+			name,
+			keys: &[],
+			auto_increment: false,
+			serializer: SerializerOptions::new(),
+			marker: PhantomData,
+		}
+	}
+
+	/// Sets the key path of records in the store. Multiple keys produce a compound key.
+	pub const fn key_path(mut self, keys: &'static [&'static str]) -> Self {
+		self.keys = keys;
+		self
+	}
+
+	/// Generates keys for records which don't have them.
+	pub const fn auto_increment(mut self) -> Self {
+		self.auto_increment = true;
+		self
+	}
+
+	/// Sets how records are converted to JS values when they are written through a [`Store`].
+	pub const fn serializer(mut self, options: SerializerOptions) -> Self {
+		self.serializer = options;
+		self
+	}
+
+	pub fn name(&self) -> &'static str {
+		self.name
+	}
+
+	pub fn serializer_options(&self) -> SerializerOptions {
+		self.serializer
+	}
+
+	pub fn to_key_path(&self) -> Option<idb::KeyPath> {
+		match self.keys {
+			[] => None,
+			[key] => Some(idb::KeyPath::new_single(key)),
+			keys => Some(idb::KeyPath::new_array(keys.to_vec())),
+		}
+	}
+
+	/// Creates the store in the database, during an upgrade.
+	pub fn create(&self, database: &idb::Database) -> Result<idb::ObjectStore, Error> {
+		let mut params = idb::ObjectStoreParams::new();
+		params.auto_increment(self.auto_increment);
+		params.key_path(self.to_key_path());
+		Ok(database.create_object_store(self.name, params)?)
+	}
+
+	/// The layout of the store, without any indexes.
+	pub fn layout(&self) -> StoreLayout {
+		StoreLayout {
+			name: self.name.to_owned(),
+			key_path: self.to_key_path(),
+			auto_increment: self.auto_increment,
+			indexes: Vec::new(),
+		}
+	}
+}
+
+/// A handle to an object store whose records are all `T`. Created by [`Client::store`], and cheap to clone.
+/// Each operation runs in its own transaction, and writes return once their transaction has committed.
+/// If the client [broadcasts changes](crate::ClientBuilder::broadcast_changes), each write is broadcast once committed.
+///
+/// Keys are serialized the same way as records, so a tuple is the key of a compound key path.
+///
+/// # Wrapped records
+/// The [compressed](Store::compressed), [encrypted](Store::encrypted), [versioned](Store::versioned)
+/// and [large value](Store::large_values) handles, and [`Encoded`](crate::Encoded) records, store each record
+/// inside a wrapper, whose fields key paths cannot reach. Their stores need out-of-line keys and have no indexes.
+/// Records which are encoded by an asynchronous browser API (compression and encryption) are encoded before the write
+/// transaction is opened, and decoded after the read transaction has finished, since a transaction commits as soon
+/// as no request is pending.
+pub struct Store<T> {
+	client: Client,
+	descriptor: StoreDescriptor<T>,
+}
+
+clone_handle!(Store { client, descriptor });
+
+impl<T> Store<T> {
+	pub(crate) fn new(client: Client, descriptor: StoreDescriptor<T>) -> Self {
+		Self { client, descriptor }
+	}
+
+	pub fn descriptor(&self) -> &StoreDescriptor<T> {
+		&self.descriptor
+	}
+
+	/// A typed handle to the index called `name`, for looking up records by a secondary key.
+	pub fn index(&self, name: &'static str) -> StoreIndex<T> {
+		StoreIndex::new(self.clone(), name)
+	}
+
+	/// A handle to the index described by the [`IndexType`] `I`, for its cursors and typed params.
+	/// The index is opened in a new read-only transaction, which finishes once the lookups queued on it have completed,
+	/// so the handle should be used right away rather than kept.
+	/// ```ignore
+	/// let user: Option<User> = client.store(&USERS).index_of::<UsersByEmail>()?.get(&by_email).await?;
+	/// ```
+	pub fn index_of<I: IndexType<Record = T>>(&self) -> Result<Index<I>, Error> {
+		Ok(Index::from(
+			self.object_store(TransactionMode::ReadOnly)?.index(I::name())?,
+		))
+	}
+
+	/// A handle to this store which writes compressed records. See [`CompressedStore`].
+	pub fn compressed(&self, compression: Compression) -> CompressedStore<T> {
+		CompressedStore::new(self.clone(), compression)
+	}
+
+	/// A handle to this store which encrypts records with `key`. See [`EncryptedStore`].
+	pub fn encrypted(&self, key: EncryptionKey) -> EncryptedStore<T> {
+		EncryptedStore::new(self.clone(), key)
+	}
+
+	/// A handle to this store which splits values larger than `threshold` bytes into chunks. See [`LargeValueStore`].
+	pub fn large_values(&self, threshold: usize) -> LargeValueStore<T> {
+		LargeValueStore::new(self.clone(), threshold)
+	}
+
+	/// A handle to this store which keeps records in schema envelopes, upgrading older ones as they are read.
+	/// See [`VersionedStore`].
+	pub fn versioned(&self, upgrades: Upgrades<T>) -> VersionedStore<T> {
+		VersionedStore::new(self.clone(), upgrades)
+	}
+
+	/// Starts a query over the records of the store. See [`StoreQuery`].
+	pub fn query(&self) -> StoreQuery<T> {
+		StoreQuery::new(self.clone())
+	}
+
+	/// Reads the value at `key` as it is stored, without deserializing it.
+	pub async fn get_raw<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<Option<JsValue>, Error> {
+		let key = crate::key_range::to_key(key)?;
+		let store = self.object_store(TransactionMode::ReadOnly)?;
+		Ok(store.get(idb::Query::Key(key))?.await?)
+	}
+
+	/// Inserts or replaces a value as it is, without serializing it.
+	/// The key is read from the value using the store's key path.
+	pub async fn put_raw(&self, value: &JsValue) -> Result<(), Error> {
+		self.write_raw(value, None).await
+	}
+
+	/// Inserts or replaces a value at `key` as it is, for stores without a key path.
+	/// The value can be anything which IndexedDB can clone, such as an `ArrayBuffer`.
+	pub async fn put_raw_with_key<K: IndexedKey + ?Sized>(&self, value: &JsValue, key: &K) -> Result<(), Error> {
+		self.write_raw(value, Some(crate::key_range::to_key(key)?)).await
+	}
+
+	/// Reads the `Blob` (or `File`) at `key`, which the browser keeps without copying it through Rust memory.
+	/// Fails with [`Error::Serialization`] if the value is not a `Blob`.
+	pub async fn get_blob<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<Option<web_sys::Blob>, Error> {
+		let Some(value) = self.get_raw(key).await? else {
+			return Ok(None);
+		};
+		let blob = value.dyn_into::<web_sys::Blob>();
+		Ok(Some(
+			blob.map_err(|_| Error::Serialization("the value is not a Blob".into()))?,
+		))
+	}
+
+	/// Stores a `Blob` (or `File`) at `key`, for stores without a key path.
+	pub async fn put_blob<K: IndexedKey + ?Sized>(&self, blob: &web_sys::Blob, key: &K) -> Result<(), Error> {
+		self.put_raw_with_key(blob, key).await
+	}
+
+	/// Reads the binary value at `key`, copying it straight out of its `Uint8Array` (or `ArrayBuffer`)
+	/// instead of deserializing it byte by byte. `bytes::Bytes::from` takes the result without copying it again.
+	/// Fails with [`Error::Serialization`] if the value is not binary.
+	pub async fn get_bytes<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<Option<Vec<u8>>, Error> {
+		self.get_raw(key).await?.as_ref().map(to_bytes).transpose()
+	}
+
+	/// Reads every binary value in the store, like [`Store::get_bytes`].
+	pub async fn get_all_bytes(&self) -> Result<Vec<Vec<u8>>, Error> {
+		let store = self.object_store(TransactionMode::ReadOnly)?;
+		let values = store.get_all(None, None)?.into_future().await?;
+		values.iter().map(to_bytes).collect()
+	}
+
+	/// Stores `bytes` at `key` as a `Uint8Array`, for stores without a key path.
+	pub async fn put_bytes<K: IndexedKey + ?Sized>(&self, bytes: &[u8], key: &K) -> Result<(), Error> {
+		self.put_raw_with_key(&js_sys::Uint8Array::from(bytes), key).await
+	}
+
+	/// Reads the keys and stored values of the records after the key `after` (or from the start of the store),
+	/// in key order and up to `count` of them, for reading a store in batches which each have their own transaction.
+	/// Both requests are queued before either is awaited, so the keys and values belong to the same records.
+	async fn read_batch(
+		&self,
+		after: Option<&JsValue>,
+		count: Option<u32>,
+	) -> Result<(Vec<JsValue>, Vec<JsValue>), Error> {
+		let store = self.object_store(TransactionMode::ReadOnly)?;
+		let query = match after {
+			Some(key) => Some(idb::Query::KeyRange(idb::KeyRange::lower_bound(key, Some(true))?)),
+			None => None,
+		};
+		let keys = store.get_all_keys(query.clone(), count)?.into_future();
+		let values = store.get_all(query, count)?.into_future();
+		Ok(futures_util::future::try_join(keys, values).await?)
+	}
+
+	async fn write_raw(&self, value: &JsValue, key: Option<JsValue>) -> Result<(), Error> {
+		let store = self.object_store(TransactionMode::ReadWrite)?;
+		let key = store.put(value, key.as_ref())?.await?;
+		crate::scoped_transaction::commit(store.transaction()).await?;
+		self.changed(key, ChangeKind::Put);
+		Ok(())
+	}
+
+	pub(crate) fn object_store(&self, mode: TransactionMode) -> Result<idb::ObjectStore, Error> {
+		let transaction = self.client.transaction(&[self.descriptor.name], mode)?;
+		Ok(transaction.object_store(self.descriptor.name)?)
+	}
+
+	fn changed(&self, key: JsValue, kind: ChangeKind) {
+		self.client.broadcast(ChangeEvent {
+			store: self.descriptor.name.to_owned(),
+			key,
+			kind,
+		});
+	}
+}
+
+impl<T: Serialize + DeserializeOwned> Store<T> {
+	fn in_transaction<M: Mode>(&self) -> Result<TransactionStore<T, M>, Error> {
+		Ok(TransactionStore::new(
+			self.object_store(M::MODE)?,
+			self.descriptor.serializer,
+		))
+	}
+
+	pub async fn get<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<Option<T>, Error> {
+		self.in_transaction::<ReadOnly>()?.get(key).await
+	}
+
+	/// Reads the record at `key`, treating it as absent unless it passes `keep`.
+	async fn get_if<K: IndexedKey + ?Sized>(&self, key: &K, keep: impl FnOnce(&T) -> bool) -> Result<Option<T>, Error> {
+		Ok(self.get(key).await?.filter(keep))
+	}
+
+	/// Reads every record which passes `keep`.
+	async fn get_all_if(&self, keep: impl FnMut(&T) -> bool) -> Result<Vec<T>, Error> {
+		let mut records = self.get_all().await?;
+		records.retain(keep);
+		Ok(records)
+	}
+
+	/// True if there is a record at `key`. The record itself is not read.
+	pub async fn exists<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<bool, Error> {
+		self.in_transaction::<ReadOnly>()?.exists(key).await
+	}
+
+	/// Reads the record with each of the `keys`, in the same order, in a single read-only transaction.
+	pub async fn get_many<'a, K>(&self, keys: impl IntoIterator<Item = &'a K>) -> Result<Vec<Option<T>>, Error>
+	where
+		K: IndexedKey + ?Sized + 'a,
+	{
+		self.in_transaction::<ReadOnly>()?.get_many(keys).await
+	}
+
+	pub async fn get_all(&self) -> Result<Vec<T>, Error> {
+		self.in_transaction::<ReadOnly>()?.get_all().await
+	}
+
+	/// Reads the records with keys in `range`, or every record if there is no range, in a single request.
+	/// At most `limit` records are returned, in key order.
+	pub async fn get_all_in<K: IndexedKey>(
+		&self,
+		range: Option<&KeyRange<K>>,
+		limit: Option<u32>,
+	) -> Result<Vec<T>, Error> {
+		self.in_transaction::<ReadOnly>()?.get_all_in(range, limit).await
+	}
+
+	/// Reads the keys of the records in `range`, or of every record if there is no range, without reading their values.
+	/// At most `limit` keys are returned, in order.
+	pub async fn get_all_keys<K>(&self, range: Option<&KeyRange<K>>, limit: Option<u32>) -> Result<Vec<K>, Error>
+	where
+		K: IndexedKey + DeserializeOwned,
+	{
+		self.in_transaction::<ReadOnly>()?.get_all_keys(range, limit).await
+	}
+
+	pub async fn count(&self) -> Result<u32, Error> {
+		self.in_transaction::<ReadOnly>()?.count().await
+	}
+
+	/// Counts the records with keys in `range`, or every record if there is no range.
+	pub async fn count_in<K: IndexedKey>(&self, range: Option<&KeyRange<K>>) -> Result<u32, Error> {
+		self.in_transaction::<ReadOnly>()?.count_in(range).await
+	}
+
+	/// Reads up to `page_size` records, in key order, after the key `after`, or from the start of the store.
+	/// Pass the [`next`](Page::next) key of each page to get the page which follows it.
+	/// A `page_size` of 0 is treated as 1, since an empty page could not say where the next one starts.
+	/// ```ignore
+	/// let mut page = client.store(&MESSAGES).page::<u32>(None, 50).await?;
+	/// while let Some(after) = page.next {
+	///   page = client.store(&MESSAGES).page(Some(after), 50).await?;
+	/// }
+	/// ```
+	pub async fn page<K>(&self, after: Option<K>, page_size: u32) -> Result<Page<T, K>, Error>
+	where
+		K: IndexedKey + DeserializeOwned,
+	{
+		self.in_transaction::<ReadOnly>()?.page(after, page_size).await
+	}
+
+	/// Opens a cursor over every record, in a new read-only transaction.
+	pub async fn cursor(&self) -> Result<Cursor<T>, Error> {
+		self.in_transaction::<ReadOnly>()?.cursor().await
+	}
+
+	/// Inserts the record, failing if a record with the same key already exists.
+	pub async fn add(&self, value: &T) -> Result<(), Error> {
+		let store = self.in_transaction::<ReadWrite>()?;
+		let key = store.add_keyed(value).await?;
+		store.commit().await?;
+		self.changed(key, ChangeKind::Add);
+		Ok(())
+	}
+
+	/// Inserts or replaces the record. The key is read from the record using the store's key path.
+	pub async fn put(&self, value: &T) -> Result<(), Error> {
+		let store = self.in_transaction::<ReadWrite>()?;
+		let key = store.put_keyed(value, None).await?;
+		store.commit().await?;
+		self.changed(key, ChangeKind::Put);
+		Ok(())
+	}
+
+	/// Inserts the record like [`Store::add`], returning its key,
+	/// such as the key generated by an auto-increment store.
+	/// The insert is rolled back if the key cannot be deserialized into `K`.
+	pub async fn add_returning<K: DeserializeOwned>(&self, value: &T) -> Result<K, Error> {
+		let store = self.in_transaction::<ReadWrite>()?;
+		let key = store.add_keyed(value).await?;
+		let output = serde_wasm_bindgen::from_value::<K>(key.clone()).map_err(Error::from);
+		let output = store.finish(output).await?;
+		self.changed(key, ChangeKind::Add);
+		Ok(output)
+	}
+
+	/// Inserts or replaces the record like [`Store::put`], returning its key,
+	/// such as the key generated by an auto-increment store.
+	/// The write is rolled back if the key cannot be deserialized into `K`.
+	pub async fn put_returning<K: DeserializeOwned>(&self, value: &T) -> Result<K, Error> {
+		let store = self.in_transaction::<ReadWrite>()?;
+		let key = store.put_keyed(value, None).await?;
+		let output = serde_wasm_bindgen::from_value::<K>(key.clone()).map_err(Error::from);
+		let output = store.finish(output).await?;
+		self.changed(key, ChangeKind::Put);
+		Ok(output)
+	}
+
+	/// Inserts or replaces the record at `key`, for stores without a key path.
+	pub async fn put_with_key<K: IndexedKey + ?Sized>(&self, value: &T, key: &K) -> Result<(), Error> {
+		let store = self.in_transaction::<ReadWrite>()?;
+		let key = store.put_keyed(value, Some(crate::key_range::to_key(key)?)).await?;
+		store.commit().await?;
+		self.changed(key, ChangeKind::Put);
+		Ok(())
+	}
+
+	/// Inserts or replaces every record in a single transaction. The keys are read from the records
+	/// using the store's key path. If any write fails, the transaction is aborted and none of the records are written.
+	pub async fn put_all<'a>(&self, values: impl IntoIterator<Item = &'a T>) -> Result<(), Error>
+	where
+		T: 'a,
+	{
+		let store = self.in_transaction::<ReadWrite>()?;
+		let put = store.put_all_keyed(values).await;
+		let keys = store.finish(put).await?;
+		for key in keys {
+			self.changed(key, ChangeKind::Put);
+		}
+		Ok(())
+	}
+
+	pub async fn delete<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<(), Error> {
+		let key = crate::key_range::to_key(key)?;
+		let store = self.in_transaction::<ReadWrite>()?;
+		store.delete_keyed(key.clone()).await?;
+		store.commit().await?;
+		self.changed(key, ChangeKind::Delete);
+		Ok(())
+	}
+
+	/// Deletes every record in the store.
+	pub async fn clear(&self) -> Result<(), Error> {
+		let store = self.in_transaction::<ReadWrite>()?;
+		store.clear().await?;
+		store.commit().await?;
+		self.changed(JsValue::UNDEFINED, ChangeKind::Clear);
+		Ok(())
+	}
+
+	/// Replaces the record at `key` with the result of `merge`, which is given the existing record if there is one.
+	/// The read and the write happen in a single transaction, so no other write can land between them.
+	pub async fn upsert<K, F>(&self, key: &K, merge: F) -> Result<T, Error>
+	where
+		K: IndexedKey + ?Sized,
+		F: FnOnce(Option<T>) -> T,
+	{
+		let store = self.in_transaction::<ReadWrite>()?;
+		let (key, value) = store.upsert_keyed(crate::key_range::to_key(key)?, merge).await?;
+		store.commit().await?;
+		self.changed(key, ChangeKind::Put);
+		Ok(value)
+	}
+
+	/// Overlays the fields of `patch` onto the record at `key`, in a single transaction, and returns the patched record.
+	/// Nested objects are merged field by field and `null` fields are removed, as in a JSON merge patch,
+	/// so optional fields of a patch which should be left unchanged need
+	/// `#[serde(skip_serializing_if = "Option::is_none")]`.
+	/// Returns `None` without writing anything if there is no record at `key`.
+	/// ```ignore
+	/// #[derive(Serialize)]
+	/// struct Rename<'a> { name: &'a str }
+	/// let user = client.store(&USERS).patch("alice", &Rename { name: "Alice" }).await?;
+	/// ```
+	pub async fn patch<K, P>(&self, key: &K, patch: &P) -> Result<Option<T>, Error>
+	where
+		K: IndexedKey + ?Sized,
+		P: Serialize + ?Sized,
+	{
+		let store = self.in_transaction::<ReadWrite>()?;
+		let Some((key, value)) = store.patch_keyed(crate::key_range::to_key(key)?, patch).await? else {
+			return Ok(None);
+		};
+		store.commit().await?;
+		self.changed(key, ChangeKind::Put);
+		Ok(Some(value))
+	}
+
+	/// Puts `value` at `key` only if the stored record is at the `expected` version, in a single transaction.
+	/// Fails with [`Error::Conflict`] without writing anything if another write changed the record first.
+	/// ```ignore
+	/// let mut doc = store.get("readme").await?.unwrap();
+	/// let expected = doc.version();
+	/// doc.version += 1;
+	/// store.put_if("readme", expected, &doc).await?;
+	/// ```
+	pub async fn put_if<K>(&self, key: &K, expected: u64, value: &T) -> Result<(), Error>
+	where
+		K: IndexedKey + ?Sized,
+		T: Versioned,
+	{
+		let store = self.in_transaction::<ReadWrite>()?;
+		let key = store
+			.put_if_keyed(crate::key_range::to_key(key)?, expected, value)
+			.await?;
+		store.commit().await?;
+		self.changed(key, ChangeKind::Put);
+		Ok(())
+	}
+
+	/// The record at `key`, for reading it and inserting it if it does not exist in one transaction.
+	pub fn entry<K: IndexedKey + ?Sized>(&self, key: &K) -> Entry<'_, T> {
+		Entry::new(self, crate::key_range::to_key(key))
+	}
+
+	/// Deletes the records with each of the `keys` in a single transaction, returning how many deletes were issued.
+	/// If any delete fails, the transaction is aborted and none of the records are deleted.
+	pub async fn delete_all<'a, K>(&self, keys: impl IntoIterator<Item = &'a K>) -> Result<usize, Error>
+	where
+		K: IndexedKey + ?Sized + 'a,
+	{
+		let keys = keys
+			.into_iter()
+			.map(crate::key_range::to_key)
+			.collect::<Result<Vec<_>, _>>()?;
+		let store = self.in_transaction::<ReadWrite>()?;
+		let deleted = store.delete_all_keyed(&keys).await;
+		store.finish(deleted).await?;
+		let count = keys.len();
+		for key in keys {
+			self.changed(key, ChangeKind::Delete);
+		}
+		Ok(count)
+	}
+
+	/// Deletes every record with a key in `range` in a single transaction, returning how many were deleted.
+	pub async fn delete_range<K: IndexedKey>(&self, range: &KeyRange<K>) -> Result<usize, Error> {
+		let store = self.in_transaction::<ReadWrite>()?;
+		let keys = store.delete_range_keyed(range.to_query()?).await?;
+		store.commit().await?;
+		let count = keys.len();
+		for key in keys {
+			self.changed(key, ChangeKind::Delete);
+		}
+		Ok(count)
+	}
+}
+
+fn to_bytes(value: &JsValue) -> Result<Vec<u8>, Error> {
+	if let Some(array) = value.dyn_ref::<js_sys::Uint8Array>() {
+		return Ok(array.to_vec());
+	}
+	match value.dyn_ref::<js_sys::ArrayBuffer>() {
+		Some(buffer) => Ok(js_sys::Uint8Array::new(buffer).to_vec()),
+		None => Err(Error::Serialization("the value is not a Uint8Array".into())),
+	}
+}
+
+/// An object store whose records are all `T`, within a transaction which may span several stores.
+/// Created by [`ScopedTransaction::store`]. Records can only be written if the transaction is [`ReadWrite`].
+pub struct TransactionStore<T, M> {
+	store: idb::ObjectStore,
+	serializer: SerializerOptions,
+	marker: PhantomData<fn() -> (T, M)>,
+}
+
+impl<T, M> TransactionStore<T, M> {
+	pub(crate) fn new(store: idb::ObjectStore, serializer: SerializerOptions) -> Self {
+		Self {
+			store,
+			serializer,
+			marker: PhantomData,
+		}
+	}
+
+	/// Commits the whole transaction the store belongs to.
+	pub(crate) async fn commit(self) -> Result<(), Error> {
+		crate::scoped_transaction::commit(self.store.transaction()).await
+	}
+
+	/// Aborts the whole transaction the store belongs to, if it has not already finished.
+	pub(crate) fn abort(self) {
+		let _ = self.store.transaction().abort();
+	}
+
+	/// Commits the transaction if the work done in it succeeded, and aborts it otherwise,
+	/// so that writes queued before the failure are not committed.
+	pub(crate) async fn finish<R>(self, result: Result<R, Error>) -> Result<R, Error> {
+		match result {
+			Ok(output) => {
+				self.commit().await?;
+				Ok(output)
+			}
+			Err(err) => {
+				self.abort();
+				Err(err)
+			}
+		}
+	}
+}
+
+impl<T: Serialize + DeserializeOwned, M> TransactionStore<T, M> {
+	pub async fn get<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<Option<T>, Error> {
+		let key = crate::key_range::to_key(key)?;
+		let Some(js_value) = self.store.get(idb::Query::Key(key))?.await? else {
+			return Ok(None);
+		};
+		Ok(Some(serde_wasm_bindgen::from_value::<T>(js_value)?))
+	}
+
+	/// True if there is a record at `key`. The record itself is not read.
+	pub async fn exists<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<bool, Error> {
+		let key = crate::key_range::to_key(key)?;
+		Ok(self.store.count(Some(idb::Query::Key(key)))?.await? > 0)
+	}
+
+	/// Reads the record with each of the `keys`, in the same order, queuing every request before the first is awaited.
+	pub async fn get_many<'a, K>(&self, keys: impl IntoIterator<Item = &'a K>) -> Result<Vec<Option<T>>, Error>
+	where
+		K: IndexedKey + ?Sized + 'a,
+	{
+		let mut requests = Vec::new();
+		for key in keys {
+			let key = crate::key_range::to_key(key)?;
+			requests.push(self.store.get(idb::Query::Key(key))?.into_future());
+		}
+		let mut values = Vec::with_capacity(requests.len());
+		for js_value in futures_util::future::try_join_all(requests).await? {
+			values.push(match js_value {
+				Some(js_value) => Some(serde_wasm_bindgen::from_value::<T>(js_value)?),
+				None => None,
+			});
+		}
+		Ok(values)
+	}
+
+	pub async fn get_all(&self) -> Result<Vec<T>, Error> {
+		self.get_all_query(None, None).await
+	}
+
+	/// Reads the records with keys in `range`, or every record if there is no range, in a single request.
+	/// At most `limit` records are returned, in key order.
+	pub async fn get_all_in<K: IndexedKey>(
+		&self,
+		range: Option<&KeyRange<K>>,
+		limit: Option<u32>,
+	) -> Result<Vec<T>, Error> {
+		self.get_all_query(range.map(KeyRange::to_query).transpose()?, limit)
+			.await
+	}
+
+	async fn get_all_query(&self, query: Option<idb::Query>, limit: Option<u32>) -> Result<Vec<T>, Error> {
+		// getAll treats a count of 0 as no limit
+		if limit == Some(0) {
+			return Ok(Vec::new());
+		}
+		let js_values = self.store.get_all(query, limit)?.into_future().await?;
+		let mut values = Vec::with_capacity(js_values.len());
+		for js_value in js_values {
+			values.push(serde_wasm_bindgen::from_value::<T>(js_value)?);
+		}
+		Ok(values)
+	}
+
+	/// Reads the keys of the records in `range`, or of every record if there is no range, without reading their values.
+	/// At most `limit` keys are returned, in order.
+	pub async fn get_all_keys<K>(&self, range: Option<&KeyRange<K>>, limit: Option<u32>) -> Result<Vec<K>, Error>
+	where
+		K: IndexedKey + DeserializeOwned,
+	{
+		let query = range.map(KeyRange::to_query).transpose()?;
+		if limit == Some(0) {
+			return Ok(Vec::new());
+		}
+		let js_keys = self.store.get_all_keys(query, limit)?.into_future().await?;
+		let mut keys = Vec::with_capacity(js_keys.len());
+		for js_key in js_keys {
+			keys.push(serde_wasm_bindgen::from_value::<K>(js_key)?);
+		}
+		Ok(keys)
+	}
+
+	pub async fn count(&self) -> Result<u32, Error> {
+		Ok(self.store.count(None)?.await?)
+	}
+
+	/// Counts the records with keys in `range`, or every record if there is no range.
+	pub async fn count_in<K: IndexedKey>(&self, range: Option<&KeyRange<K>>) -> Result<u32, Error> {
+		let query = range.map(KeyRange::to_query).transpose()?;
+		Ok(self.store.count(query)?.await?)
+	}
+
+	/// Reads up to `page_size` records, in key order, after the key `after`, or from the start of the store.
+	/// A `page_size` of 0 is treated as 1.
+	pub async fn page<K>(&self, after: Option<K>, page_size: u32) -> Result<Page<T, K>, Error>
+	where
+		K: IndexedKey + DeserializeOwned,
+	{
+		let page_size = page_size.max(1);
+		let range = after.map(|after| KeyRange::lower_bound(after).lower_open());
+		let query = range.as_ref().map(KeyRange::to_query).transpose()?;
+		// the keys are requested alongside the records, for the key of the next page
+		let keys = self.store.get_all_keys(query.clone(), Some(page_size))?.into_future();
+		let items = self.get_all_query(query, Some(page_size));
+		let (keys, items) = futures_util::future::try_join(async { Ok(keys.await?) }, items).await?;
+		let next = match keys.last() {
+			Some(key) if items.len() as u32 == page_size => Some(serde_wasm_bindgen::from_value::<K>(key.clone())?),
+			_ => None,
+		};
+		Ok(Page { items, next })
+	}
+
+	pub async fn cursor(&self) -> Result<Cursor<T>, Error> {
+		let cursor = Cursor::new(self.store.open_cursor(None, None)?.await?);
+		Ok(cursor.with_query(None).with_serializer(self.serializer))
+	}
+}
+
+impl<T: Serialize + DeserializeOwned> TransactionStore<T, ReadWrite> {
+	/// Inserts the record, failing if a record with the same key already exists.
+	pub async fn add(&self, value: &T) -> Result<(), Error> {
+		self.add_keyed(value).await?;
+		Ok(())
+	}
+
+	/// Inserts or replaces the record. The key is read from the record using the store's key path.
+	pub async fn put(&self, value: &T) -> Result<(), Error> {
+		self.put_keyed(value, None).await?;
+		Ok(())
+	}
+
+	/// Inserts the record, returning its key, such as the key generated by an auto-increment store.
+	pub async fn add_returning<K: DeserializeOwned>(&self, value: &T) -> Result<K, Error> {
+		let key = self.add_keyed(value).await?;
+		Ok(serde_wasm_bindgen::from_value::<K>(key)?)
+	}
+
+	/// Inserts or replaces the record, returning its key, such as the key generated by an auto-increment store.
+	pub async fn put_returning<K: DeserializeOwned>(&self, value: &T) -> Result<K, Error> {
+		let key = self.put_keyed(value, None).await?;
+		Ok(serde_wasm_bindgen::from_value::<K>(key)?)
+	}
+
+	/// Inserts or replaces the record at `key`, for stores without a key path.
+	pub async fn put_with_key<K: IndexedKey + ?Sized>(&self, value: &T, key: &K) -> Result<(), Error> {
+		self.put_keyed(value, Some(crate::key_range::to_key(key)?)).await?;
+		Ok(())
+	}
+
+	/// Inserts or replaces every record. The keys are read from the records using the store's key path.
+	pub async fn put_all<'a>(&self, values: impl IntoIterator<Item = &'a T>) -> Result<(), Error>
+	where
+		T: 'a,
+	{
+		self.put_all_keyed(values).await?;
+		Ok(())
+	}
+
+	pub async fn delete<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<(), Error> {
+		self.delete_keyed(crate::key_range::to_key(key)?).await
+	}
+
+	/// Deletes every record in the store.
+	pub async fn clear(&self) -> Result<(), Error> {
+		self.store.clear()?.await?;
+		Ok(())
+	}
+
+	/// Replaces the record at `key` with the result of `merge`, which is given the existing record if there is one.
+	pub async fn upsert<K, F>(&self, key: &K, merge: F) -> Result<T, Error>
+	where
+		K: IndexedKey + ?Sized,
+		F: FnOnce(Option<T>) -> T,
+	{
+		let (_, value) = self.upsert_keyed(crate::key_range::to_key(key)?, merge).await?;
+		Ok(value)
+	}
+
+	/// Puts `value` at `key` only if the stored record is at the `expected` version. See [`Store::put_if`].
+	pub async fn put_if<K>(&self, key: &K, expected: u64, value: &T) -> Result<(), Error>
+	where
+		K: IndexedKey + ?Sized,
+		T: Versioned,
+	{
+		self.put_if_keyed(crate::key_range::to_key(key)?, expected, value)
+			.await?;
+		Ok(())
+	}
+
+	/// Overlays the fields of `patch` onto the record at `key`, and returns the patched record. See [`Store::patch`].
+	pub async fn patch<K, P>(&self, key: &K, patch: &P) -> Result<Option<T>, Error>
+	where
+		K: IndexedKey + ?Sized,
+		P: Serialize + ?Sized,
+	{
+		let patched = self.patch_keyed(crate::key_range::to_key(key)?, patch).await?;
+		Ok(patched.map(|(_, value)| value))
+	}
+
+	/// Deletes the records with each of the `keys`, returning how many deletes were issued.
+	pub async fn delete_all<'a, K>(&self, keys: impl IntoIterator<Item = &'a K>) -> Result<usize, Error>
+	where
+		K: IndexedKey + ?Sized + 'a,
+	{
+		let keys = keys
+			.into_iter()
+			.map(crate::key_range::to_key)
+			.collect::<Result<Vec<_>, _>>()?;
+		self.delete_all_keyed(&keys).await?;
+		Ok(keys.len())
+	}
+
+	/// Deletes every record with a key in `range`, returning how many were deleted.
+	pub async fn delete_range<K: IndexedKey>(&self, range: &KeyRange<K>) -> Result<usize, Error> {
+		Ok(self.delete_range_keyed(range.to_query()?).await?.len())
+	}
+
+	/// Adds the record, returning its key.
+	async fn add_keyed(&self, value: &T) -> Result<JsValue, Error> {
+		let value = self.serializer.serialize(value)?;
+		let request = self.store.add(&value, None)?;
+		request
+			.await
+			.map_err(|err| crate::error::add_failed(err, &self.store, &value, None))
+	}
+
+	/// Puts the record, returning its key.
+	async fn put_keyed(&self, value: &T, key: Option<JsValue>) -> Result<JsValue, Error> {
+		Ok(self
+			.store
+			.put(&self.serializer.serialize(value)?, key.as_ref())?
+			.await?)
+	}
+
+	/// Puts the record which belongs at `key`, returning its key. Stores without a key path are given `key`,
+	/// and stores with one read the key from the record instead, so it must be `key`.
+	async fn put_at_keyed(&self, value: &T, key: JsValue) -> Result<JsValue, Error> {
+		let value = self.serializer.serialize(value)?;
+		let key = match self.store.key_path()? {
+			Some(key_path) => {
+				crate::error::check_key(&value, &key_path, &key)?;
+				None
+			}
+			None => Some(key),
+		};
+		Ok(self.store.put(&value, key.as_ref())?.await?)
+	}
+
+	/// Puts every record, returning their keys. All of the records are serialized before any are written,
+	/// and every request is queued before the first is awaited.
+	async fn put_all_keyed<'a>(&self, values: impl IntoIterator<Item = &'a T>) -> Result<Vec<JsValue>, Error>
+	where
+		T: 'a,
+	{
+		let values = values
+			.into_iter()
+			.map(|value| self.serializer.serialize(value))
+			.collect::<Result<Vec<_>, _>>()?;
+		let mut requests = Vec::with_capacity(values.len());
+		for value in &values {
+			requests.push(self.store.put(value, None)?.into_future());
+		}
+		Ok(futures_util::future::try_join_all(requests).await?)
+	}
+
+	async fn delete_keyed(&self, key: JsValue) -> Result<(), Error> {
+		self.store.delete(idb::Query::Key(key))?.await?;
+		Ok(())
+	}
+
+	/// Reads the record at `key` and puts the merged record, returning its key.
+	async fn upsert_keyed<F>(&self, key: JsValue, merge: F) -> Result<(JsValue, T), Error>
+	where
+		F: FnOnce(Option<T>) -> T,
+	{
+		let existing = match self.store.get(idb::Query::Key(key.clone()))?.await? {
+			Some(js_value) => Some(serde_wasm_bindgen::from_value::<T>(js_value)?),
+			None => None,
+		};
+		let value = merge(existing);
+		let key = self.put_at_keyed(&value, key).await?;
+		Ok((key, value))
+	}
+
+	/// Reads the record at `key`, putting the result of `default` if there is none.
+	/// Returns the key of the new record if one was put.
+	async fn get_or_insert_keyed<F>(&self, key: JsValue, default: F) -> Result<(Option<JsValue>, T), Error>
+	where
+		F: FnOnce() -> T,
+	{
+		if let Some(js_value) = self.store.get(idb::Query::Key(key.clone()))?.await? {
+			return Ok((None, serde_wasm_bindgen::from_value::<T>(js_value)?));
+		}
+		let value = default();
+		let key = self.put_at_keyed(&value, key).await?;
+		Ok((Some(key), value))
+	}
+
+	/// Puts the record if the record at `key` is at the `expected` version, returning its key.
+	async fn put_if_keyed(&self, key: JsValue, expected: u64, value: &T) -> Result<JsValue, Error>
+	where
+		T: Versioned,
+	{
+		let current = match self.store.get(idb::Query::Key(key.clone()))?.await? {
+			Some(js_value) => Some(serde_wasm_bindgen::from_value::<T>(js_value)?.version()),
+			None => None,
+		};
+		if current != Some(expected) {
+			return Err(Error::Conflict { current });
+		}
+		self.put_at_keyed(value, key).await
+	}
+
+	/// Reads the record at `key` and puts it back with `patch` merged in, returning its key,
+	/// or `None` if there is no record. The merged record must still deserialize as `T`.
+	async fn patch_keyed<P>(&self, key: JsValue, patch: &P) -> Result<Option<(JsValue, T)>, Error>
+	where
+		P: Serialize + ?Sized,
+	{
+		let patch = self.serializer.serialize(patch)?;
+		let Some(existing) = self.store.get(idb::Query::Key(key.clone()))?.await? else {
+			return Ok(None);
+		};
+		let merged = patch::merge(existing, &patch);
+		let value = serde_wasm_bindgen::from_value::<T>(merged)?;
+		let key = self.put_at_keyed(&value, key).await?;
+		Ok(Some((key, value)))
+	}
+
+	/// Deletes every key, queuing every request before the first is awaited.
+	async fn delete_all_keyed(&self, keys: &[JsValue]) -> Result<(), Error> {
+		let mut requests = Vec::with_capacity(keys.len());
+		for key in keys {
+			requests.push(self.store.delete(idb::Query::Key(key.clone()))?.into_future());
+		}
+		futures_util::future::try_join_all(requests).await?;
+		Ok(())
+	}
+
+	/// Deletes the records whose keys in the index called `index` match the query, returning their primary keys.
+	async fn delete_indexed_keyed(&self, index: &str, query: idb::Query) -> Result<Vec<JsValue>, Error> {
+		let index = self.store.index(index)?;
+		let keys = index.get_all_keys(Some(query), None)?.into_future().await?;
+		self.delete_all_keyed(&keys).await?;
+		Ok(keys)
+	}
+
+	/// Deletes the records matching the query, returning their keys.
+	async fn delete_range_keyed(&self, query: idb::Query) -> Result<Vec<JsValue>, Error> {
+		let keys = self
+			.store
+			.get_all_keys(Some(query.clone()), None)?
+			.into_future()
+			.await?;
+		self.store.delete(query)?.await?;
+		Ok(keys)
+	}
+}
+
+/// One page of records read by [`Store::page`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T, K> {
+	pub items: Vec<T>,
+	/// The key of the last record, to read the next page after. `None` once a page has fewer records than were asked for.
+	pub next: Option<K>,
+}
+
+/// Names an object store for [`Client::read_transaction`] and [`Client::write_transaction`], such as a [`StoreDescriptor`].
+pub trait StoreRef {
+	fn store_name(&self) -> &'static str;
+}
+
+impl<T> StoreRef for StoreDescriptor<T> {
+	fn store_name(&self) -> &'static str {
+		self.name
+	}
+}
@@ -0,0 +1,208 @@
+use super::Store;
+use crate::{js::call, Error, IndexedKey, SerializerOptions, TransactionMode};
+use futures_util::{Stream, TryStreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use std::future::IntoFuture;
+use wasm_bindgen::{JsCast, JsValue};
+
+/// The field which marks a compressed record, and names its format.
+pub(super) const MARKER: &str = "$compressed";
+
+/// A format of the browser's `CompressionStream`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+	Gzip,
+	Deflate,
+	DeflateRaw,
+}
+
+impl Compression {
+	fn as_str(&self) -> &'static str {
+		match self {
+			Self::Gzip => "gzip",
+			Self::Deflate => "deflate",
+			Self::DeflateRaw => "deflate-raw",
+		}
+	}
+
+	fn parse(format: &str) -> Option<Self> {
+		match format {
+			"gzip" => Some(Self::Gzip),
+			"deflate" => Some(Self::Deflate),
+			"deflate-raw" => Some(Self::DeflateRaw),
+			_ => None,
+		}
+	}
+}
+
+/// A [`Store`] whose records are written as compressed JSON, created by [`Store::compressed`].
+/// Each compressed record is stored as `{ "$compressed": format, data: Uint8Array }`,
+/// and records without the marker are read as usual, so a store can switch to compression without migrating it.
+/// See [wrapped records](Store#wrapped-records) for what this means for the store.
+///
+/// Records are compressed as JSON, so they can only hold what JSON can: fields which are stored as JS objects,
+/// such as [`Timestamp`](crate::Timestamp), [`BinaryKey`](crate::BinaryKey) and bytes, fail to compress with
+/// [`Error::Serialization`] instead of being written in a form which could not be read back.
+/// ```ignore
+/// let documents = client.store(&DOCUMENTS).compressed(Compression::Gzip);
+/// documents.put(&"readme", &document).await?;
+/// let document = documents.get(&"readme").await?;
+/// ```
+pub struct CompressedStore<T> {
+	store: Store<T>,
+	compression: Compression,
+}
+
+clone_handle!(CompressedStore { store, compression });
+
+impl<T> CompressedStore<T> {
+	pub(crate) fn new(store: Store<T>, compression: Compression) -> Self {
+		Self { store, compression }
+	}
+
+	pub fn compression(&self) -> Compression {
+		self.compression
+	}
+}
+
+impl<T: Serialize + DeserializeOwned> CompressedStore<T> {
+	/// Compresses the record and puts it at `key`.
+	pub async fn put<K: IndexedKey + ?Sized>(&self, key: &K, value: &T) -> Result<(), Error> {
+		let record = encode(self.compression, value).await?;
+		let key = crate::key_range::to_key(key)?;
+		self.store.write_raw(&record, Some(key)).await
+	}
+
+	/// Reads the record at `key`, decompressing it if it was compressed.
+	pub async fn get<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<Option<T>, Error> {
+		match self.store.get_raw(key).await? {
+			Some(value) => Ok(Some(decode(value).await?)),
+			None => Ok(None),
+		}
+	}
+
+	/// Reads and decompresses every record. See [`CompressedStore::stream`] for stores which are too large to read at once.
+	pub async fn get_all(&self) -> Result<Vec<T>, Error> {
+		let store = self.store.object_store(TransactionMode::ReadOnly)?;
+		let js_values = store.get_all(None, None)?.into_future().await?;
+		let mut values = Vec::with_capacity(js_values.len());
+		for js_value in js_values {
+			values.push(decode(js_value).await?);
+		}
+		Ok(values)
+	}
+
+	/// Reads every record in key order, `batch_size` records at a time.
+	/// Each batch is read in its own transaction, which has finished before the batch is decompressed.
+	/// ```ignore
+	/// let mut documents = documents.stream(50);
+	/// while let Some(document) = documents.try_next().await? {
+	///   index(document);
+	/// }
+	/// ```
+	pub fn stream(&self, batch_size: u32) -> impl Stream<Item = Result<T, Error>> + '_ {
+		let batch_size = batch_size.max(1);
+		let batches = futures_util::stream::try_unfold(Some(None::<JsValue>), move |after| async move {
+			let Some(after) = after else {
+				return Ok::<_, Error>(None);
+			};
+			let (keys, values) = self.store.read_batch(after.as_ref(), Some(batch_size)).await?;
+			let mut records = Vec::with_capacity(values.len());
+			for value in values {
+				records.push(Ok(decode(value).await?));
+			}
+			let next = match keys.len() < batch_size as usize {
+				true => None,
+				false => keys.last().cloned().map(Some),
+			};
+			Ok(Some((futures_util::stream::iter(records), next)))
+		});
+		batches.try_flatten()
+	}
+}
+
+/// Compresses the JSON of `value` into a marked record.
+pub(super) async fn encode<T: Serialize>(compression: Compression, value: &T) -> Result<JsValue, Error> {
+	let json = to_json(value)?;
+	let data = transform(json.as_bytes(), "CompressionStream", compression.as_str()).await?;
+	let record = js_sys::Object::new();
+	let set = |field: &str, value: &JsValue| js_sys::Reflect::set(&record, &field.into(), value);
+	set(MARKER, &compression.as_str().into())
+		.and_then(|_| set("data", &js_sys::Uint8Array::from(data.as_slice())))
+		.map_err(|err| Error::Internal(format!("{err:?}")))?;
+	Ok(record.into())
+}
+
+/// The compression of a marked record, if it is one.
+pub(super) fn compression_of(value: &JsValue) -> Option<Compression> {
+	let format = js_sys::Reflect::get(value, &MARKER.into()).ok()?.as_string()?;
+	Compression::parse(&format)
+}
+
+pub(super) async fn decode<T: DeserializeOwned>(value: JsValue) -> Result<T, Error> {
+	let get = |field: &str| js_sys::Reflect::get(&value, &field.into()).ok();
+	let Some(format) = get(MARKER).and_then(|format| format.as_string()) else {
+		return Ok(serde_wasm_bindgen::from_value::<T>(value)?);
+	};
+	let Some(compression) = Compression::parse(&format) else {
+		return Err(Error::Serialization(format!("unknown compression format {format:?}")));
+	};
+	let data = get("data").unwrap_or_default();
+	let data = js_sys::Uint8Array::new(&data).to_vec();
+	let json = transform(&data, "DecompressionStream", compression.as_str()).await?;
+	let json = String::from_utf8(json).map_err(|err| Error::Serialization(err.to_string()))?;
+	let value = js_sys::JSON::parse(&json).map_err(|err| Error::Serialization(format!("{err:?}")))?;
+	Ok(serde_wasm_bindgen::from_value::<T>(value)?)
+}
+
+/// Serializes `value` the same way as an uncompressed record, then as JSON.
+/// Fails if the value holds anything other than plain objects, arrays and primitives, which JSON would lose.
+fn to_json<T: Serialize>(value: &T) -> Result<String, Error> {
+	let value = SerializerOptions::new().serialize(value)?;
+	if let Some(kind) = unplain(&value) {
+		return Err(Error::Serialization(format!("compressed records cannot hold a {kind}")));
+	}
+	let json = js_sys::JSON::stringify(&value).map_err(|err| Error::Serialization(format!("{err:?}")))?;
+	Ok(json.into())
+}
+
+/// The name of the constructor of the first value within `value` which is not plain data, if there is one.
+fn unplain(value: &JsValue) -> Option<String> {
+	if !value.is_object() {
+		return None;
+	}
+	if js_sys::Array::is_array(value) {
+		return js_sys::Array::from(value).iter().find_map(|item| unplain(&item));
+	}
+	let object = value.unchecked_ref::<js_sys::Object>();
+	let prototype = js_sys::Object::get_prototype_of(object);
+	let plain = prototype.is_null()
+		|| JsValue::from(&prototype) == JsValue::from(js_sys::Object::get_prototype_of(&js_sys::Object::new()));
+	if !plain {
+		return Some(object.constructor().name().into());
+	}
+	js_sys::Object::values(object).iter().find_map(|item| unplain(&item))
+}
+
+/// Pipes `bytes` through a new `CompressionStream` or `DecompressionStream` of `format`.
+async fn transform(bytes: &[u8], stream: &str, format: &str) -> Result<Vec<u8>, Error> {
+	let js_error = |err: JsValue| Error::Internal(format!("{err:?}"));
+	let constructor = |name: &str| {
+		js_sys::Reflect::get(&js_sys::global(), &name.into())
+			.ok()
+			.and_then(|constructor| constructor.dyn_into::<js_sys::Function>().ok())
+			.ok_or_else(|| Error::Unsupported(name.to_owned()))
+	};
+	let transform =
+		js_sys::Reflect::construct(&constructor(stream)?, &js_sys::Array::of1(&format.into())).map_err(js_error)?;
+	let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+	let blob = web_sys::Blob::new_with_u8_array_sequence(&parts).map_err(js_error)?;
+	let piped = call(&call(&blob, "stream", &[])?, "pipeThrough", &[transform])?;
+	let response =
+		js_sys::Reflect::construct(&constructor("Response")?, &js_sys::Array::of1(&piped)).map_err(js_error)?;
+	let buffer = call(&response, "arrayBuffer", &[])?;
+	let buffer = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::from(buffer))
+		.await
+		.map_err(js_error)?;
+	Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
@@ -0,0 +1,162 @@
+use super::Store;
+use crate::{js::call, Error, IndexedKey};
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+
+/// The field which marks an encrypted record, and names its algorithm.
+pub(super) const MARKER: &str = "$encrypted";
+const ALGORITHM: &str = "AES-GCM";
+const IV_LENGTH: u32 = 12;
+
+/// An AES-GCM key for an [`EncryptedStore`], such as one derived from the user's credentials.
+/// Keys are held by the browser, and imported keys cannot be exported again.
+#[derive(Debug, Clone)]
+pub struct EncryptionKey(JsValue);
+
+impl EncryptionKey {
+	/// Imports a raw AES key of 16 or 32 bytes.
+	pub async fn import(bytes: &[u8]) -> Result<Self, Error> {
+		let usages = js_sys::Array::of2(&"encrypt".into(), &"decrypt".into());
+		let arguments = [
+			"raw".into(),
+			js_sys::Uint8Array::from(bytes).into(),
+			ALGORITHM.into(),
+			false.into(),
+			usages.into(),
+		];
+		let key = resolve(call(&subtle()?, "importKey", &arguments)?).await?;
+		Ok(Self(key))
+	}
+
+	/// Uses a `CryptoKey` which was created for AES-GCM, such as by `crypto.subtle.deriveKey`.
+	pub fn from_crypto_key(key: JsValue) -> Self {
+		Self(key)
+	}
+}
+
+/// A [`Store`] whose records are encrypted with AES-GCM through the browser's `SubtleCrypto`,
+/// created by [`Store::encrypted`]. Each record is encrypted as JSON with a new random IV,
+/// and stored as `{ "$encrypted": "AES-GCM", iv: Uint8Array, data: Uint8Array }`.
+/// The store name and record key are authenticated with the record, so an encrypted record which is moved
+/// to another key fails to decrypt. Encrypted records are [wrapped records](Store#wrapped-records).
+/// ```ignore
+/// let key = EncryptionKey::import(&key_bytes).await?;
+/// let secrets = client.store(&SECRETS).encrypted(key);
+/// secrets.put(&"token", &token).await?;
+/// let token = secrets.get(&"token").await?;
+/// ```
+pub struct EncryptedStore<T> {
+	store: Store<T>,
+	key: EncryptionKey,
+}
+
+clone_handle!(EncryptedStore { store, key });
+
+impl<T> EncryptedStore<T> {
+	pub(crate) fn new(store: Store<T>, key: EncryptionKey) -> Self {
+		Self { store, key }
+	}
+}
+
+impl<T: Serialize + DeserializeOwned> EncryptedStore<T> {
+	/// Encrypts the record and puts it at `key`.
+	pub async fn put<K: IndexedKey + ?Sized>(&self, key: &K, value: &T) -> Result<(), Error> {
+		let key = crate::key_range::to_key(key)?;
+		let json = serde_json::to_vec(value).map_err(|err| Error::Serialization(err.to_string()))?;
+		let iv = js_sys::Uint8Array::new_with_length(IV_LENGTH);
+		call(&crypto()?, "getRandomValues", &[iv.clone().into()])?;
+		let data = resolve(call(
+			&subtle()?,
+			"encrypt",
+			&[
+				self.params(&iv, &key)?,
+				self.key.0.clone(),
+				js_sys::Uint8Array::from(json.as_slice()).into(),
+			],
+		)?)
+		.await?;
+
+		let record = js_sys::Object::new();
+		let set = |field: &str, value: &JsValue| js_sys::Reflect::set(&record, &field.into(), value);
+		set(MARKER, &ALGORITHM.into())
+			.and_then(|_| set("iv", &iv))
+			.and_then(|_| set("data", &js_sys::Uint8Array::new(&data)))
+			.map_err(js_error)?;
+		self.store.write_raw(&record, Some(key)).await
+	}
+
+	/// Reads and decrypts the record at `key`.
+	/// Fails with [`Error::Serialization`] if the record is not encrypted, or cannot be decrypted with this key.
+	pub async fn get<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<Option<T>, Error> {
+		let js_key = crate::key_range::to_key(key)?;
+		match self.store.get_raw(key).await? {
+			Some(value) => Ok(Some(self.decrypt(&js_key, &value).await?)),
+			None => Ok(None),
+		}
+	}
+
+	/// Reads and decrypts every record.
+	pub async fn get_all(&self) -> Result<Vec<T>, Error> {
+		let (keys, js_values) = self.store.read_batch(None, None).await?;
+		let mut values = Vec::with_capacity(js_values.len());
+		for (key, js_value) in keys.iter().zip(js_values.iter()) {
+			values.push(self.decrypt(key, js_value).await?);
+		}
+		Ok(values)
+	}
+
+	async fn decrypt(&self, key: &JsValue, value: &JsValue) -> Result<T, Error> {
+		let get = |field: &str| js_sys::Reflect::get(value, &field.into()).unwrap_or_default();
+		if get(MARKER).as_string().as_deref() != Some(ALGORITHM) {
+			return Err(Error::Serialization("the record is not encrypted".into()));
+		}
+		let iv = js_sys::Uint8Array::new(&get("iv"));
+		let data = get("data");
+		let decrypted = call(
+			&subtle()?,
+			"decrypt",
+			&[self.params(&iv, key)?, self.key.0.clone(), data],
+		)?;
+		let json = resolve(decrypted)
+			.await
+			.map_err(|_| Error::Serialization("the record could not be decrypted".into()))?;
+		let json = js_sys::Uint8Array::new(&json).to_vec();
+		serde_json::from_slice(&json).map_err(|err| Error::Serialization(err.to_string()))
+	}
+
+	/// The parameters of encrypting or decrypting the record at `key`, which authenticate where the record is stored.
+	fn params(&self, iv: &js_sys::Uint8Array, key: &JsValue) -> Result<JsValue, Error> {
+		let key = js_sys::JSON::stringify(key).map_err(js_error)?;
+		let metadata = format!("{}\n{}", self.store.descriptor().name(), String::from(key));
+		let params = js_sys::Object::new();
+		let set = |field: &str, value: &JsValue| js_sys::Reflect::set(&params, &field.into(), value);
+		set("name", &ALGORITHM.into())
+			.and_then(|_| set("iv", iv))
+			.and_then(|_| set("additionalData", &js_sys::Uint8Array::from(metadata.as_bytes())))
+			.map_err(js_error)?;
+		Ok(params.into())
+	}
+}
+
+fn js_error(err: JsValue) -> Error {
+	Error::Internal(format!("{err:?}"))
+}
+
+fn crypto() -> Result<JsValue, Error> {
+	js_sys::Reflect::get(&js_sys::global(), &"crypto".into())
+		.ok()
+		.filter(|crypto| !crypto.is_undefined())
+		.ok_or_else(|| Error::Unsupported("crypto".into()))
+}
+
+fn subtle() -> Result<JsValue, Error> {
+	js_sys::Reflect::get(&crypto()?, &"subtle".into())
+		.ok()
+		.filter(|subtle| !subtle.is_undefined())
+		.ok_or_else(|| Error::Unsupported("crypto.subtle".into()))
+}
+
+async fn resolve(promise: JsValue) -> Result<JsValue, Error> {
+	let promise = promise.dyn_into::<js_sys::Promise>().map_err(js_error)?;
+	wasm_bindgen_futures::JsFuture::from(promise).await.map_err(js_error)
+}
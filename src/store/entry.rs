@@ -0,0 +1,48 @@
+use super::Store;
+use crate::{ChangeKind, Error, ReadWrite};
+use serde::{de::DeserializeOwned, Serialize};
+use wasm_bindgen::JsValue;
+
+/// The record at one key of a [`Store`], which may not exist yet, created by [`Store::entry`].
+/// In a store with a key path, the key is read from the inserted record, so defaults must have the entry's key,
+/// otherwise inserting fails with [`Error::KeyMismatch`] and nothing is written.
+/// ```ignore
+/// let settings = client.store(&SETTINGS).entry(&user_id).or_insert_with(Settings::default).await?;
+/// ```
+pub struct Entry<'store, T> {
+	store: &'store Store<T>,
+	key: Result<JsValue, Error>,
+}
+
+impl<'store, T> Entry<'store, T> {
+	pub(crate) fn new(store: &'store Store<T>, key: Result<JsValue, Error>) -> Self {
+		Self { store, key }
+	}
+}
+
+impl<T: Serialize + DeserializeOwned> Entry<'_, T> {
+	/// Reads the record, first putting `default` at the key if there is no record.
+	pub async fn or_insert(self, default: T) -> Result<T, Error> {
+		self.or_insert_with(|| default).await
+	}
+
+	/// Reads the record, first putting the result of `default` at the key if there is no record.
+	/// The read and the write happen in a single transaction, so a record written in between is never replaced.
+	pub async fn or_insert_with(self, default: impl FnOnce() -> T) -> Result<T, Error> {
+		let store = self.store.in_transaction::<ReadWrite>()?;
+		let read = store.get_or_insert_keyed(self.key?, default).await;
+		let (inserted, value) = store.finish(read).await?;
+		if let Some(key) = inserted {
+			self.store.changed(key, ChangeKind::Put);
+		}
+		Ok(value)
+	}
+
+	/// Reads the record, first putting the default value at the key if there is no record.
+	pub async fn or_default(self) -> Result<T, Error>
+	where
+		T: Default,
+	{
+		self.or_insert_with(T::default).await
+	}
+}
@@ -0,0 +1,111 @@
+use super::{Store, TransactionStore};
+use crate::{ChangeKind, Error, IndexedKey, KeyRange, ReadWrite, Timestamp};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::time::Duration;
+use wasm_bindgen::JsValue;
+
+/// The index which [`Store::purge_expired`] finds expired records in.
+/// It is declared with [`StoreBuilder::expires_at_index`](crate::StoreBuilder::expires_at_index).
+pub const EXPIRES_AT_INDEX: &str = "expires_at";
+
+/// A record which expires some time after it was [put](Store::put_with_ttl), such as an entry of an HTTP cache.
+/// Expired records stay in the store until they are [purged](Store::purge_expired), but live reads treat them as absent.
+/// Records without an expiry never expire, and are left out of the index on `expires_at`.
+/// ```ignore
+/// static RESPONSES: StoreDescriptor<Expiring<Response>> = StoreDescriptor::new("responses");
+///
+/// let responses = client.store(&RESPONSES);
+/// responses.put_with_ttl(url, response, Duration::from_secs(3600)).await?;
+/// let cached = responses.get_live(url).await?;
+/// responses.purge_expired().await?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Expiring<V> {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub expires_at: Option<Timestamp>,
+	pub data: V,
+}
+
+impl<V> Expiring<V> {
+	pub fn is_expired_at(&self, now: Timestamp) -> bool {
+		self.expires_at.is_some_and(|expires_at| expires_at <= now)
+	}
+
+	pub fn is_expired(&self) -> bool {
+		self.is_expired_at(Timestamp::now())
+	}
+}
+
+impl<V: Serialize + DeserializeOwned> Store<Expiring<V>> {
+	/// Puts `data` at `key`, expiring `ttl` from now.
+	/// In a store with a key path, `data` must hold `key`, otherwise this fails with [`Error::KeyMismatch`].
+	pub async fn put_with_ttl<K>(&self, key: &K, data: V, ttl: Duration) -> Result<Expiring<V>, Error>
+	where
+		K: IndexedKey + ?Sized,
+	{
+		let store = self.in_transaction::<ReadWrite>()?;
+		let put = store
+			.put_with_ttl_keyed(crate::key_range::to_key(key)?, data, ttl)
+			.await;
+		let (key, saved) = store.finish(put).await?;
+		self.changed(key, ChangeKind::Put);
+		Ok(saved)
+	}
+
+	/// Reads the record at `key`, unless it has expired.
+	pub async fn get_live<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<Option<Expiring<V>>, Error> {
+		self.get_if(key, |record| !record.is_expired()).await
+	}
+
+	/// Reads every record which has not expired, comparing them all against the same time.
+	pub async fn get_all_live(&self) -> Result<Vec<Expiring<V>>, Error> {
+		let now = Timestamp::now();
+		self.get_all_if(|record| !record.is_expired_at(now)).await
+	}
+
+	/// Deletes every expired record from the store, returning how many were deleted. Each deletion is broadcast.
+	pub async fn purge_expired(&self) -> Result<usize, Error> {
+		let store = self.in_transaction::<ReadWrite>()?;
+		let purged = store.purge_expired_keyed().await;
+		let keys = store.finish(purged).await?;
+		let count = keys.len();
+		for key in keys {
+			self.changed(key, ChangeKind::Delete);
+		}
+		Ok(count)
+	}
+}
+
+impl<V: Serialize + DeserializeOwned> TransactionStore<Expiring<V>, ReadWrite> {
+	/// Puts `data` at `key`, expiring `ttl` from now. See [`Store::put_with_ttl`].
+	pub async fn put_with_ttl<K>(&self, key: &K, data: V, ttl: Duration) -> Result<Expiring<V>, Error>
+	where
+		K: IndexedKey + ?Sized,
+	{
+		let (_, saved) = self
+			.put_with_ttl_keyed(crate::key_range::to_key(key)?, data, ttl)
+			.await?;
+		Ok(saved)
+	}
+
+	/// Deletes every expired record, returning how many were deleted. See [`Store::purge_expired`].
+	pub async fn purge_expired(&self) -> Result<usize, Error> {
+		Ok(self.purge_expired_keyed().await?.len())
+	}
+
+	async fn put_with_ttl_keyed(&self, key: JsValue, data: V, ttl: Duration) -> Result<(JsValue, Expiring<V>), Error> {
+		let expires_at = Timestamp::from_millis(Timestamp::now().as_millis() + ttl.as_millis() as f64);
+		let saved = Expiring {
+			expires_at: Some(expires_at),
+			data,
+		};
+		let key = self.put_at_keyed(&saved, key).await?;
+		Ok((key, saved))
+	}
+
+	/// Deletes every record which expired by now, returning their keys.
+	async fn purge_expired_keyed(&self) -> Result<Vec<JsValue>, Error> {
+		let expired = KeyRange::upper_bound(Timestamp::now()).to_query()?;
+		self.delete_indexed_keyed(EXPIRES_AT_INDEX, expired).await
+	}
+}
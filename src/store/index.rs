@@ -0,0 +1,96 @@
+use super::Store;
+use crate::{Error, IndexedKey, KeyRange, TransactionMode};
+use serde::de::DeserializeOwned;
+use std::future::IntoFuture;
+
+/// A typed handle to an index of a [`Store`], created by [`Store::index`].
+/// Each lookup runs in a new read-only transaction.
+/// ```ignore
+/// let user: Option<User> = client.store(&USERS).index("email").get("alice@example.com").await?;
+/// ```
+pub struct StoreIndex<T> {
+	store: Store<T>,
+	name: &'static str,
+}
+
+clone_handle!(StoreIndex { store, name });
+
+impl<T> StoreIndex<T> {
+	pub(crate) fn new(store: Store<T>, name: &'static str) -> Self {
+		Self { store, name }
+	}
+
+	pub fn name(&self) -> &'static str {
+		self.name
+	}
+
+	fn index(&self) -> Result<idb::Index, Error> {
+		Ok(self.store.object_store(TransactionMode::ReadOnly)?.index(self.name)?)
+	}
+
+	/// Counts the records whose index keys are in `range`, or every record in the index if there is no range.
+	pub async fn count_in<K: IndexedKey>(&self, range: Option<&KeyRange<K>>) -> Result<u32, Error> {
+		let query = range.map(KeyRange::to_query).transpose()?;
+		Ok(self.index()?.count(query)?.await?)
+	}
+
+	/// The primary keys of the records whose index keys are in `range`, or of every record in the index,
+	/// without reading the record values. At most `limit` keys are returned, in index order.
+	pub async fn get_all_keys<K, P>(&self, range: Option<&KeyRange<K>>, limit: Option<u32>) -> Result<Vec<P>, Error>
+	where
+		K: IndexedKey,
+		P: DeserializeOwned,
+	{
+		let query = range.map(KeyRange::to_query).transpose()?;
+		// getAllKeys treats a count of 0 as no limit
+		if limit == Some(0) {
+			return Ok(Vec::new());
+		}
+		let js_keys = self.index()?.get_all_keys(query, limit)?.into_future().await?;
+		let mut keys = Vec::with_capacity(js_keys.len());
+		for js_key in js_keys {
+			keys.push(serde_wasm_bindgen::from_value::<P>(js_key)?);
+		}
+		Ok(keys)
+	}
+}
+
+impl<T: DeserializeOwned> StoreIndex<T> {
+	/// The first record whose index key is `value`.
+	pub async fn get<K: IndexedKey + ?Sized>(&self, value: &K) -> Result<Option<T>, Error> {
+		let query = idb::Query::Key(crate::key_range::to_key(value)?);
+		let Some(js_value) = self.index()?.get(query)?.await? else {
+			return Ok(None);
+		};
+		Ok(Some(serde_wasm_bindgen::from_value::<T>(js_value)?))
+	}
+
+	/// Every record whose index key is `value`.
+	pub async fn get_all<K: IndexedKey + ?Sized>(&self, value: &K) -> Result<Vec<T>, Error> {
+		self.read_all(Some(idb::Query::Key(crate::key_range::to_key(value)?)), None)
+			.await
+	}
+
+	/// The records whose index keys are in `range`, or every record in the index if there is no range.
+	/// At most `limit` records are returned, in index order.
+	pub async fn get_all_in<K: IndexedKey>(
+		&self,
+		range: Option<&KeyRange<K>>,
+		limit: Option<u32>,
+	) -> Result<Vec<T>, Error> {
+		self.read_all(range.map(KeyRange::to_query).transpose()?, limit).await
+	}
+
+	async fn read_all(&self, query: Option<idb::Query>, limit: Option<u32>) -> Result<Vec<T>, Error> {
+		// getAll treats a count of 0 as no limit
+		if limit == Some(0) {
+			return Ok(Vec::new());
+		}
+		let js_values = self.index()?.get_all(query, limit)?.into_future().await?;
+		let mut values = Vec::with_capacity(js_values.len());
+		for js_value in js_values {
+			values.push(serde_wasm_bindgen::from_value::<T>(js_value)?);
+		}
+		Ok(values)
+	}
+}
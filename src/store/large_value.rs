@@ -0,0 +1,337 @@
+use super::Store;
+use crate::{js::call, ChangeKind, Error, IndexedKey, TransactionMode};
+use futures_util::{Stream, TryStreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use std::future::IntoFuture;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+/// The field of a manifest record which holds how many chunks the value was split into.
+pub(super) const MARKER: &str = "$chunks";
+
+/// A [`Store`] which splits values larger than a threshold into several chunk records,
+/// for browsers (such as Safari) which struggle with very large records. Created by [`Store::large_values`].
+///
+/// Each value is written as JSON. Values within the threshold are stored inline as `{ "$chunks": 0, data: Uint8Array }`,
+/// and larger ones as a manifest `{ "$chunks": count, id, length }` at their key
+/// with each chunk stored as a `Uint8Array` at the key `[key, id, index]`.
+/// Records without the marker are read as usual. Manifests are [wrapped records](Store#wrapped-records),
+/// and the chunks share the store with them, so the store should only be written through this wrapper.
+/// ```ignore
+/// let attachments = client.store(&ATTACHMENTS).large_values(1 << 20);
+/// attachments.put(&"video", &video).await?;
+/// let mut chunks = attachments.stream(&"video").await?.unwrap();
+/// while let Some(chunk) = chunks.try_next().await? {
+///   parser.feed(&chunk);
+/// }
+/// ```
+pub struct LargeValueStore<T> {
+	store: Store<T>,
+	threshold: usize,
+}
+
+clone_handle!(LargeValueStore { store, threshold });
+
+impl<T> LargeValueStore<T> {
+	pub(crate) fn new(store: Store<T>, threshold: usize) -> Self {
+		Self {
+			store,
+			threshold: threshold.max(1),
+		}
+	}
+
+	/// The largest number of bytes stored in a single record.
+	pub fn threshold(&self) -> usize {
+		self.threshold
+	}
+
+	/// Streams the bytes of the value at `key`, reading each chunk in its own transaction
+	/// so only one chunk is held in memory at a time. Values written by [`LargeValueStore::put`] are streamed as JSON.
+	/// The stream fails if the value is replaced or deleted before every chunk has been read.
+	pub async fn stream<K: IndexedKey + ?Sized>(
+		&self,
+		key: &K,
+	) -> Result<Option<impl Stream<Item = Result<Vec<u8>, Error>> + '_>, Error> {
+		let key = crate::key_range::to_key(key)?;
+		let Some(manifest) = self.manifest(&key).await? else {
+			return Ok(None);
+		};
+		let chunks = futures_util::stream::try_unfold(0, move |index| {
+			let key = key.clone();
+			let manifest = manifest.clone();
+			async move {
+				let chunk = manifest.chunk(&self.store, &key, index).await?;
+				Ok(chunk.map(|chunk| (chunk, index + 1)))
+			}
+		});
+		Ok(Some(chunks))
+	}
+
+	/// Writes the bytes of a web `ReadableStream` at `key` as they are read, such as the body of a large download,
+	/// returning how many bytes were written. Each chunk is written in its own transaction,
+	/// and the value replaces any previous one once the stream has ended, so reads never see a partial value.
+	/// If the stream fails, or reads anything other than a `Uint8Array`, the chunks which were written are deleted.
+	/// If the page closes before the stream ends, those chunks are left behind until the next
+	/// [`put`](LargeValueStore::put) or [`delete`](LargeValueStore::delete) at `key`, which removes every chunk of the key.
+	pub async fn put_stream<K: IndexedKey + ?Sized>(
+		&self,
+		key: &K,
+		stream: &web_sys::ReadableStream,
+	) -> Result<usize, Error> {
+		let key = crate::key_range::to_key(key)?;
+		let id = JsValue::from(js_sys::Math::random());
+		let reader = call(stream, "getReader", &[])?;
+		let (count, length) = match self.write_chunks(&key, &id, &reader).await {
+			Ok(written) => written,
+			Err(err) => {
+				let _ = call(&reader, "cancel", &[]);
+				if let Err(err) = self.delete_chunks(&key, &id).await {
+					log::warn!(target: "database::store", "Failed to delete the chunks of a failed stream: {err}");
+				}
+				return Err(err);
+			}
+		};
+
+		let manifest = js_sys::Object::new();
+		let set = |field: &str, value: &JsValue| js_sys::Reflect::set(&manifest, &field.into(), value);
+		set(MARKER, &count.into())
+			.and_then(|_| set("id", &id))
+			.and_then(|_| set("length", &length.into()))
+			.and_then(|_| match count {
+				0 => set("data", &js_sys::Uint8Array::new_with_length(0)),
+				_ => Ok(true),
+			})
+			.map_err(|err| Error::Internal(format!("{err:?}")))?;
+
+		let store = self.store.object_store(TransactionMode::ReadWrite)?;
+		let written = async {
+			// only the chunks of the previous value are deleted, in case it was streamed too
+			let previous = store.get(idb::Query::Key(key.clone()))?.await?;
+			let previous = previous.map(|previous| js_sys::Reflect::get(&previous, &"id".into()).unwrap_or_default());
+			if let Some(previous) = previous.filter(|previous| !previous.is_undefined()) {
+				store.delete(chunk_range(&key, Some(&previous))?)?.into_future().await?;
+			}
+			store.put(&manifest, Some(&key))?.into_future().await?;
+			Ok::<_, Error>(())
+		};
+		if let Err(err) = written.await {
+			let _ = store.transaction().abort();
+			return Err(err);
+		}
+		crate::scoped_transaction::commit(store.transaction()).await?;
+		self.store.changed(key, ChangeKind::Put);
+		Ok(length)
+	}
+
+	/// Reads the stream in chunks of the threshold, writing each one at `[key, id, index]`
+	/// and returning how many chunks and bytes were written.
+	async fn write_chunks(&self, key: &JsValue, id: &JsValue, reader: &JsValue) -> Result<(usize, usize), Error> {
+		let mut buffer = Vec::new();
+		let (mut count, mut length) = (0, 0);
+		loop {
+			let read = call(reader, "read", &[])?;
+			let read = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::from(read))
+				.await
+				.map_err(|err| Error::Internal(format!("{err:?}")))?;
+			let get = |field: &str| js_sys::Reflect::get(&read, &field.into()).unwrap_or_default();
+			let done = get("done").is_truthy();
+			if !done {
+				let bytes = get("value")
+					.dyn_into::<js_sys::Uint8Array>()
+					.map_err(|value| Error::Serialization(format!("the stream read {value:?}, which is not bytes")))?
+					.to_vec();
+				length += bytes.len();
+				buffer.extend(bytes);
+			}
+			while buffer.len() >= self.threshold || (done && !buffer.is_empty()) {
+				let rest = buffer.split_off(self.threshold.min(buffer.len()));
+				let store = self.store.object_store(TransactionMode::ReadWrite)?;
+				let chunk_key = chunk_key(key, id, count as u32);
+				store
+					.put(&js_sys::Uint8Array::from(buffer.as_slice()), Some(&chunk_key))?
+					.await?;
+				crate::scoped_transaction::commit(store.transaction()).await?;
+				buffer = rest;
+				count += 1;
+			}
+			if done {
+				return Ok((count, length));
+			}
+		}
+	}
+
+	async fn delete_chunks(&self, key: &JsValue, id: &JsValue) -> Result<(), Error> {
+		let store = self.store.object_store(TransactionMode::ReadWrite)?;
+		store.delete(chunk_range(key, Some(id))?)?.into_future().await?;
+		crate::scoped_transaction::commit(store.transaction()).await
+	}
+
+	async fn manifest(&self, key: &JsValue) -> Result<Option<Manifest>, Error> {
+		let store = self.store.object_store(TransactionMode::ReadOnly)?;
+		let Some(record) = store.get(idb::Query::Key(key.clone()))?.await? else {
+			return Ok(None);
+		};
+		let get = |field: &str| js_sys::Reflect::get(&record, &field.into()).unwrap_or_default();
+		Ok(Some(match get(MARKER).as_f64() {
+			Some(count) if count > 0.0 => Manifest::Chunked {
+				count: count as u32,
+				id: get("id"),
+			},
+			Some(_) => Manifest::Inline(js_sys::Uint8Array::new(&get("data")).to_vec()),
+			None => {
+				let json = js_sys::JSON::stringify(&record).map_err(|err| Error::Serialization(format!("{err:?}")))?;
+				Manifest::Inline(String::from(json).into_bytes())
+			}
+		}))
+	}
+
+	/// Deletes the value at `key` along with its chunks.
+	pub async fn delete<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<(), Error> {
+		let key = crate::key_range::to_key(key)?;
+		let store = self.store.object_store(TransactionMode::ReadWrite)?;
+		store.delete(chunk_range(&key, None)?)?.into_future().await?;
+		store.delete(idb::Query::Key(key.clone()))?.into_future().await?;
+		crate::scoped_transaction::commit(store.transaction()).await?;
+		self.store.changed(key, ChangeKind::Delete);
+		Ok(())
+	}
+}
+
+impl<T: 'static> LargeValueStore<T> {
+	/// Streams the bytes of the value at `key` as a web `ReadableStream`, such as for the body of a `Response`.
+	/// Like [`LargeValueStore::stream`], each chunk is read in its own transaction when the stream pulls it.
+	pub async fn get_stream<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<Option<web_sys::ReadableStream>, Error> {
+		let key = crate::key_range::to_key(key)?;
+		let Some(manifest) = self.manifest(&key).await? else {
+			return Ok(None);
+		};
+		let store = self.store.clone();
+		let next = std::rc::Rc::new(std::cell::Cell::new(0));
+		let pull = Closure::<dyn FnMut(JsValue) -> js_sys::Promise>::new(move |controller: JsValue| {
+			let (store, key, manifest) = (store.clone(), key.clone(), manifest.clone());
+			let index = next.get();
+			next.set(index + 1);
+			wasm_bindgen_futures::future_to_promise(async move {
+				let pulled = match manifest.chunk(&store, &key, index).await {
+					Ok(Some(chunk)) => call(
+						&controller,
+						"enqueue",
+						&[js_sys::Uint8Array::from(chunk.as_slice()).into()],
+					),
+					Ok(None) => call(&controller, "close", &[]),
+					Err(err) => Err(err),
+				};
+				pulled.map_err(|err| js_sys::Error::new(&err.to_string()).into())
+			})
+		});
+
+		let source = js_sys::Object::new();
+		js_sys::Reflect::set(&source, &"pull".into(), &pull.into_js_value())
+			.map_err(|err| Error::Internal(format!("{err:?}")))?;
+		web_sys::ReadableStream::new_with_underlying_source(&source)
+			.map_err(|err| Error::Internal(format!("{err:?}")))
+			.map(Some)
+	}
+}
+
+impl<T: Serialize + DeserializeOwned> LargeValueStore<T> {
+	/// Puts the value at `key`, splitting it into chunks if its JSON is larger than the threshold.
+	/// The manifest and every chunk are written in one transaction, replacing the chunks of any previous value.
+	pub async fn put<K: IndexedKey + ?Sized>(&self, key: &K, value: &T) -> Result<(), Error> {
+		let key = crate::key_range::to_key(key)?;
+		let json = serde_json::to_vec(value).map_err(|err| Error::Serialization(err.to_string()))?;
+		let chunks = match json.len() <= self.threshold {
+			true => Vec::new(),
+			false => json.chunks(self.threshold).collect(),
+		};
+		// each value gets a new id, so a stream of the previous value cannot read chunks of this one
+		let id = JsValue::from(js_sys::Math::random());
+		let manifest = js_sys::Object::new();
+		let set = |field: &str, value: &JsValue| js_sys::Reflect::set(&manifest, &field.into(), value);
+		match chunks.is_empty() {
+			true => set(MARKER, &0.into()).and_then(|_| set("data", &js_sys::Uint8Array::from(json.as_slice()))),
+			false => set(MARKER, &chunks.len().into())
+				.and_then(|_| set("id", &id))
+				.and_then(|_| set("length", &json.len().into())),
+		}
+		.map_err(|err| Error::Internal(format!("{err:?}")))?;
+
+		let store = self.store.object_store(TransactionMode::ReadWrite)?;
+		let written = async {
+			store.delete(chunk_range(&key, None)?)?.into_future().await?;
+			let mut requests = Vec::with_capacity(chunks.len() + 1);
+			for (index, chunk) in chunks.into_iter().enumerate() {
+				let chunk_key = chunk_key(&key, &id, index as u32);
+				requests.push(
+					store
+						.put(&js_sys::Uint8Array::from(chunk), Some(&chunk_key))?
+						.into_future(),
+				);
+			}
+			requests.push(store.put(&manifest, Some(&key))?.into_future());
+			futures_util::future::try_join_all(requests).await?;
+			Ok::<_, Error>(())
+		};
+		if let Err(err) = written.await {
+			let _ = store.transaction().abort();
+			return Err(err);
+		}
+		crate::scoped_transaction::commit(store.transaction()).await?;
+		self.store.changed(key, ChangeKind::Put);
+		Ok(())
+	}
+
+	/// Reads the value at `key`, reassembling it from its chunks.
+	/// Records which were not written by this wrapper are read as usual.
+	pub async fn get<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<Option<T>, Error> {
+		let Some(chunks) = self.stream(key).await? else {
+			return Ok(None);
+		};
+		let json = chunks.try_concat().await?;
+		let value = serde_json::from_slice(&json).map_err(|err| Error::Serialization(err.to_string()))?;
+		Ok(Some(value))
+	}
+}
+
+fn chunk_key(key: &JsValue, id: &JsValue, index: u32) -> JsValue {
+	js_sys::Array::of3(key, id, &index.into()).into()
+}
+
+/// Every chunk key of the value at `key`, or only those of the value `id`.
+/// Arrays sort after every other kind of key, so `[key, []]` is above every `[key, id, index]`
+/// and `[key, id, []]` is above every `[key, id, index]`.
+fn chunk_range(key: &JsValue, id: Option<&JsValue>) -> Result<idb::Query, Error> {
+	let prefix = js_sys::Array::of1(key);
+	if let Some(id) = id {
+		prefix.push(id);
+	}
+	let upper = prefix.concat(&js_sys::Array::of1(&js_sys::Array::new()));
+	Ok(idb::Query::KeyRange(idb::KeyRange::bound(&prefix, &upper, None, None)?))
+}
+
+/// How a value is stored at its key.
+#[derive(Clone)]
+enum Manifest {
+	/// The bytes of a value which was small enough to store in one record.
+	Inline(Vec<u8>),
+	Chunked {
+		count: u32,
+		id: JsValue,
+	},
+}
+
+impl Manifest {
+	/// Reads the chunk at `index`, or `None` after the last chunk.
+	async fn chunk<T>(&self, store: &Store<T>, key: &JsValue, index: u32) -> Result<Option<Vec<u8>>, Error> {
+		match self {
+			Self::Inline(data) => Ok((index == 0).then(|| data.clone())),
+			Self::Chunked { count, .. } if index >= *count => Ok(None),
+			Self::Chunked { id, .. } => {
+				let store = store.object_store(TransactionMode::ReadOnly)?;
+				let Some(chunk) = store.get(idb::Query::Key(chunk_key(key, id, index)))?.await? else {
+					return Err(Error::Internal("the value changed while its chunks were read".into()));
+				};
+				Ok(Some(js_sys::Uint8Array::new(&chunk).to_vec()))
+			}
+		}
+	}
+}
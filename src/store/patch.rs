@@ -0,0 +1,28 @@
+use wasm_bindgen::{JsCast, JsValue};
+
+/// Applies `patch` to `target` as a JSON merge patch: each field of the patch replaces the field of the target,
+/// objects are merged field by field, and `null` fields are removed.
+/// A patch which is not an object replaces the target.
+pub(super) fn merge(target: JsValue, patch: &JsValue) -> JsValue {
+	if !is_plain_object(patch) {
+		return patch.clone();
+	}
+	let target = match is_plain_object(&target) {
+		true => target,
+		false => js_sys::Object::new().into(),
+	};
+	for field in js_sys::Object::keys(patch.unchecked_ref::<js_sys::Object>()) {
+		let value = js_sys::Reflect::get(patch, &field).unwrap_or(JsValue::NULL);
+		if value.is_null() {
+			let _ = js_sys::Reflect::delete_property(target.unchecked_ref::<js_sys::Object>(), &field);
+			continue;
+		}
+		let existing = js_sys::Reflect::get(&target, &field).unwrap_or(JsValue::UNDEFINED);
+		let _ = js_sys::Reflect::set(&target, &field, &merge(existing, &value));
+	}
+	target
+}
+
+fn is_plain_object(value: &JsValue) -> bool {
+	value.is_object() && !js_sys::Array::is_array(value)
+}
@@ -0,0 +1,144 @@
+use super::Store;
+use crate::{Cursor, CursorDirection, Error, IndexedKey, KeyRange, TransactionMode};
+use futures_util::TryStreamExt;
+use serde::de::DeserializeOwned;
+use std::future::IntoFuture;
+
+/// Reads the records of a [`Store`] which match a key range, created by [`Store::query`].
+/// A query reads in a single request with `getAll` when it can, and otherwise through a cursor.
+/// Each query runs in a new read-only transaction.
+/// ```ignore
+/// let recent: Vec<Post> = client
+///   .store(&POSTS)
+///   .query()
+///   .index("created_at")
+///   .between(&start, &end)
+///   .reverse()
+///   .limit(50)
+///   .collect()
+///   .await?;
+/// ```
+pub struct StoreQuery<T> {
+	store: Store<T>,
+	index: Option<&'static str>,
+	range: Result<Option<idb::Query>, Error>,
+	reverse: bool,
+	limit: Option<u32>,
+}
+
+impl<T> StoreQuery<T> {
+	pub(crate) fn new(store: Store<T>) -> Self {
+		Self {
+			store,
+			index: None,
+			range: Ok(None),
+			reverse: false,
+			limit: None,
+		}
+	}
+
+	/// Matches by the keys of the index called `name` instead of the primary keys of the store.
+	pub fn index(mut self, name: &'static str) -> Self {
+		self.index = Some(name);
+		self
+	}
+
+	/// Only matches records whose key is in `range`.
+	pub fn range<K: IndexedKey>(mut self, range: &KeyRange<K>) -> Self {
+		self.range = range.to_query().map(Some);
+		self
+	}
+
+	/// Only matches records whose key is `key`.
+	pub fn equals<K: IndexedKey>(self, key: K) -> Self {
+		self.range(&KeyRange::only(key))
+	}
+
+	/// Only matches records whose key is between `lower` and `upper`, inclusive.
+	pub fn between<K: IndexedKey>(self, lower: K, upper: K) -> Self {
+		self.range(&KeyRange::bound(lower, upper))
+	}
+
+	/// Only matches records whose key is after `lower`.
+	pub fn above<K: IndexedKey>(self, lower: K) -> Self {
+		self.range(&KeyRange::lower_bound(lower).lower_open())
+	}
+
+	/// Only matches records whose key is at or after `lower`.
+	pub fn at_least<K: IndexedKey>(self, lower: K) -> Self {
+		self.range(&KeyRange::lower_bound(lower))
+	}
+
+	/// Only matches records whose key is before `upper`.
+	pub fn below<K: IndexedKey>(self, upper: K) -> Self {
+		self.range(&KeyRange::upper_bound(upper).upper_open())
+	}
+
+	/// Only matches records whose key is at or before `upper`.
+	pub fn at_most<K: IndexedKey>(self, upper: K) -> Self {
+		self.range(&KeyRange::upper_bound(upper))
+	}
+
+	/// Reads the matching records from the largest key to the smallest.
+	pub fn reverse(mut self) -> Self {
+		self.reverse = true;
+		self
+	}
+
+	/// Reads at most `count` records, so a limit of 0 reads none.
+	pub fn limit(mut self, count: u32) -> Self {
+		self.limit = Some(count);
+		self
+	}
+}
+
+impl<T: DeserializeOwned> StoreQuery<T> {
+	/// Reads every matching record, up to the limit.
+	pub async fn collect(self) -> Result<Vec<T>, Error> {
+		let query = self.range?;
+		// getAll would read every record for a count of 0
+		if self.limit == Some(0) {
+			return Ok(Vec::new());
+		}
+		let store = self.store.object_store(TransactionMode::ReadOnly)?;
+		let index = self.index.map(|name| store.index(name)).transpose()?;
+		if !self.reverse {
+			let js_values = match &index {
+				Some(index) => index.get_all(query, self.limit)?.into_future().await?,
+				None => store.get_all(query, self.limit)?.into_future().await?,
+			};
+			let mut values = Vec::with_capacity(js_values.len());
+			for js_value in js_values {
+				values.push(serde_wasm_bindgen::from_value::<T>(js_value)?);
+			}
+			return Ok(values);
+		}
+
+		// getAll only reads in ascending order
+		let direction = Some(CursorDirection::Prev);
+		let cursor = match &index {
+			Some(index) => index.open_cursor(query.clone(), direction)?.await?,
+			None => store.open_cursor(query.clone(), direction)?.await?,
+		};
+		let mut cursor = Cursor::<T>::new(cursor).with_query(query.as_ref());
+		if let Some(limit) = self.limit {
+			cursor = cursor.take(limit);
+		}
+		cursor.into_try_stream().try_collect().await
+	}
+
+	/// Reads the first matching record.
+	pub async fn first(self) -> Result<Option<T>, Error> {
+		Ok(self.limit(1).collect().await?.pop())
+	}
+
+	/// Counts the matching records, ignoring the limit.
+	pub async fn count(self) -> Result<u32, Error> {
+		let query = self.range?;
+		let store = self.store.object_store(TransactionMode::ReadOnly)?;
+		Ok(match self.index {
+			Some(name) => store.index(name)?.count(query)?.await?,
+			None => store.count(query)?.await?,
+		})
+	}
+}
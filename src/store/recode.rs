@@ -0,0 +1,246 @@
+use super::Store;
+use crate::{Codec, Compression, Error, SerializerOptions, TransactionMode};
+use futures_util::future::LocalBoxFuture;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{future::IntoFuture, marker::PhantomData};
+use wasm_bindgen::{JsCast, JsValue};
+
+/// A way of storing records of type `T` as JS values, which [`Store::recode`] converts records between.
+pub trait RecordFormat<T> {
+	/// True if `value` looks like it was written in this format.
+	fn is_format(&self, value: &JsValue) -> bool;
+	fn decode(&self, value: JsValue) -> LocalBoxFuture<'_, Result<T, Error>>;
+	fn encode<'a>(&'a self, value: &'a T) -> LocalBoxFuture<'a, Result<JsValue, Error>>;
+}
+
+/// Records stored as JS objects, the way a [`Store`] writes them.
+impl<T: Serialize + DeserializeOwned> RecordFormat<T> for SerializerOptions {
+	fn is_format(&self, value: &JsValue) -> bool {
+		let markers = [
+			super::compressed::MARKER,
+			super::encrypted::MARKER,
+			super::large_value::MARKER,
+		];
+		let marked = markers
+			.iter()
+			.any(|marker| js_sys::Reflect::has(value, &(*marker).into()).unwrap_or_default());
+		value.is_object()
+			&& !marked
+			&& !value.is_instance_of::<js_sys::ArrayBuffer>()
+			&& !js_sys::ArrayBuffer::is_view(value)
+	}
+
+	fn decode(&self, value: JsValue) -> LocalBoxFuture<'_, Result<T, Error>> {
+		Box::pin(async move { Ok(serde_wasm_bindgen::from_value(value)?) })
+	}
+
+	fn encode<'a>(&'a self, value: &'a T) -> LocalBoxFuture<'a, Result<JsValue, Error>> {
+		Box::pin(async move { Ok(self.serialize(value)?) })
+	}
+}
+
+/// Records stored the way a [`CompressedStore`](super::CompressedStore) writes them.
+impl<T: Serialize + DeserializeOwned> RecordFormat<T> for Compression {
+	fn is_format(&self, value: &JsValue) -> bool {
+		super::compressed::compression_of(value) == Some(*self)
+	}
+
+	fn decode(&self, value: JsValue) -> LocalBoxFuture<'_, Result<T, Error>> {
+		Box::pin(async move { super::compressed::decode(value).await })
+	}
+
+	fn encode<'a>(&'a self, value: &'a T) -> LocalBoxFuture<'a, Result<JsValue, Error>> {
+		Box::pin(super::compressed::encode(*self, value))
+	}
+}
+
+/// Records stored as a `Uint8Array` of their encoding with the codec `C`, the way [`Encoded`](crate::Encoded) records are.
+pub struct Binary<C>(PhantomData<fn() -> C>);
+
+impl<C> Binary<C> {
+	pub fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<C> Default for Binary<C> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T: Serialize + DeserializeOwned, C: Codec> RecordFormat<T> for Binary<C> {
+	fn is_format(&self, value: &JsValue) -> bool {
+		value.is_instance_of::<js_sys::Uint8Array>()
+	}
+
+	fn decode(&self, value: JsValue) -> LocalBoxFuture<'_, Result<T, Error>> {
+		Box::pin(async move {
+			let bytes = value
+				.dyn_into::<js_sys::Uint8Array>()
+				.map_err(|_| Error::Serialization("the record is not a Uint8Array".into()))?;
+			C::decode(&bytes.to_vec()).map_err(Error::Serialization)
+		})
+	}
+
+	fn encode<'a>(&'a self, value: &'a T) -> LocalBoxFuture<'a, Result<JsValue, Error>> {
+		Box::pin(async move {
+			let bytes = C::encode(value).map_err(Error::Serialization)?;
+			Ok(js_sys::Uint8Array::from(bytes.as_slice()).into())
+		})
+	}
+}
+
+impl<T: Serialize + DeserializeOwned> Store<T> {
+	/// Rewrites every record of the store from the format `from` to the format `to`, returning how many were rewritten.
+	///
+	/// Records are read `batch_size` at a time, each batch in its own transaction, and converted between transactions
+	/// so formats can do asynchronous work such as compression. Records which are already in the format `to` are skipped,
+	/// so a recode which was interrupted (such as by the page closing) can be run again to finish it.
+	/// Records in neither format are skipped too, as are records which were changed while their batch was converted.
+	/// Rewrites are not broadcast, since the records' values are unchanged.
+	/// ```ignore
+	/// let store = client.store(&DOCUMENTS);
+	/// store.recode(&SerializerOptions::new(), &Compression::Gzip, 100).await?;
+	/// ```
+	pub async fn recode<F, G>(&self, from: &F, to: &G, batch_size: u32) -> Result<usize, Error>
+	where
+		F: RecordFormat<T>,
+		G: RecordFormat<T>,
+	{
+		let batch_size = batch_size.max(1);
+		let mut after = None::<JsValue>;
+		let mut recoded = 0;
+		loop {
+			let (keys, values) = self.read_batch(after.as_ref(), Some(batch_size)).await?;
+			let Some(last) = keys.last().cloned() else {
+				break;
+			};
+			let read = keys.len();
+
+			let mut batch = Vec::new();
+			for (key, value) in keys.into_iter().zip(values) {
+				// records in neither format (such as from another writer) are left as they are
+				if to.is_format(&value) || !from.is_format(&value) {
+					continue;
+				}
+				let record = from.decode(value.clone()).await?;
+				let recoded = to.encode(&record).await?;
+				batch.push(Recoded {
+					key,
+					read: value,
+					recoded,
+				});
+			}
+			if !batch.is_empty() {
+				recoded += self.write_recoded(batch).await?;
+			}
+
+			if read < batch_size as usize {
+				break;
+			}
+			after = Some(last);
+		}
+		Ok(recoded)
+	}
+
+	/// Puts each recoded value, unless its record was changed or deleted since it was read,
+	/// so that a write made while the batch was being converted is never overwritten with older data.
+	async fn write_recoded(&self, batch: Vec<Recoded>) -> Result<usize, Error> {
+		let store = self.object_store(TransactionMode::ReadWrite)?;
+		let written = async {
+			let out_of_line = store.key_path()?.is_none();
+			let mut reads = Vec::with_capacity(batch.len());
+			for record in &batch {
+				reads.push(store.get(idb::Query::Key(record.key.clone()))?.into_future());
+			}
+			let current = futures_util::future::try_join_all(reads).await?;
+
+			let mut writes = Vec::with_capacity(batch.len());
+			for (record, current) in batch.iter().zip(current) {
+				match current {
+					Some(current) if same_value(&current, &record.read) => {
+						let key = out_of_line.then_some(&record.key);
+						writes.push(store.put(&record.recoded, key)?.into_future());
+					}
+					_ => {}
+				}
+			}
+			let count = writes.len();
+			futures_util::future::try_join_all(writes).await?;
+			Ok::<_, Error>(count)
+		};
+		match written.await {
+			Ok(count) => {
+				crate::scoped_transaction::commit(store.transaction()).await?;
+				Ok(count)
+			}
+			Err(err) => {
+				let _ = store.transaction().abort();
+				Err(err)
+			}
+		}
+	}
+}
+
+/// A record converted by [`Store::recode`].
+struct Recoded {
+	key: JsValue,
+	/// The stored value the record was converted from.
+	read: JsValue,
+	recoded: JsValue,
+}
+
+/// True if `a` and `b` hold the same data, comparing arrays, plain objects, dates and binary data by their contents.
+/// Other objects (such as blobs) are only the same as themselves.
+pub(super) fn same_value(a: &JsValue, b: &JsValue) -> bool {
+	use js_sys::{Array, ArrayBuffer, Date, Object, Reflect, Uint8Array};
+	if Object::is(a, b) {
+		return true;
+	}
+	if !a.is_object() || !b.is_object() {
+		return false;
+	}
+	let (a, b) = (a.unchecked_ref::<Object>(), b.unchecked_ref::<Object>());
+	let prototype = Object::get_prototype_of(a);
+	if JsValue::from(&prototype) != JsValue::from(Object::get_prototype_of(b)) {
+		return false;
+	}
+	if let (Some(a), Some(b)) = (a.dyn_ref::<Date>(), b.dyn_ref::<Date>()) {
+		return Object::is(&a.get_time().into(), &b.get_time().into());
+	}
+	let bytes = |value: &Object| {
+		if value.is_instance_of::<ArrayBuffer>() {
+			return Some(Uint8Array::new(value).to_vec());
+		}
+		if !ArrayBuffer::is_view(value) {
+			return None;
+		}
+		let field = |name: &str| Reflect::get(value, &name.into()).ok();
+		let buffer = field("buffer")?.dyn_into::<ArrayBuffer>().ok()?;
+		let offset = field("byteOffset")?.as_f64()? as u32;
+		let length = field("byteLength")?.as_f64()? as u32;
+		Some(Uint8Array::new_with_byte_offset_and_length(&buffer, offset, length).to_vec())
+	};
+	if let (Some(a), Some(b)) = (bytes(a), bytes(b)) {
+		return a == b;
+	}
+	if Array::is_array(a) {
+		let (a, b) = (a.unchecked_ref::<Array>(), b.unchecked_ref::<Array>());
+		return a.length() == b.length() && (0..a.length()).all(|index| same_value(&a.get(index), &b.get(index)));
+	}
+	let plain =
+		prototype.is_null() || JsValue::from(&prototype) == JsValue::from(Object::get_prototype_of(&Object::new()));
+	if !plain {
+		return false;
+	}
+	let keys = Object::keys(a);
+	keys.length() == Object::keys(b).length()
+		&& keys.iter().all(|key| {
+			Reflect::has(b, &key).unwrap_or_default()
+				&& match (Reflect::get(a, &key), Reflect::get(b, &key)) {
+					(Ok(a), Ok(b)) => same_value(&a, &b),
+					_ => false,
+				}
+		})
+}
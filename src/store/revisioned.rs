@@ -0,0 +1,113 @@
+use super::{Store, TransactionStore};
+use crate::{ChangeKind, Error, IndexedKey, ReadWrite, Versioned};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+/// A record stored with a revision number, which is incremented on every [save](Store::save),
+/// so that saving a copy which was read before another tab saved it is rejected instead of overwriting that change.
+/// A store of revisioned records is declared with a `StoreDescriptor<Revisioned<V>>`.
+/// ```ignore
+/// static DOCS: StoreDescriptor<Revisioned<Doc>> = StoreDescriptor::new("docs");
+///
+/// let docs = client.store(&DOCS);
+/// let doc = docs.get("readme").await?.unwrap_or_default();
+/// let saved = docs.save("readme", doc.rev, edit(doc.data)).await?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Revisioned<V> {
+	/// The number of times the record has been saved. Records which do not exist yet are at revision 0.
+	pub rev: u64,
+	pub data: V,
+}
+
+impl<V> Versioned for Revisioned<V> {
+	fn version(&self) -> u64 {
+		self.rev
+	}
+}
+
+impl<V: Serialize + DeserializeOwned> Store<Revisioned<V>> {
+	/// Saves `data` at `key` as the revision after `rev`, which is the revision the data was read at.
+	/// Fails with [`Error::Conflict`] if the record was saved by someone else since then.
+	pub async fn save<K>(&self, key: &K, rev: u64, data: V) -> Result<Revisioned<V>, Error>
+	where
+		K: IndexedKey + ?Sized,
+	{
+		self.save_with(key, rev, data, |_, _| None).await
+	}
+
+	/// Saves `data` at `key` like [`Store::save`], but when the record was saved by someone else since `rev`,
+	/// `resolve` is given the stored record and `data`, and returns the data to save over the stored record instead.
+	/// Returning `None` fails with [`Error::Conflict`].
+	pub async fn save_with<K, F>(&self, key: &K, rev: u64, data: V, resolve: F) -> Result<Revisioned<V>, Error>
+	where
+		K: IndexedKey + ?Sized,
+		F: FnOnce(Revisioned<V>, V) -> Option<V>,
+	{
+		let store = self.in_transaction::<ReadWrite>()?;
+		let (key, saved) = store
+			.save_keyed(crate::key_range::to_key(key)?, rev, data, resolve)
+			.await?;
+		store.commit().await?;
+		self.changed(key, ChangeKind::Put);
+		Ok(saved)
+	}
+}
+
+impl<V: Serialize + DeserializeOwned> TransactionStore<Revisioned<V>, ReadWrite> {
+	/// Saves `data` at `key` as the revision after `rev`. See [`Store::save`].
+	pub async fn save<K>(&self, key: &K, rev: u64, data: V) -> Result<Revisioned<V>, Error>
+	where
+		K: IndexedKey + ?Sized,
+	{
+		self.save_with(key, rev, data, |_, _| None).await
+	}
+
+	/// Saves `data` at `key`, resolving conflicting saves with `resolve`. See [`Store::save_with`].
+	pub async fn save_with<K, F>(&self, key: &K, rev: u64, data: V, resolve: F) -> Result<Revisioned<V>, Error>
+	where
+		K: IndexedKey + ?Sized,
+		F: FnOnce(Revisioned<V>, V) -> Option<V>,
+	{
+		let (_, saved) = self
+			.save_keyed(crate::key_range::to_key(key)?, rev, data, resolve)
+			.await?;
+		Ok(saved)
+	}
+
+	/// Puts the next revision of the record at `key`, returning its key.
+	async fn save_keyed<F>(
+		&self,
+		key: JsValue,
+		rev: u64,
+		data: V,
+		resolve: F,
+	) -> Result<(JsValue, Revisioned<V>), Error>
+	where
+		F: FnOnce(Revisioned<V>, V) -> Option<V>,
+	{
+		let current = match self.store.get(idb::Query::Key(key.clone()))?.await? {
+			Some(js_value) => Some(serde_wasm_bindgen::from_value::<Revisioned<V>>(js_value)?),
+			None => None,
+		};
+		let current_rev = current.as_ref().map_or(0, |current| current.rev);
+		let data = match current {
+			Some(current) if current.rev != rev => match resolve(current, data) {
+				Some(resolved) => resolved,
+				None => {
+					return Err(Error::Conflict {
+						current: Some(current_rev),
+					})
+				}
+			},
+			None if rev != 0 => return Err(Error::Conflict { current: None }),
+			_ => data,
+		};
+		let saved = Revisioned {
+			rev: current_rev + 1,
+			data,
+		};
+		let key = self.put_at_keyed(&saved, key).await?;
+		Ok((key, saved))
+	}
+}
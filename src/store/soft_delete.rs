@@ -0,0 +1,149 @@
+use super::{Store, TransactionStore};
+use crate::{ChangeKind, Error, IndexedKey, KeyRange, ReadWrite, Timestamp};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+/// The index of tombstones by when their record was deleted, which is how a sync layer finds deletions it has not seen.
+/// It is declared with [`StoreBuilder::deleted_at_index`](crate::StoreBuilder::deleted_at_index).
+pub const DELETED_AT_INDEX: &str = "deleted_at";
+
+/// A record which can be [soft deleted](SoftDeleteStore::delete) by marking it with when it was deleted,
+/// so a sync layer can find and propagate deletions before the tombstones are [compacted](SoftDeleteStore::compact_tombstones).
+/// The data of a tombstone is kept, so that key paths within it still resolve.
+/// Records which are not deleted are left out of the index on `deleted_at`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SoftDeleted<V> {
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub deleted_at: Option<Timestamp>,
+	pub data: V,
+}
+
+impl<V> SoftDeleted<V> {
+	/// A record which has not been deleted.
+	pub fn new(data: V) -> Self {
+		Self { deleted_at: None, data }
+	}
+
+	pub fn is_deleted(&self) -> bool {
+		self.deleted_at.is_some()
+	}
+}
+
+impl<V> Store<SoftDeleted<V>> {
+	/// A handle to this store whose deletes leave tombstones, and whose reads skip them. See [`SoftDeleteStore`].
+	pub fn with_tombstones(&self) -> SoftDeleteStore<V> {
+		SoftDeleteStore { store: self.clone() }
+	}
+}
+
+/// A store of [`SoftDeleted`] records whose [`delete`](SoftDeleteStore::delete) replaces a record with a tombstone,
+/// and whose reads treat tombstones as absent. Created by [`Store::with_tombstones`];
+/// the tombstones themselves are read through [`SoftDeleteStore::deleted_since`] or the underlying [`Store`].
+/// ```ignore
+/// static TASKS: StoreDescriptor<SoftDeleted<Task>> = StoreDescriptor::new("tasks").key_path(&["data.id"]);
+///
+/// let tasks = client.store(&TASKS).with_tombstones();
+/// tasks.put(task).await?;
+/// tasks.delete("write-docs").await?;
+/// assert_eq!(tasks.get("write-docs").await?, None);
+/// let deleted = tasks.deleted_since(last_sync).await?;
+/// tasks.compact_tombstones(last_sync).await?;
+/// ```
+pub struct SoftDeleteStore<V> {
+	store: Store<SoftDeleted<V>>,
+}
+
+impl<V> Clone for SoftDeleteStore<V> {
+	fn clone(&self) -> Self {
+		Self {
+			store: self.store.clone(),
+		}
+	}
+}
+
+impl<V> SoftDeleteStore<V> {
+	/// The underlying store, for reading and writing tombstones directly.
+	pub fn store(&self) -> &Store<SoftDeleted<V>> {
+		&self.store
+	}
+}
+
+impl<V: Serialize + DeserializeOwned> SoftDeleteStore<V> {
+	/// Puts `data` as a record which is not deleted, replacing any tombstone at its key.
+	pub async fn put(&self, data: V) -> Result<(), Error> {
+		self.store.put(&SoftDeleted::new(data)).await
+	}
+
+	/// Puts `data` at `key` (in a store with out-of-line keys) as a record which is not deleted.
+	pub async fn put_with_key<K: IndexedKey + ?Sized>(&self, data: V, key: &K) -> Result<(), Error> {
+		self.store.put_with_key(&SoftDeleted::new(data), key).await
+	}
+
+	/// Reads the record at `key`, unless it has been deleted.
+	pub async fn get<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<Option<V>, Error> {
+		let record = self.store.get_if(key, |record| !record.is_deleted()).await?;
+		Ok(record.map(|record| record.data))
+	}
+
+	/// Reads every record which has not been deleted.
+	pub async fn get_all(&self) -> Result<Vec<V>, Error> {
+		let records = self.store.get_all_if(|record| !record.is_deleted()).await?;
+		Ok(records.into_iter().map(|record| record.data).collect())
+	}
+
+	/// Replaces the record at `key` with a tombstone, returning false if there was no record or it was already deleted.
+	/// The deletion is broadcast as a delete, since the record is gone for every reader of this handle.
+	pub async fn delete<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<bool, Error> {
+		let store = self.store.in_transaction::<ReadWrite>()?;
+		let deleted = store.soft_delete_keyed(crate::key_range::to_key(key)?).await;
+		let Some(key) = store.finish(deleted).await? else {
+			return Ok(false);
+		};
+		self.store.changed(key, ChangeKind::Delete);
+		Ok(true)
+	}
+
+	/// Reads every tombstone of a record deleted after `after`, oldest deletion first.
+	pub async fn deleted_since(&self, after: Timestamp) -> Result<Vec<SoftDeleted<V>>, Error> {
+		self.store.query().index(DELETED_AT_INDEX).above(after).collect().await
+	}
+
+	/// Removes every tombstone of a record deleted before `before`, returning how many were removed.
+	/// Removing a tombstone is not broadcast, since its record was already broadcast as deleted.
+	pub async fn compact_tombstones(&self, before: Timestamp) -> Result<usize, Error> {
+		let store = self.store.in_transaction::<ReadWrite>()?;
+		let compacted = store.compact_tombstones_keyed(before).await;
+		Ok(store.finish(compacted).await?.len())
+	}
+}
+
+impl<V: Serialize + DeserializeOwned> TransactionStore<SoftDeleted<V>, ReadWrite> {
+	/// Replaces the record at `key` with a tombstone. See [`SoftDeleteStore::delete`].
+	pub async fn soft_delete<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<bool, Error> {
+		Ok(self.soft_delete_keyed(crate::key_range::to_key(key)?).await?.is_some())
+	}
+
+	/// Removes every tombstone of a record deleted before `before`. See [`SoftDeleteStore::compact_tombstones`].
+	pub async fn compact_tombstones(&self, before: Timestamp) -> Result<usize, Error> {
+		Ok(self.compact_tombstones_keyed(before).await?.len())
+	}
+
+	/// Marks the record at `key` as deleted, returning its key if it was not deleted already.
+	async fn soft_delete_keyed(&self, key: JsValue) -> Result<Option<JsValue>, Error> {
+		let Some(js_value) = self.store.get(idb::Query::Key(key.clone()))?.await? else {
+			return Ok(None);
+		};
+		let mut record = serde_wasm_bindgen::from_value::<SoftDeleted<V>>(js_value)?;
+		if record.is_deleted() {
+			return Ok(None);
+		}
+		record.deleted_at = Some(Timestamp::now());
+		Ok(Some(self.put_at_keyed(&record, key).await?))
+	}
+
+	/// Deletes every tombstone from before `before`, returning their keys.
+	async fn compact_tombstones_keyed(&self, before: Timestamp) -> Result<Vec<JsValue>, Error> {
+		let range = KeyRange::upper_bound(before).upper_open().to_query()?;
+		self.delete_indexed_keyed(DELETED_AT_INDEX, range).await
+	}
+}
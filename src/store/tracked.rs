@@ -0,0 +1,67 @@
+use super::{Store, TransactionStore};
+use crate::{ChangeKind, Error, IndexedKey, ReadWrite, Timestamp};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+
+/// The index which [`Store::updated_since`] reads, from [`StoreBuilder::updated_at_index`](crate::StoreBuilder::updated_at_index).
+pub const UPDATED_AT_INDEX: &str = "updated_at";
+
+/// A record stored with when it was first created and last updated, which [saving](Store::track) maintains.
+/// A store of tracked records is declared with a `StoreDescriptor<Tracked<V>>`, and its key path is within `data`.
+/// The timestamps are stored as dates, so an index on `updated_at` sorts records by when they were updated.
+/// ```ignore
+/// static NOTES: StoreDescriptor<Tracked<Note>> = StoreDescriptor::new("notes").key_path(&["data.id"]);
+///
+/// let notes = client.store(&NOTES);
+/// let saved = notes.track("first", note).await?;
+/// let changed = notes.updated_since(last_sync).await?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tracked<V> {
+	pub created_at: Timestamp,
+	pub updated_at: Timestamp,
+	pub data: V,
+}
+
+impl<V: Serialize + DeserializeOwned> Store<Tracked<V>> {
+	/// Saves `data` at `key`, keeping the creation time of the record it replaces and updating it now.
+	/// In a store with a key path, `data` must hold `key`, otherwise this fails with [`Error::KeyMismatch`].
+	pub async fn track<K: IndexedKey + ?Sized>(&self, key: &K, data: V) -> Result<Tracked<V>, Error> {
+		let store = self.in_transaction::<ReadWrite>()?;
+		let tracked = store.track_keyed(crate::key_range::to_key(key)?, data).await;
+		let (key, saved) = store.finish(tracked).await?;
+		self.changed(key, ChangeKind::Put);
+		Ok(saved)
+	}
+
+	/// Reads every record which was updated after `after`, oldest update first.
+	pub async fn updated_since(&self, after: Timestamp) -> Result<Vec<Tracked<V>>, Error> {
+		self.query().index(UPDATED_AT_INDEX).above(after).collect().await
+	}
+}
+
+impl<V: Serialize + DeserializeOwned> TransactionStore<Tracked<V>, ReadWrite> {
+	/// Saves `data` at `key`, keeping the creation time of the record it replaces. See [`Store::track`].
+	pub async fn track<K: IndexedKey + ?Sized>(&self, key: &K, data: V) -> Result<Tracked<V>, Error> {
+		let (_, saved) = self.track_keyed(crate::key_range::to_key(key)?, data).await?;
+		Ok(saved)
+	}
+
+	/// Puts the record at `key` with updated timestamps, returning its key.
+	async fn track_keyed(&self, key: JsValue, data: V) -> Result<(JsValue, Tracked<V>), Error> {
+		let created_at = match self.store.get(idb::Query::Key(key.clone()))?.await? {
+			Some(js_value) => {
+				Some(serde_wasm_bindgen::from_value::<Tracked<serde::de::IgnoredAny>>(js_value)?.created_at)
+			}
+			None => None,
+		};
+		let now = Timestamp::now();
+		let saved = Tracked {
+			created_at: created_at.unwrap_or(now),
+			updated_at: now,
+			data,
+		};
+		let key = self.put_at_keyed(&saved, key).await?;
+		Ok((key, saved))
+	}
+}
@@ -0,0 +1,206 @@
+use super::Store;
+use crate::{Error, IndexedKey, TransactionMode};
+use serde::{de::DeserializeOwned, Serialize};
+use std::rc::Rc;
+use wasm_bindgen::JsValue;
+
+/// Upgrades the JSON of a record from one schema to the next.
+type Step = Rc<dyn Fn(serde_json::Value) -> Result<serde_json::Value, String>>;
+
+/// The schema of a record type `T` and the steps which upgrade records written with older schemas,
+/// for evolving a record type without bumping the version of the database.
+/// ```ignore
+/// let upgrades = Upgrades::<Settings>::new(2)
+///   .step(0, |mut data| { data["theme"] = "light".into(); Ok(data) })
+///   .step(1, |mut data| { data["zoom"] = 1.0.into(); Ok(data) });
+/// let settings = client.store(&SETTINGS).versioned(upgrades);
+/// ```
+pub struct Upgrades<T> {
+	current: u16,
+	steps: Vec<(u16, Step)>,
+	marker: std::marker::PhantomData<fn() -> T>,
+}
+
+clone_handle!(Upgrades { current, steps, marker });
+
+impl<T> Upgrades<T> {
+	/// Records are written with the `current` schema.
+	pub fn new(current: u16) -> Self {
+		Self {
+			current,
+			steps: Vec::new(),
+			marker: std::marker::PhantomData,
+		}
+	}
+
+	/// Registers how the JSON of a record with schema `from` is upgraded to schema `from + 1`.
+	pub fn step<F>(mut self, from: u16, upgrade: F) -> Self
+	where
+		F: Fn(serde_json::Value) -> Result<serde_json::Value, String> + 'static,
+	{
+		self.steps.retain(|(schema, _)| *schema != from);
+		self.steps.push((from, Rc::new(upgrade)));
+		self
+	}
+
+	pub fn current(&self) -> u16 {
+		self.current
+	}
+
+	/// Runs every step from `schema` up to the current schema.
+	fn upgrade(&self, mut schema: u16, mut data: serde_json::Value) -> Result<serde_json::Value, Error> {
+		while schema < self.current {
+			let Some((_, step)) = self.steps.iter().find(|(from, _)| *from == schema) else {
+				return Err(Error::Serialization(format!("no upgrade from schema {schema}")));
+			};
+			data = step(data).map_err(Error::Serialization)?;
+			schema += 1;
+		}
+		Ok(data)
+	}
+}
+
+/// The field which marks a record as an envelope, so records whose own data has a `schema` field aren't mistaken for one.
+const MARKER: &str = "$versioned";
+
+#[derive(Serialize)]
+struct Envelope<'a, T> {
+	#[serde(rename = "$versioned")]
+	marker: bool,
+	schema: u16,
+	data: &'a T,
+}
+
+impl<'a, T> Envelope<'a, T> {
+	fn new(schema: u16, data: &'a T) -> Self {
+		Self {
+			marker: true,
+			schema,
+			data,
+		}
+	}
+}
+
+/// A record which was upgraded as it was read, and is written back once the read has finished.
+struct Upgraded {
+	key: JsValue,
+	original: JsValue,
+	upgraded: JsValue,
+}
+
+/// A [`Store`] whose records are stored in an envelope `{ $versioned, schema, data }`, created by [`Store::versioned`].
+/// Records with an older schema are upgraded by the [`Upgrades`] as they are read,
+/// and the upgraded records are written back in one transaction before the read returns,
+/// unless they were replaced in the meantime.
+/// Records without an envelope are read as schema 0, so an existing store can start using envelopes without migrating it.
+/// Older records are upgraded as JSON, so they shouldn't hold values which only JS can represent, such as dates.
+/// Enveloped records are [wrapped records](Store#wrapped-records).
+pub struct VersionedStore<T> {
+	store: Store<T>,
+	upgrades: Upgrades<T>,
+}
+
+clone_handle!(VersionedStore { store, upgrades });
+
+impl<T> VersionedStore<T> {
+	pub(crate) fn new(store: Store<T>, upgrades: Upgrades<T>) -> Self {
+		Self { store, upgrades }
+	}
+}
+
+impl<T: Serialize + DeserializeOwned> VersionedStore<T> {
+	/// Puts the record at `key` with the current schema.
+	pub async fn put<K: IndexedKey + ?Sized>(&self, key: &K, value: &T) -> Result<(), Error> {
+		let envelope = Envelope::new(self.upgrades.current, value);
+		let value = self.store.descriptor.serializer_options().serialize(&envelope)?;
+		self.store.write_raw(&value, Some(crate::key_range::to_key(key)?)).await
+	}
+
+	/// Reads the record at `key`, upgrading it if it was written with an older schema.
+	pub async fn get<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<Option<T>, Error> {
+		let key = crate::key_range::to_key(key)?;
+		let store = self.store.object_store(TransactionMode::ReadOnly)?;
+		let Some(value) = store.get(idb::Query::Key(key.clone()))?.await? else {
+			return Ok(None);
+		};
+		let (record, upgraded) = self.read(key, value)?;
+		self.write_back(upgraded.into_iter().collect()).await?;
+		Ok(Some(record))
+	}
+
+	/// Reads every record, upgrading any which were written with an older schema.
+	pub async fn get_all(&self) -> Result<Vec<T>, Error> {
+		let (keys, js_values) = self.store.read_batch(None, None).await?;
+		let mut records = Vec::with_capacity(keys.len());
+		let mut upgraded = Vec::new();
+		for (key, value) in keys.into_iter().zip(js_values) {
+			let (record, upgrade) = self.read(key, value)?;
+			records.push(record);
+			upgraded.extend(upgrade);
+		}
+		self.write_back(upgraded).await?;
+		Ok(records)
+	}
+
+	/// Reads the record in `value`, along with its upgraded form if it was written with an older schema.
+	fn read(&self, key: JsValue, value: JsValue) -> Result<(T, Option<Upgraded>), Error> {
+		let marked = js_sys::Reflect::get(&value, &MARKER.into()).unwrap_or_default();
+		let (schema, data) = match marked.as_bool() {
+			Some(true) => {
+				let schema = js_sys::Reflect::get(&value, &"schema".into()).unwrap_or_default();
+				let data = js_sys::Reflect::get(&value, &"data".into()).unwrap_or_default();
+				(parse_schema(&schema)?, data)
+			}
+			_ => (0, value.clone()),
+		};
+		if schema == self.upgrades.current {
+			return Ok((serde_wasm_bindgen::from_value(data)?, None));
+		}
+		if schema > self.upgrades.current {
+			return Err(Error::Serialization(format!(
+				"the record has schema {schema}, which is newer than {}",
+				self.upgrades.current
+			)));
+		}
+
+		let data = serde_wasm_bindgen::from_value::<serde_json::Value>(data)?;
+		let data = self.upgrades.upgrade(schema, data)?;
+		let record = serde_json::from_value::<T>(data).map_err(|err| Error::Serialization(err.to_string()))?;
+		let envelope = Envelope::new(self.upgrades.current, &record);
+		let upgraded = self.store.descriptor.serializer_options().serialize(&envelope)?;
+		let upgraded = Upgraded {
+			key,
+			original: value,
+			upgraded,
+		};
+		Ok((record, Some(upgraded)))
+	}
+
+	/// Replaces each record with its upgraded form in one transaction, if it is still the record which was upgraded.
+	/// The records' values are unchanged, so the writes are not broadcast.
+	async fn write_back(&self, upgraded: Vec<Upgraded>) -> Result<(), Error> {
+		if upgraded.is_empty() {
+			return Ok(());
+		}
+		let store = self.store.object_store(TransactionMode::ReadWrite)?;
+		for record in upgraded {
+			let Some(current) = store.get(idb::Query::Key(record.key.clone()))?.await? else {
+				continue;
+			};
+			if super::recode::same_value(&current, &record.original) {
+				store.put(&record.upgraded, Some(&record.key))?.await?;
+			}
+		}
+		crate::scoped_transaction::commit(store.transaction()).await
+	}
+}
+
+/// Reads the schema of an envelope, which must be an integer that fits in a `u16`.
+fn parse_schema(schema: &JsValue) -> Result<u16, Error> {
+	match schema.as_f64() {
+		Some(value) if value.fract() == 0.0 && (0.0..=f64::from(u16::MAX)).contains(&value) => Ok(value as u16),
+		_ => Err(Error::Serialization(format!(
+			"the record has an invalid schema {schema:?}"
+		))),
+	}
+}
@@ -0,0 +1,70 @@
+use super::{Error, IndexedKey, Store, TransactionMode};
+use std::future::IntoFuture;
+use wasm_bindgen::{JsCast, JsValue};
+
+/// Marks a `wasm_bindgen` type which IndexedDB stores with the structured clone algorithm,
+/// so a [`Store`] of it reads and writes the values as they are instead of converting them through serde.
+/// ```ignore
+/// static THUMBNAILS: StoreDescriptor<web_sys::ImageBitmap> = StoreDescriptor::new("thumbnails");
+/// let thumbnails = client.store(&THUMBNAILS);
+/// thumbnails.put_cloned_with_key(&bitmap, &"avatar").await?;
+/// let bitmap = thumbnails.get_cloned(&"avatar").await?;
+/// ```
+pub trait StructuredClone: JsCast {}
+
+impl StructuredClone for JsValue {}
+impl StructuredClone for js_sys::Object {}
+impl StructuredClone for js_sys::Array {}
+impl StructuredClone for js_sys::Map {}
+impl StructuredClone for js_sys::Set {}
+impl StructuredClone for js_sys::Date {}
+impl StructuredClone for js_sys::RegExp {}
+impl StructuredClone for js_sys::JsString {}
+impl StructuredClone for js_sys::ArrayBuffer {}
+impl StructuredClone for js_sys::DataView {}
+impl StructuredClone for js_sys::Int8Array {}
+impl StructuredClone for js_sys::Uint8Array {}
+impl StructuredClone for js_sys::Uint8ClampedArray {}
+impl StructuredClone for js_sys::Int16Array {}
+impl StructuredClone for js_sys::Uint16Array {}
+impl StructuredClone for js_sys::Int32Array {}
+impl StructuredClone for js_sys::Uint32Array {}
+impl StructuredClone for js_sys::Float32Array {}
+impl StructuredClone for js_sys::Float64Array {}
+impl StructuredClone for js_sys::BigInt64Array {}
+impl StructuredClone for js_sys::BigUint64Array {}
+impl StructuredClone for web_sys::Blob {}
+impl StructuredClone for web_sys::File {}
+impl StructuredClone for web_sys::ImageBitmap {}
+impl StructuredClone for web_sys::ImageData {}
+
+impl<T: StructuredClone> Store<T> {
+	/// Reads the value at `key` without deserializing it.
+	/// Fails with [`Error::Serialization`] if the stored value is not a `T`.
+	pub async fn get_cloned<K: IndexedKey + ?Sized>(&self, key: &K) -> Result<Option<T>, Error> {
+		self.get_raw(key).await?.map(cast).transpose()
+	}
+
+	/// Reads every value in the store without deserializing them.
+	pub async fn get_all_cloned(&self) -> Result<Vec<T>, Error> {
+		let store = self.object_store(TransactionMode::ReadOnly)?;
+		let values = store.get_all(None, None)?.into_future().await?;
+		values.into_iter().map(cast).collect()
+	}
+
+	/// Inserts or replaces the value, reading its key with the store's key path.
+	pub async fn put_cloned(&self, value: &T) -> Result<(), Error> {
+		self.put_raw(value.as_ref()).await
+	}
+
+	/// Inserts or replaces the value at `key`, for stores without a key path.
+	pub async fn put_cloned_with_key<K: IndexedKey + ?Sized>(&self, value: &T, key: &K) -> Result<(), Error> {
+		self.put_raw_with_key(value.as_ref(), key).await
+	}
+}
+
+fn cast<T: JsCast>(value: JsValue) -> Result<T, Error> {
+	value
+		.dyn_into::<T>()
+		.map_err(|_| Error::Serialization(format!("the value is not a {}", std::any::type_name::<T>())))
+}
@@ -0,0 +1,75 @@
+use super::{Client, Error, MissingVersion, Schema, TransactionMode};
+use serde::Serialize;
+use std::{future::IntoFuture, marker::PhantomData};
+use wasm_bindgen::JsValue;
+
+/// Tests the migrations of the schema `V`: creates a fresh database at an older version,
+/// fills it with fixture records, then upgrades it to the latest version so the result can be asserted on.
+/// ```ignore
+/// #[wasm_bindgen_test]
+/// async fn migrates_v1_users() {
+///   let client = MigrationHarness::<Version>::new("test-users", 1)
+///     .fixture("users", [UserV1 { id: "alice".into(), name: "Alice".into() }])
+///     .run()
+///     .await
+///     .unwrap();
+///   let user: Option<User> = client.store(&USERS).get("alice").await.unwrap();
+///   assert_eq!(user.map(|user| user.display_name), Some("Alice".into()));
+/// }
+/// ```
+pub struct MigrationHarness<V> {
+	name: String,
+	from_version: u32,
+	fixtures: Vec<(String, Result<Vec<JsValue>, Error>)>,
+	marker: PhantomData<fn() -> V>,
+}
+
+impl<V> MigrationHarness<V>
+where
+	V: 'static + Schema + TryFrom<u32, Error = MissingVersion>,
+{
+	/// Any existing database called `name` is deleted when the harness runs.
+	pub fn new(name: impl Into<String>, from_version: u32) -> Self {
+		Self {
+			name: name.into(),
+			from_version,
+			fixtures: Vec::new(),
+			marker: PhantomData,
+		}
+	}
+
+	/// Adds records to `store` before the database is upgraded. The store must have a key path at `from_version`.
+	pub fn fixture<T: Serialize>(mut self, store: impl Into<String>, records: impl IntoIterator<Item = T>) -> Self {
+		let values = records.into_iter().map(|record| Ok(crate::record::to_value(&record)?));
+		self.fixtures.push((store.into(), values.collect()));
+		self
+	}
+
+	/// Creates the database at the starting version, adds the fixtures, and upgrades it to [`Schema::latest`].
+	pub async fn run(self) -> Result<Client, Error> {
+		Client::delete_database(&self.name).await?;
+
+		let client = Client::builder(self.name.as_str())
+			.open_at::<V>(self.from_version)
+			.await?;
+		if !self.fixtures.is_empty() {
+			let store_names = self
+				.fixtures
+				.iter()
+				.map(|(store, _)| store.as_str())
+				.collect::<Vec<_>>();
+			let transaction = client.transaction(&store_names, TransactionMode::ReadWrite)?;
+			for (store, values) in self.fixtures {
+				let store = transaction.object_store(&store)?;
+				for value in values? {
+					store.put(&value, None)?.into_future().await?;
+				}
+			}
+			transaction.commit().await?;
+		}
+		// the old connection must be closed, otherwise it would block the upgrade
+		drop(client);
+
+		Client::open::<V>(&self.name).await
+	}
+}
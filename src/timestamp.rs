@@ -0,0 +1,76 @@
+use super::{Error, IndexedKey};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use wasm_bindgen::JsValue;
+
+#[cfg(feature = "chrono")]
+pub mod chrono_date;
+#[cfg(feature = "time")]
+pub mod time_date;
+
+/// A point in time, stored as a native JS `Date`, both as a key and as a field of a record.
+/// IndexedDB sorts date keys by time, and dates sort after every number and before every string,
+/// unlike the numbers or strings which a date would otherwise have to be stored as.
+///
+/// The `Date` is only preserved by `serde_wasm_bindgen`,
+/// so records with timestamps cannot be [`Encoded`](crate::Encoded).
+/// ```ignore
+/// #[derive(Serialize, Deserialize)]
+/// struct Message {
+///   sent_at: Timestamp,
+///   text: String,
+/// }
+/// let today = store.query().index("sent_at").at_least(Timestamp::from_millis(midnight)).collect().await?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Timestamp(f64);
+
+impl Timestamp {
+	/// The time in milliseconds since the Unix epoch.
+	pub fn from_millis(millis: f64) -> Self {
+		Self(millis)
+	}
+
+	pub fn now() -> Self {
+		Self(js_sys::Date::now())
+	}
+
+	pub fn as_millis(&self) -> f64 {
+		self.0
+	}
+
+	pub fn to_date(&self) -> js_sys::Date {
+		js_sys::Date::new(&self.0.into())
+	}
+}
+
+impl From<&js_sys::Date> for Timestamp {
+	fn from(date: &js_sys::Date) -> Self {
+		Self(date.get_time())
+	}
+}
+
+impl From<Timestamp> for js_sys::Date {
+	fn from(timestamp: Timestamp) -> Self {
+		timestamp.to_date()
+	}
+}
+
+impl Serialize for Timestamp {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serde_wasm_bindgen::preserve::serialize(&self.to_date(), serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let date = serde_wasm_bindgen::preserve::deserialize::<D, js_sys::Date>(deserializer)?;
+		Ok(Self::from(&date))
+	}
+}
+
+impl crate::indexed_key::sealed::Sealed for Timestamp {}
+impl IndexedKey for Timestamp {
+	fn to_js_key(&self) -> Result<JsValue, Error> {
+		Ok(self.to_date().into())
+	}
+}
@@ -0,0 +1,48 @@
+//! Stores `chrono::DateTime<Utc>` as a native JS `Date`, with the `chrono` feature.
+//! Use it on fields with `#[serde(with = "database::chrono_date")]`, and pass date times directly as keys.
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Message {
+//!   #[serde(with = "database::chrono_date")]
+//!   sent_at: DateTime<Utc>,
+//! }
+//! ```
+//! Dates have millisecond precision, so finer precision is rounded down (towards the past) when a date time is stored.
+use super::Timestamp;
+use crate::{Error, IndexedKey};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use wasm_bindgen::JsValue;
+
+pub fn serialize<S: Serializer>(date_time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+	Timestamp::from(*date_time).serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+	let timestamp = Timestamp::deserialize(deserializer)?;
+	DateTime::try_from(timestamp).map_err(serde::de::Error::custom)
+}
+
+impl From<DateTime<Utc>> for Timestamp {
+	fn from(date_time: DateTime<Utc>) -> Self {
+		Self::from_millis(date_time.timestamp_millis() as f64)
+	}
+}
+
+impl TryFrom<Timestamp> for DateTime<Utc> {
+	type Error = Error;
+
+	fn try_from(timestamp: Timestamp) -> Result<Self, Error> {
+		let millis = timestamp.as_millis();
+		DateTime::from_timestamp_millis(millis as i64)
+			.filter(|_| millis.is_finite())
+			.ok_or_else(|| Error::Serialization(format!("the date {millis}ms is out of range")))
+	}
+}
+
+impl crate::indexed_key::sealed::Sealed for DateTime<Utc> {}
+impl IndexedKey for DateTime<Utc> {
+	fn to_js_key(&self) -> Result<JsValue, Error> {
+		Timestamp::from(*self).to_js_key()
+	}
+}
@@ -0,0 +1,51 @@
+//! Stores `time::OffsetDateTime` as a native JS `Date`, with the `time` feature.
+//! Use it on fields with `#[serde(with = "database::time_date")]`, and pass date times directly as keys.
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Message {
+//!   #[serde(with = "database::time_date")]
+//!   sent_at: OffsetDateTime,
+//! }
+//! ```
+//! Dates have millisecond precision and no offset, so date times are read back in UTC
+//! and finer precision is rounded down (towards the past) when a date time is stored.
+use super::Timestamp;
+use crate::{Error, IndexedKey};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use time::OffsetDateTime;
+use wasm_bindgen::JsValue;
+
+pub fn serialize<S: Serializer>(date_time: &OffsetDateTime, serializer: S) -> Result<S::Ok, S::Error> {
+	Timestamp::from(*date_time).serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<OffsetDateTime, D::Error> {
+	let timestamp = Timestamp::deserialize(deserializer)?;
+	OffsetDateTime::try_from(timestamp).map_err(serde::de::Error::custom)
+}
+
+impl From<OffsetDateTime> for Timestamp {
+	fn from(date_time: OffsetDateTime) -> Self {
+		Self::from_millis(date_time.unix_timestamp_nanos().div_euclid(1_000_000) as f64)
+	}
+}
+
+impl TryFrom<Timestamp> for OffsetDateTime {
+	type Error = Error;
+
+	fn try_from(timestamp: Timestamp) -> Result<Self, Error> {
+		let millis = timestamp.as_millis();
+		let out_of_range = || Error::Serialization(format!("the date {millis}ms is out of range"));
+		if !millis.is_finite() {
+			return Err(out_of_range());
+		}
+		OffsetDateTime::from_unix_timestamp_nanos(millis as i128 * 1_000_000).map_err(|_| out_of_range())
+	}
+}
+
+impl crate::indexed_key::sealed::Sealed for OffsetDateTime {}
+impl IndexedKey for OffsetDateTime {
+	fn to_js_key(&self) -> Result<JsValue, Error> {
+		Timestamp::from(*self).to_js_key()
+	}
+}
@@ -0,0 +1,55 @@
+#![cfg(target_arch = "wasm32")]
+
+use database::{Client, DatabaseRegistry, Error, SchemaBuilder};
+use std::{cell::Cell, rc::Rc};
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+async fn on_created_errors_fail_the_open() {
+	let name = "client-seed-error";
+	Client::delete_database(name).await.unwrap();
+	let layout = SchemaBuilder::new()
+		.store("items", |store| store.key_path("id"))
+		.build();
+	let result = Client::builder(name)
+		.on_created(|_| async { Err(Error::Internal("seed failed".into())) })
+		.open_layout(1, layout.clone())
+		.await;
+	assert!(matches!(result, Err(Error::Internal(message)) if message == "seed failed"));
+
+	// the failed seed aborted the upgrade, so the database is created (and seeded) again
+	let seeded = Rc::new(Cell::new(false));
+	let client = Client::builder(name)
+		.on_created({
+			let seeded = seeded.clone();
+			move |_| {
+				seeded.set(true);
+				async { Ok(()) }
+			}
+		})
+		.open_layout(1, layout)
+		.await
+		.unwrap();
+	assert!(seeded.get());
+	drop(client);
+}
+
+#[wasm_bindgen_test]
+async fn concurrent_registry_clients_open_the_database_once() {
+	let opened = Rc::new(Cell::new(0));
+	let registry = DatabaseRegistry::new().register_with("client-registry", {
+		let opened = opened.clone();
+		move |builder| {
+			opened.set(opened.get() + 1);
+			let layout = SchemaBuilder::new().store("items", |store| store).build();
+			builder.open_layout(1, layout)
+		}
+	});
+	let (first, second) =
+		futures::future::join(registry.client("client-registry"), registry.client("client-registry")).await;
+	first.unwrap();
+	second.unwrap();
+	assert_eq!(opened.get(), 1);
+}
@@ -0,0 +1,36 @@
+#![allow(dead_code)]
+
+use database::{Client, Record, SchemaBuilder, StoreDescriptor};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Item {
+	pub id: u32,
+	pub value: u32,
+}
+
+impl Record for Item {
+	fn store_id() -> &'static str {
+		"items"
+	}
+}
+
+pub static ITEMS: StoreDescriptor<Item> = StoreDescriptor::new("items").key_path(&["id"]);
+
+/// The name of the out-of-line keyed store, for the descriptors of tests which wrap its records.
+/// It has the indexes of expiring and soft deleted records.
+pub const BLOBS: &str = "blobs";
+
+/// Opens a fresh database called `name`, with an `items` store holding `count` items (indexed by `value`)
+/// and an empty `blobs` store with out-of-line keys.
+pub async fn open_items(name: &str, count: u32) -> Client {
+	Client::delete_database(name).await.unwrap();
+	let layout = SchemaBuilder::new()
+		.store("items", |store| store.key_path("id").index("value", "value"))
+		.store(BLOBS, |store| store.expires_at_index().deleted_at_index())
+		.build();
+	let client = Client::open_layout(name, 1, layout).await.unwrap();
+	let items = (0..count).map(|id| Item { id, value: id }).collect::<Vec<_>>();
+	client.store(&ITEMS).put_all(&items).await.unwrap();
+	client
+}
@@ -0,0 +1,184 @@
+#![cfg(target_arch = "wasm32")]
+
+mod common;
+
+use common::{open_items, Item, BLOBS, ITEMS};
+use database::StoreDescriptor;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+async fn for_each_mut_updates_every_record() {
+	let client = open_items("cursor-for-each-mut", 5).await;
+	let transaction = client.write_transaction(&[&ITEMS]).unwrap();
+	let mut cursor = transaction.store(&ITEMS).unwrap().cursor().await.unwrap();
+	cursor
+		.for_each_mut(|entry| {
+			entry.value_mut().value += 10;
+		})
+		.await
+		.unwrap();
+	transaction.commit().await.unwrap();
+
+	let values = client.store(&ITEMS).get_all().await.unwrap();
+	assert_eq!(
+		values.iter().map(|item| item.value).collect::<Vec<_>>(),
+		vec![10, 11, 12, 13, 14]
+	);
+}
+
+#[wasm_bindgen_test]
+async fn for_each_mut_after_stream_waits_for_the_next_entry() {
+	let client = open_items("cursor-for-each-mut-stream", 5).await;
+	let transaction = client.write_transaction(&[&ITEMS]).unwrap();
+	let cursor = transaction.store(&ITEMS).unwrap().cursor().await.unwrap();
+	let mut stream = cursor.into_try_stream();
+	let first = stream.next().await.unwrap().unwrap();
+	assert_eq!(first, Item { id: 0, value: 0 });
+	let mut cursor = stream.into_inner();
+	cursor
+		.for_each_mut(|entry| {
+			entry.value_mut().value += 10;
+		})
+		.await
+		.unwrap();
+	transaction.commit().await.unwrap();
+
+	let values = client.store(&ITEMS).get_all().await.unwrap();
+	assert_eq!(
+		values.iter().map(|item| item.value).collect::<Vec<_>>(),
+		vec![0, 11, 12, 13, 14]
+	);
+}
+
+#[wasm_bindgen_test]
+async fn delete_current_and_advance_after_stream() {
+	let client = open_items("cursor-delete-and-advance", 3).await;
+	let transaction = client.write_transaction(&[&ITEMS]).unwrap();
+	let cursor = transaction.store(&ITEMS).unwrap().cursor().await.unwrap();
+	let mut stream = cursor.into_try_stream();
+	stream.next().await.unwrap().unwrap();
+	let mut cursor = stream.into_inner();
+	let next = cursor.delete_current_and_advance().await.unwrap();
+	assert_eq!(next, Some(Item { id: 2, value: 2 }));
+	assert_eq!(cursor.delete_current_and_advance().await.unwrap(), None);
+	assert!(cursor.delete_current_and_advance().await.is_err());
+	transaction.commit().await.unwrap();
+
+	let ids = client.store(&ITEMS).get_all().await.unwrap();
+	assert_eq!(ids, vec![Item { id: 0, value: 0 }]);
+}
+
+#[wasm_bindgen_test]
+async fn seek_moves_a_manually_iterated_cursor() {
+	let client = open_items("cursor-seek-manual", 6).await;
+	let transaction = client.read_transaction(&[&ITEMS]).unwrap();
+	let mut cursor = transaction.store(&ITEMS).unwrap().cursor().await.unwrap();
+	let mut visited = Vec::new();
+	while let Some(item) = cursor.value().unwrap() {
+		visited.push(item.id);
+		if item.id == 1 {
+			cursor.seek(&4u32).unwrap();
+		}
+		cursor.advance().await.unwrap();
+	}
+	assert_eq!(visited, vec![0, 1, 4, 5]);
+}
+
+#[wasm_bindgen_test]
+async fn seek_moves_a_streamed_cursor() {
+	let client = open_items("cursor-seek-stream", 6).await;
+	let transaction = client.read_transaction(&[&ITEMS]).unwrap();
+	let mut cursor = transaction.store(&ITEMS).unwrap().cursor().await.unwrap();
+	assert_eq!(cursor.next().await.map(|item| item.id), Some(0));
+	cursor.seek(&3u32).unwrap();
+	let rest = cursor.map(|item| item.id).collect::<Vec<_>>().await;
+	assert_eq!(rest, vec![3, 4, 5]);
+}
+
+#[wasm_bindgen_test]
+async fn take_limits_manual_iteration() {
+	let client = open_items("cursor-take-manual", 5).await;
+	let transaction = client.write_transaction(&[&ITEMS]).unwrap();
+	let cursor = transaction.store(&ITEMS).unwrap().cursor().await.unwrap();
+	let mut cursor = cursor.take(3);
+	assert_eq!(cursor.next().await.map(|item| item.id), Some(0));
+	cursor
+		.for_each_mut(|entry| {
+			entry.value_mut().value += 10;
+		})
+		.await
+		.unwrap();
+	assert_eq!(cursor.value().unwrap(), None);
+	transaction.commit().await.unwrap();
+
+	let values = client.store(&ITEMS).get_all().await.unwrap();
+	assert_eq!(
+		values.iter().map(|item| item.value).collect::<Vec<_>>(),
+		vec![0, 11, 12, 3, 4]
+	);
+}
+
+#[wasm_bindgen_test]
+async fn manual_iteration_rejects_filtered_cursors() {
+	let client = open_items("cursor-filter-manual", 3).await;
+	let transaction = client.write_transaction(&[&ITEMS]).unwrap();
+	let cursor = transaction.store(&ITEMS).unwrap().cursor().await.unwrap();
+	let mut cursor = cursor.filter_raw(|_| false);
+	assert!(cursor.value().is_err());
+	assert!(cursor.advance().await.is_err());
+	assert!(cursor.for_each_mut(|entry| entry.delete()).await.is_err());
+}
+
+static NULLABLE: StoreDescriptor<Option<u32>> = StoreDescriptor::new(BLOBS);
+
+#[wasm_bindgen_test]
+async fn null_records_do_not_end_the_cursor() {
+	let client = open_items("cursor-null-records", 0).await;
+	let store = client.store(&NULLABLE);
+	store.put_raw_with_key(&JsValue::NULL, &1u32).await.unwrap();
+	store.put_with_key(&Some(2), &2u32).await.unwrap();
+
+	let transaction = client.read_transaction(&[&NULLABLE]).unwrap();
+	let mut cursor = transaction.store(&NULLABLE).unwrap().cursor().await.unwrap();
+	assert_eq!(cursor.value().unwrap(), Some(None));
+	cursor.advance().await.unwrap();
+	assert_eq!(cursor.value().unwrap(), Some(Some(2)));
+	cursor.advance().await.unwrap();
+	assert_eq!(cursor.value().unwrap(), None);
+
+	let cursor = transaction.store(&NULLABLE).unwrap().cursor().await.unwrap();
+	assert_eq!(cursor.collect::<Vec<_>>().await, vec![None, Some(2)]);
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Note {
+	text: Option<String>,
+}
+
+static NOTES: StoreDescriptor<Note> = StoreDescriptor::new(BLOBS);
+
+#[wasm_bindgen_test]
+async fn updates_use_the_serializer_of_the_store() {
+	let client = open_items("cursor-update-serializer", 0).await;
+	let note = Note {
+		text: Some("hello".into()),
+	};
+	client.store(&NOTES).put_with_key(&note, &1u32).await.unwrap();
+
+	let transaction = client.write_transaction(&[&NOTES]).unwrap();
+	let mut cursor = transaction.store(&NOTES).unwrap().cursor().await.unwrap();
+	cursor
+		.for_each_mut(|entry| entry.value_mut().text = None)
+		.await
+		.unwrap();
+	transaction.commit().await.unwrap();
+
+	// the default options store `None` as null, where serde_wasm_bindgen's defaults would leave it undefined
+	let raw = client.store(&NOTES).get_raw(&1u32).await.unwrap().unwrap();
+	assert!(js_sys::Reflect::get(&raw, &"text".into()).unwrap().is_null());
+}
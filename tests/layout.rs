@@ -0,0 +1,194 @@
+use database::{
+	Client, Error, KeyPath, MigrationOp, MissingVersion, Schema, SchemaBuilder, SchemaLayout, SchemaMismatch,
+};
+
+fn declared() -> SchemaLayout {
+	SchemaBuilder::new()
+		.store("users", |store| store.key_path("id").unique_index("email", "email"))
+		.store("posts", |store| {
+			store.key_path("id").auto_increment().index("author", "author")
+		})
+		.build()
+}
+
+#[test]
+fn builder_sorts_stores_and_replaces_duplicates() {
+	let layout = SchemaBuilder::new()
+		.store("b", |store| store)
+		.store("a", |store| store.key_path("first"))
+		.store("a", |store| store.key_path("second").index("x", "x").index("x", "y"))
+		.build();
+	let names = layout
+		.stores
+		.iter()
+		.map(|store| store.name.as_str())
+		.collect::<Vec<_>>();
+	assert_eq!(names, ["a", "b"]);
+	let store = layout.store("a").unwrap();
+	assert_eq!(store.key_path, Some(KeyPath::new_single("second")));
+	assert_eq!(store.indexes.len(), 1);
+	assert_eq!(store.index("x").unwrap().key_path, Some(KeyPath::new_single("y")));
+}
+
+#[test]
+fn layout_round_trips_through_json() {
+	let layout = SchemaBuilder::new()
+		.store("entries", |store| {
+			store
+				.compound_key_path(&["owner", "id"])
+				.multi_entry_index("tags", "tags")
+		})
+		.build();
+	let json = layout.to_json(4).unwrap();
+	assert_eq!(SchemaLayout::from_json(&json).unwrap(), (4, layout));
+}
+
+#[test]
+fn matching_layouts_have_no_mismatches_or_operations() {
+	assert!(declared().mismatches(&declared()).is_empty());
+	assert!(declared().plan(&declared()).unwrap().is_empty());
+}
+
+#[test]
+fn mismatches_report_what_the_database_lacks() {
+	let actual = SchemaBuilder::new()
+		.store("users", |store| {
+			store.key_path("uid").index("email", "email").index("extra", "extra")
+		})
+		.store("other", |store| store)
+		.build();
+	let mismatches = declared().mismatches(&actual);
+	assert_eq!(
+		mismatches,
+		[
+			SchemaMismatch::MissingStore("posts".into()),
+			SchemaMismatch::StoreKeyPath {
+				store: "users".into(),
+				expected: Some(KeyPath::new_single("id")),
+				actual: Some(KeyPath::new_single("uid")),
+			},
+			SchemaMismatch::IndexUnique {
+				store: "users".into(),
+				index: "email".into(),
+				expected: true,
+			},
+		]
+	);
+}
+
+#[test]
+fn plan_creates_missing_and_deletes_undeclared() {
+	let existing = SchemaBuilder::new()
+		.store("users", |store| {
+			store.key_path("id").index("email", "email").index("name", "name")
+		})
+		.store("sessions", |store| store)
+		.build();
+	let declared = declared();
+	let operations = declared.plan(&existing).unwrap();
+	assert_eq!(
+		operations,
+		[
+			MigrationOp::DeleteStore("sessions".into()),
+			MigrationOp::CreateStore(declared.store("posts").unwrap().clone()),
+			MigrationOp::DeleteIndex {
+				store: "users".into(),
+				index: "email".into(),
+			},
+			MigrationOp::DeleteIndex {
+				store: "users".into(),
+				index: "name".into(),
+			},
+			MigrationOp::CreateIndex {
+				store: "users".into(),
+				index: declared.store("users").unwrap().index("email").unwrap().clone(),
+			},
+		]
+	);
+}
+
+#[test]
+fn plan_refuses_to_change_the_key_of_a_store() {
+	let existing = SchemaBuilder::new()
+		.store("users", |store| {
+			store.key_path("id").auto_increment().unique_index("email", "email")
+		})
+		.store("posts", |store| {
+			store.key_path("id").auto_increment().index("author", "author")
+		})
+		.build();
+	assert!(declared().plan(&existing).is_err());
+}
+
+enum Version {
+	Users,
+	Posts,
+	Backfill,
+}
+
+impl TryFrom<u32> for Version {
+	type Error = MissingVersion;
+
+	fn try_from(version: u32) -> Result<Self, MissingVersion> {
+		match version {
+			1 => Ok(Self::Users),
+			2 => Ok(Self::Posts),
+			3 => Ok(Self::Backfill),
+			_ => Err(MissingVersion(version)),
+		}
+	}
+}
+
+impl Schema for Version {
+	fn latest() -> u32 {
+		3
+	}
+
+	fn apply(&self, _database: &Client) -> Result<(), Error> {
+		Ok(())
+	}
+
+	fn layout(&self) -> Option<SchemaLayout> {
+		match self {
+			Self::Users => Some(
+				SchemaBuilder::new()
+					.store("users", |store| store.key_path("id"))
+					.build(),
+			),
+			Self::Posts => Some(declared()),
+			Self::Backfill => None,
+		}
+	}
+
+	fn operations(&self) -> Vec<MigrationOp> {
+		match self {
+			Self::Backfill => vec![MigrationOp::Custom("backfill authors".into())],
+			_ => Vec::new(),
+		}
+	}
+}
+
+#[test]
+fn schema_plan_diffs_the_layouts_of_consecutive_versions() {
+	let plan = Version::plan(0).unwrap();
+	let operations = plan
+		.iter()
+		.map(|version| version.operations.clone())
+		.collect::<Vec<_>>();
+	let users = Version::Users.layout().unwrap().store("users").unwrap().clone();
+	assert_eq!(
+		operations,
+		[
+			vec![MigrationOp::CreateStore(users)],
+			vec![
+				MigrationOp::CreateStore(declared().store("posts").unwrap().clone()),
+				MigrationOp::CreateIndex {
+					store: "users".into(),
+					index: declared().store("users").unwrap().index("email").unwrap().clone(),
+				},
+			],
+			vec![MigrationOp::Custom("backfill authors".into())],
+		]
+	);
+	assert_eq!(Version::plan(2).unwrap().len(), 1);
+}
@@ -0,0 +1,166 @@
+use database::{decode_ordered, encode_ordered, ordered_prefix, BinaryKey, DecodeOrdered, Descending, KeyRange};
+use std::{cmp::Ordering, fmt::Debug};
+
+/// A small deterministic generator, so failures can be reproduced.
+struct Rng(u64);
+
+impl Rng {
+	fn next(&mut self) -> u64 {
+		self.0 ^= self.0 << 13;
+		self.0 ^= self.0 >> 7;
+		self.0 ^= self.0 << 17;
+		self.0
+	}
+
+	fn bytes(&mut self) -> Vec<u8> {
+		let len = self.next() % 6;
+		// mostly small bytes, so escaped zeros and shared prefixes are common
+		(0..len)
+			.map(|_| [0, 1, 0xFF, self.next() as u8][self.next() as usize % 4])
+			.collect()
+	}
+}
+
+/// Checks that every value decodes to itself, and that the encodings of every pair sort like the values.
+fn check<K>(values: &[K], cmp: impl Fn(&K, &K) -> Ordering)
+where
+	K: DecodeOrdered + Debug + PartialEq,
+{
+	for value in values {
+		let encoded = encode_ordered(value);
+		assert_eq!(decode_ordered::<K>(encoded.as_bytes()).as_ref(), Ok(value), "{value:?}");
+	}
+	for a in values {
+		for b in values {
+			let encoded = encode_ordered(a).as_bytes().cmp(encode_ordered(b).as_bytes());
+			assert_eq!(encoded, cmp(a, b), "{a:?} and {b:?}");
+		}
+	}
+}
+
+#[test]
+fn unsigned_integers() {
+	let mut rng = Rng(1);
+	let mut values = vec![0, 1, u32::MAX - 1, u32::MAX];
+	values.extend((0..64).map(|_| rng.next() as u32));
+	check(&values, u32::cmp);
+}
+
+#[test]
+fn signed_integers() {
+	let mut rng = Rng(2);
+	let mut values = vec![i64::MIN, i64::MIN + 1, -1, 0, 1, i64::MAX];
+	values.extend((0..64).map(|_| rng.next() as i64));
+	check(&values, i64::cmp);
+
+	let values = (i8::MIN..=i8::MAX).collect::<Vec<_>>();
+	check(&values, i8::cmp);
+}
+
+#[test]
+fn floats() {
+	let mut rng = Rng(3);
+	let mut values = vec![
+		f64::NEG_INFINITY,
+		f64::MIN,
+		-1.5,
+		-f64::MIN_POSITIVE,
+		-0.0,
+		0.0,
+		f64::MIN_POSITIVE,
+		1.5,
+		f64::MAX,
+		f64::INFINITY,
+	];
+	values.extend(
+		(0..64)
+			.map(|_| f64::from_bits(rng.next()))
+			.filter(|value| !value.is_nan()),
+	);
+	check(&values, f64::total_cmp);
+}
+
+#[test]
+fn strings_sort_by_prefix_and_escape_zeros() {
+	let mut rng = Rng(4);
+	let mut values = [
+		"", "\0", "\0\0", "\u{1}", "a", "a\0", "a\0b", "a\u{1}", "ab", "b", "\u{ff}",
+	]
+	.map(String::from)
+	.to_vec();
+	values.extend((0..48).filter_map(|_| String::from_utf8(rng.bytes()).ok()));
+	check(&values, String::cmp);
+}
+
+#[test]
+fn bytes() {
+	let mut rng = Rng(5);
+	let values = (0..64).map(|_| BinaryKey(rng.bytes())).collect::<Vec<_>>();
+	check(&values, BinaryKey::cmp);
+}
+
+#[test]
+fn tuples_sort_field_by_field() {
+	let mut rng = Rng(6);
+	let mut values = Vec::new();
+	for _ in 0..64 {
+		let name = String::from_utf8_lossy(&rng.bytes()).into_owned();
+		values.push((name, rng.next() as i16, rng.next().is_multiple_of(2)));
+	}
+	check(&values, |a, b| a.cmp(b));
+}
+
+#[test]
+fn descending_flips_the_order() {
+	let mut rng = Rng(7);
+	let values = (0..48)
+		.map(|_| Descending(String::from_utf8_lossy(&rng.bytes()).into_owned()))
+		.collect::<Vec<_>>();
+	check(&values, Descending::cmp);
+
+	let values = (0..48)
+		.map(|_| (rng.next() as u8 % 4, Descending(rng.next() as i32), rng.next() as u8))
+		.collect::<Vec<_>>();
+	check(&values, |a, b| a.cmp(b));
+}
+
+#[test]
+fn decoding_rejects_malformed_keys() {
+	assert!(decode_ordered::<u32>(&[0, 0, 1]).is_err());
+	assert!(decode_ordered::<u8>(&[0, 0]).is_err());
+	assert!(decode_ordered::<bool>(&[2]).is_err());
+	assert!(decode_ordered::<String>(b"abc").is_err());
+	assert!(decode_ordered::<String>(&[b'a', 0, 7]).is_err());
+	assert!(decode_ordered::<String>(&[0xC0, 0, 1]).is_err());
+}
+
+#[test]
+fn prefix_range_covers_keys_starting_with_the_prefix() {
+	let range = ordered_prefix(&(7u32,));
+	assert_eq!(
+		range,
+		KeyRange::bound(BinaryKey(vec![0, 0, 0, 7]), BinaryKey(vec![0, 0, 0, 8])).upper_open()
+	);
+
+	let lower = encode_ordered(&(7u32,));
+	let upper = encode_ordered(&(8u32,));
+	let mut rng = Rng(8);
+	for _ in 0..64 {
+		let name = String::from_utf8_lossy(&rng.bytes()).into_owned();
+		let key = encode_ordered(&(7u32, name));
+		assert!(lower <= key && key < upper, "{key:?}");
+	}
+}
+
+#[test]
+fn prefix_range_of_maximal_bytes_has_no_upper_bound() {
+	assert_eq!(
+		ordered_prefix(&(u16::MAX,)),
+		KeyRange::lower_bound(BinaryKey(vec![0xFF, 0xFF]))
+	);
+	// trailing 0xFF bytes are dropped before incrementing
+	assert_eq!(
+		ordered_prefix(&(0x01FFu16,)),
+		KeyRange::bound(BinaryKey(vec![0x01, 0xFF]), BinaryKey(vec![0x02])).upper_open()
+	);
+}
@@ -0,0 +1,33 @@
+use database::RetryPolicy;
+use std::time::Duration;
+
+#[test]
+fn delay_doubles_after_each_retry() {
+	let policy = RetryPolicy::default().with_backoff(Duration::from_millis(10));
+	let delays = (0..4).map(|retry| policy.delay(retry)).collect::<Vec<_>>();
+	assert_eq!(delays, [10, 20, 40, 80].map(Duration::from_millis));
+}
+
+#[test]
+fn delay_is_capped_at_the_max_backoff() {
+	let policy = RetryPolicy {
+		attempts: 10,
+		backoff: Duration::from_millis(500),
+		max_backoff: Duration::from_secs(1),
+	};
+	assert_eq!(policy.delay(1), Duration::from_secs(1));
+	assert_eq!(policy.delay(2), Duration::from_secs(1));
+}
+
+#[test]
+fn delay_saturates_instead_of_overflowing() {
+	let policy = RetryPolicy::default();
+	assert_eq!(policy.delay(40), policy.max_backoff);
+	assert_eq!(policy.delay(u32::MAX), policy.max_backoff);
+}
+
+#[test]
+fn none_attempts_once() {
+	assert_eq!(RetryPolicy::none().attempts, 1);
+	assert_eq!(RetryPolicy::none().with_attempts(5).attempts, 5);
+}
@@ -0,0 +1,22 @@
+use database::StorageEstimate;
+
+#[test]
+fn available_is_what_remains_of_the_quota() {
+	let estimate = StorageEstimate { usage: 30, quota: 100 };
+	assert_eq!(estimate.available(), 70);
+	assert_eq!(estimate.fraction_used(), 0.3);
+}
+
+#[test]
+fn usage_over_the_quota_leaves_nothing_available() {
+	let estimate = StorageEstimate { usage: 120, quota: 100 };
+	assert_eq!(estimate.available(), 0);
+	assert_eq!(estimate.fraction_used(), 1.2);
+}
+
+#[test]
+fn a_zero_quota_is_fully_used() {
+	let estimate = StorageEstimate { usage: 0, quota: 0 };
+	assert_eq!(estimate.available(), 0);
+	assert_eq!(estimate.fraction_used(), 1.0);
+}
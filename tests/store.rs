@@ -0,0 +1,295 @@
+#![cfg(target_arch = "wasm32")]
+
+mod common;
+
+use common::{open_items, Item, BLOBS, ITEMS};
+use database::{
+	Compression, EncryptionKey, Error, Expiring, IndexType, KeyRange, SerializerOptions, SoftDeleted, StoreDescriptor,
+	Timestamp, Upgrades,
+};
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+async fn entry_rejects_a_default_with_another_key() {
+	let client = open_items("store-entry-key-mismatch", 0).await;
+	let items = client.store(&ITEMS);
+	let result = items.entry(&7u32).or_insert_with(|| Item { id: 8, value: 0 }).await;
+	assert!(matches!(result, Err(Error::KeyMismatch { .. })), "{result:?}");
+	assert_eq!(items.count().await.unwrap(), 0);
+
+	let inserted = items
+		.entry(&7u32)
+		.or_insert_with(|| Item { id: 7, value: 1 })
+		.await
+		.unwrap();
+	assert_eq!(inserted, Item { id: 7, value: 1 });
+}
+
+#[wasm_bindgen_test]
+async fn index_looks_records_up_by_name() {
+	let client = open_items("store-index-by-name", 4).await;
+	let items = client.store(&ITEMS);
+	items.put(&Item { id: 9, value: 2 }).await.unwrap();
+	let by_value = items.index("value");
+	assert_eq!(by_value.get(&3u32).await.unwrap(), Some(Item { id: 3, value: 3 }));
+	assert_eq!(by_value.get(&7u32).await.unwrap(), None);
+	let twos = by_value.get_all(&2u32).await.unwrap();
+	assert_eq!(twos, [Item { id: 2, value: 2 }, Item { id: 9, value: 2 }]);
+	let range = KeyRange::bound(1u32, 2u32);
+	assert_eq!(by_value.count_in(Some(&range)).await.unwrap(), 3);
+	assert_eq!(by_value.get_all_in(Some(&range), Some(2)).await.unwrap().len(), 2);
+}
+
+struct ItemsByValue(u32);
+
+impl IndexType for ItemsByValue {
+	type Record = Item;
+
+	fn name() -> &'static str {
+		"value"
+	}
+
+	fn keys() -> &'static [&'static str] {
+		&["value"]
+	}
+
+	fn as_query(&self) -> Result<idb::Query, idb::Error> {
+		Ok(idb::Query::Key(self.0.into()))
+	}
+}
+
+#[wasm_bindgen_test]
+async fn typed_index_counts_records_in_a_range() {
+	let client = open_items("store-index-count-in", 5).await;
+	let items = client.store(&ITEMS);
+	// each handle is opened in its own transaction, which finishes once its lookup is done
+	let in_range = items.index_of::<ItemsByValue>().unwrap();
+	assert_eq!(in_range.count_in(Some(&KeyRange::bound(1u32, 3u32))).await.unwrap(), 3);
+	let all = items.index_of::<ItemsByValue>().unwrap();
+	assert_eq!(all.count_in(None::<&KeyRange<u32>>).await.unwrap(), 5);
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Doc {
+	title: String,
+	body: String,
+}
+
+impl Doc {
+	fn new(title: &str, body: &str) -> Self {
+		Self {
+			title: title.into(),
+			body: body.into(),
+		}
+	}
+}
+
+static DOCS: StoreDescriptor<Doc> = StoreDescriptor::new(BLOBS);
+
+#[wasm_bindgen_test]
+async fn compressed_records_read_back_alongside_unmarked_ones() {
+	let client = open_items("store-compressed", 0).await;
+	let docs = client.store(&DOCS);
+	let compressed = docs.compressed(Compression::Gzip);
+	compressed.put(&1u32, &Doc::new("one", &"a".repeat(200))).await.unwrap();
+	// written before the store was compressed, so it has no marker
+	docs.put_with_key(&Doc::new("two", "b"), &2u32).await.unwrap();
+
+	let raw = docs.get_raw(&1u32).await.unwrap().unwrap();
+	assert!(js_sys::Reflect::has(&raw, &"$compressed".into()).unwrap());
+	assert_eq!(
+		compressed.get(&1u32).await.unwrap(),
+		Some(Doc::new("one", &"a".repeat(200)))
+	);
+	assert_eq!(compressed.get(&2u32).await.unwrap(), Some(Doc::new("two", "b")));
+	assert_eq!(compressed.get(&3u32).await.unwrap(), None);
+	let streamed = compressed.stream(1).try_collect::<Vec<_>>().await.unwrap();
+	assert_eq!(streamed, compressed.get_all().await.unwrap());
+	assert_eq!(streamed.len(), 2);
+}
+
+#[wasm_bindgen_test]
+async fn encrypted_records_are_bound_to_their_key() {
+	let client = open_items("store-encrypted", 0).await;
+	let docs = client.store(&DOCS);
+	let encrypted = docs.encrypted(EncryptionKey::import(&[7; 16]).await.unwrap());
+	encrypted.put(&1u32, &Doc::new("secret", "s")).await.unwrap();
+	assert_eq!(encrypted.get(&1u32).await.unwrap(), Some(Doc::new("secret", "s")));
+
+	// the key is authenticated with the record, so a copy at another key is rejected
+	let raw = docs.get_raw(&1u32).await.unwrap().unwrap();
+	docs.put_raw_with_key(&raw, &2u32).await.unwrap();
+	let moved = encrypted.get(&2u32).await;
+	assert!(matches!(moved, Err(Error::Serialization(_))), "{moved:?}");
+
+	let other = docs.encrypted(EncryptionKey::import(&[8; 16]).await.unwrap());
+	let wrong_key = other.get(&1u32).await;
+	assert!(matches!(wrong_key, Err(Error::Serialization(_))), "{wrong_key:?}");
+
+	docs.put_with_key(&Doc::new("plain", "p"), &3u32).await.unwrap();
+	let unencrypted = encrypted.get(&3u32).await;
+	assert!(matches!(unencrypted, Err(Error::Serialization(_))), "{unencrypted:?}");
+}
+
+#[wasm_bindgen_test]
+async fn large_values_replace_and_delete_their_chunks() {
+	let client = open_items("store-large-values", 0).await;
+	let docs = client.store(&DOCS);
+	let large = docs.large_values(32);
+	let big = Doc::new("big", &"x".repeat(100));
+	large.put(&1u32, &big).await.unwrap();
+	let chunks = serde_json::to_vec(&big).unwrap().len().div_ceil(32) as u32;
+	assert_eq!(docs.count().await.unwrap(), 1 + chunks);
+	assert_eq!(large.get(&1u32).await.unwrap(), Some(big));
+
+	// a value within the threshold is stored inline, and the chunks of the previous value are removed
+	let small = Doc::new("a", "b");
+	large.put(&1u32, &small).await.unwrap();
+	assert_eq!(docs.count().await.unwrap(), 1);
+	assert_eq!(large.get(&1u32).await.unwrap(), Some(small));
+
+	docs.put_with_key(&Doc::new("plain", "p"), &2u32).await.unwrap();
+	assert_eq!(large.get(&2u32).await.unwrap(), Some(Doc::new("plain", "p")));
+
+	large.put(&1u32, &Doc::new("big", &"y".repeat(100))).await.unwrap();
+	large.delete(&1u32).await.unwrap();
+	assert_eq!(large.get(&1u32).await.unwrap(), None);
+	assert_eq!(docs.count().await.unwrap(), 1);
+}
+
+#[derive(Serialize, Deserialize)]
+struct OldDoc {
+	body: String,
+}
+
+static OLD_DOCS: StoreDescriptor<OldDoc> = StoreDescriptor::new(BLOBS);
+
+fn doc_upgrades() -> Upgrades<Doc> {
+	Upgrades::new(1).step(0, |mut data| {
+		data["title"] = "untitled".into();
+		Ok(data)
+	})
+}
+
+#[wasm_bindgen_test]
+async fn versioned_reads_write_upgraded_records_back() {
+	let client = open_items("store-versioned", 0).await;
+	let old = OldDoc { body: "old".into() };
+	client.store(&OLD_DOCS).put_with_key(&old, &1u32).await.unwrap();
+	client.store(&OLD_DOCS).put_with_key(&old, &2u32).await.unwrap();
+	let docs = client.store(&DOCS);
+	let versioned = docs.versioned(doc_upgrades());
+	versioned.put(&3u32, &Doc::new("new", "n")).await.unwrap();
+
+	assert_eq!(versioned.get(&1u32).await.unwrap(), Some(Doc::new("untitled", "old")));
+	let schema = |raw: wasm_bindgen::JsValue| js_sys::Reflect::get(&raw, &"schema".into()).unwrap().as_f64();
+	assert_eq!(schema(docs.get_raw(&1u32).await.unwrap().unwrap()), Some(1.0));
+	assert_eq!(schema(docs.get_raw(&2u32).await.unwrap().unwrap()), None);
+
+	let all = versioned.get_all().await.unwrap();
+	assert_eq!(
+		all,
+		[
+			Doc::new("untitled", "old"),
+			Doc::new("untitled", "old"),
+			Doc::new("new", "n")
+		]
+	);
+	assert_eq!(schema(docs.get_raw(&2u32).await.unwrap().unwrap()), Some(1.0));
+	// the upgraded record reads back unchanged once it was written back
+	assert_eq!(versioned.get(&2u32).await.unwrap(), Some(Doc::new("untitled", "old")));
+}
+
+#[wasm_bindgen_test]
+async fn recode_converts_only_records_in_the_source_format() {
+	let client = open_items("store-recode", 0).await;
+	let docs = client.store(&DOCS);
+	for id in 0..3u32 {
+		docs.put_with_key(&Doc::new("plain", &id.to_string()), &id)
+			.await
+			.unwrap();
+	}
+	let compressed = docs.compressed(Compression::Gzip);
+	compressed.put(&3u32, &Doc::new("compressed", "3")).await.unwrap();
+
+	assert_eq!(
+		docs.recode(&SerializerOptions::new(), &Compression::Gzip, 2)
+			.await
+			.unwrap(),
+		3
+	);
+	for id in 0..3u32 {
+		let raw = docs.get_raw(&id).await.unwrap().unwrap();
+		assert!(js_sys::Reflect::has(&raw, &"$compressed".into()).unwrap());
+		assert_eq!(
+			compressed.get(&id).await.unwrap(),
+			Some(Doc::new("plain", &id.to_string()))
+		);
+	}
+	// every record is already compressed, so running it again has nothing to do
+	assert_eq!(
+		docs.recode(&SerializerOptions::new(), &Compression::Gzip, 2)
+			.await
+			.unwrap(),
+		0
+	);
+}
+
+static EXPIRING: StoreDescriptor<Expiring<String>> = StoreDescriptor::new(BLOBS);
+
+#[wasm_bindgen_test]
+async fn expired_records_are_hidden_until_purged() {
+	let client = open_items("store-expiring", 0).await;
+	let store = client.store(&EXPIRING);
+	store
+		.put_with_ttl(&1u32, "stale".to_owned(), Duration::ZERO)
+		.await
+		.unwrap();
+	let fresh = store
+		.put_with_ttl(&2u32, "fresh".to_owned(), Duration::from_secs(3600))
+		.await
+		.unwrap();
+	let forever = Expiring {
+		expires_at: None,
+		data: "forever".to_owned(),
+	};
+	store.put_with_key(&forever, &3u32).await.unwrap();
+
+	assert_eq!(store.get_live(&1u32).await.unwrap(), None);
+	assert_eq!(store.get_live(&2u32).await.unwrap(), Some(fresh.clone()));
+	assert_eq!(store.get_all_live().await.unwrap(), [fresh, forever]);
+	assert_eq!(store.count().await.unwrap(), 3);
+	assert_eq!(store.purge_expired().await.unwrap(), 1);
+	assert_eq!(store.count().await.unwrap(), 2);
+}
+
+static TASKS: StoreDescriptor<SoftDeleted<String>> = StoreDescriptor::new(BLOBS);
+
+#[wasm_bindgen_test]
+async fn soft_deletes_leave_tombstones_until_compacted() {
+	let client = open_items("store-soft-delete", 0).await;
+	let tasks = client.store(&TASKS).with_tombstones();
+	tasks.put_with_key("write".to_owned(), &1u32).await.unwrap();
+	tasks.put_with_key("review".to_owned(), &2u32).await.unwrap();
+
+	assert!(tasks.delete(&1u32).await.unwrap());
+	assert!(!tasks.delete(&1u32).await.unwrap());
+	assert!(!tasks.delete(&3u32).await.unwrap());
+	assert_eq!(tasks.get(&1u32).await.unwrap(), None);
+	assert_eq!(tasks.get_all().await.unwrap(), ["review".to_owned()]);
+
+	let deleted = tasks.deleted_since(Timestamp::from_millis(0.0)).await.unwrap();
+	assert_eq!(
+		deleted.iter().map(|task| task.data.as_str()).collect::<Vec<_>>(),
+		["write"]
+	);
+	assert_eq!(tasks.store().count().await.unwrap(), 2);
+	let later = Timestamp::from_millis(Timestamp::now().as_millis() + 1000.0);
+	assert_eq!(tasks.compact_tombstones(later).await.unwrap(), 1);
+	assert_eq!(tasks.store().count().await.unwrap(), 1);
+}
@@ -0,0 +1,21 @@
+#![cfg(feature = "time")]
+use database::Timestamp;
+use time::OffsetDateTime;
+
+fn millis(nanos: i128) -> f64 {
+	Timestamp::from(OffsetDateTime::from_unix_timestamp_nanos(nanos).unwrap()).as_millis()
+}
+
+#[test]
+fn sub_millisecond_precision_rounds_towards_the_past() {
+	assert_eq!(millis(1_500_000), 1.0);
+	assert_eq!(millis(-1), -1.0);
+	assert_eq!(millis(-1_500_000), -2.0);
+	assert_eq!(millis(-2_000_000), -2.0);
+}
+
+#[test]
+fn whole_milliseconds_round_trip() {
+	let date_time = OffsetDateTime::from_unix_timestamp_nanos(-86_400_123_000_000).unwrap();
+	assert_eq!(OffsetDateTime::try_from(Timestamp::from(date_time)).unwrap(), date_time);
+}
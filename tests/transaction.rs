@@ -0,0 +1,49 @@
+#![cfg(target_arch = "wasm32")]
+
+mod common;
+
+use common::{open_items, Item, ITEMS};
+use database::Error;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+async fn with_transaction_fails_when_a_request_aborted_it() {
+	let client = open_items("transaction-abort-after-error", 1).await;
+	let result = client
+		.with_write_transaction(&[&ITEMS], |transaction| async move {
+			let items = transaction.store(&ITEMS)?;
+			items.put(&Item { id: 1, value: 1 }).await?;
+			// the failed request aborts the transaction, even though its error is ignored here
+			assert!(items.add(&Item { id: 0, value: 0 }).await.is_err());
+			Ok(())
+		})
+		.await;
+	assert!(matches!(result, Err(Error::TransactionAborted(_))), "{result:?}");
+	assert_eq!(client.store(&ITEMS).get(&1u32).await.unwrap(), None);
+}
+
+#[wasm_bindgen_test]
+async fn with_transaction_fails_when_the_scope_aborts() {
+	let client = open_items("transaction-explicit-abort", 1).await;
+	let result = client
+		.with_write_transaction(&[&ITEMS], |transaction| async move {
+			transaction.store(&ITEMS)?.put(&Item { id: 1, value: 1 }).await?;
+			transaction.abort()?;
+			Ok(())
+		})
+		.await;
+	assert!(matches!(result, Err(Error::TransactionAborted(_))), "{result:?}");
+	assert_eq!(client.store(&ITEMS).get(&1u32).await.unwrap(), None);
+}
+
+#[wasm_bindgen_test]
+async fn commit_fails_when_a_request_aborted_the_transaction() {
+	let client = open_items("transaction-commit-after-error", 1).await;
+	let transaction = client.write_transaction(&[&ITEMS]).unwrap();
+	let items = transaction.store(&ITEMS).unwrap();
+	assert!(items.add(&Item { id: 0, value: 0 }).await.is_err());
+	let result = transaction.commit().await;
+	assert!(matches!(result, Err(Error::TransactionAborted(_))), "{result:?}");
+}